@@ -6,28 +6,332 @@ bl_result_t bl_greycat_abi__read_fn_param(bl_slice_t *b, fn_param_t *value) {
   BL_TRY(bl_slice__read_vu32(b, &value->name));
   return bl_result_ok;
 }
+void bl_greycat_abi__free_fn_param(fn_param_t *value) {
+  (void)value;
+}
+void bl_greycat_abi__print_fn_param(FILE *out, const fn_param_t *value, int indent) {
+  fprintf(out, "FnParam {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "nullable: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->nullable);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "name: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->name);
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_fn_param(bl_json_t *json, const fn_param_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "nullable");
+  bl_json__uint(json, value->nullable);
+  bl_json__key(json, "type");
+  bl_json__uint(json, value->type);
+  bl_json__key(json, "name");
+  bl_json__uint(json, value->name);
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_fn_param(const fn_param_t *a, const fn_param_t *b) {
+  if (a->nullable != b->nullable) {
+    return false;
+  }
+  if (a->type != b->type) {
+    return false;
+  }
+  if (a->name != b->name) {
+    return false;
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_fn_param(fn_param_t *dst, const fn_param_t *src) {
+  *dst = *src;
+  return bl_result_ok;
+}
+uint64_t bl_greycat_abi__hash_fn_param(const fn_param_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->nullable);
+  hash = bl_hash__u64(hash, (uint64_t)value->type);
+  hash = bl_hash__u64(hash, (uint64_t)value->name);
+  return hash;
+}
+static bl_unused void bl_greycat_abi__print_function_flags(FILE *out, function_flags_t value) {
+  fprintf(out, "0x%02x (", value);
+  const char *sep = "";
+  if (value & FUNCTION_FLAGS_RETURN_NULLABLE) {
+    fprintf(out, "%sreturn_nullable", sep);
+    sep = " | ";
+  }
+  fprintf(out, ")\n");
+}
+static bl_unused void bl_greycat_abi__to_json_function_flags(bl_json_t *json, function_flags_t value) {
+  static const char *const names[8] = {"return_nullable", NULL, NULL, NULL, NULL, NULL, NULL, NULL};
+  bl_json__flags(json, value, names);
+}
 bl_result_t bl_greycat_abi__read_function(bl_slice_t *b, function_t *value) {
-  BL_TRY(bl_slice__read_vu32(b, &value->module));
-  BL_TRY(bl_slice__read_vu32(b, &value->type));
-  BL_TRY(bl_slice__read_vu32(b, &value->name));
-  BL_TRY(bl_slice__read_vu32(b, &value->lib));
-  BL_TRY(bl_slice__read_vu32(b, &value->params.size));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->module), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->type), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->name), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->lib), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->params.size), res, fail);
+  BL_TRY_GOTO(bl_slice__check_count(b, value->params.size, 3, sizeof *value->params.elems), res, fail);
   array_reserve(&value->params, value->params.size);
   for (uint32_t i = 0; i < value->params.size; i++) {
-    BL_TRY(bl_greycat_abi__read_fn_param(b, value->params.elems + i));
+    res = bl_greycat_abi__read_fn_param(b, value->params.elems + i);
+    if (res <= 0) {
+      value->params.size = i;
+      goto fail;
+    }
   }
-  BL_TRY(bl_slice__read_vu32(b, &value->return_type));
-  BL_TRY(bl_slice__read_u8(b, &value->flags));
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->return_type), res, fail);
+  BL_TRY_GOTO(bl_slice__read_u8(b, &value->flags), res, fail);
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_function(value);
+  return res;
+}
+void bl_greycat_abi__free_function(function_t *value) {
+  array_delete(&value->params);
+}
+void bl_greycat_abi__print_function(FILE *out, const function_t *value, int indent) {
+  fprintf(out, "Function {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "module: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->module);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "name: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->name);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "lib: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->lib);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "params: [\n");
+  for (uint32_t i = 0; i < value->params.size; i++) {
+    bl_print__indent(out, indent + 2);
+    bl_greycat_abi__print_fn_param(out, &value->params.elems[i], indent + 2);
+  }
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "]\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "return_type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->return_type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "flags: ");
+  bl_greycat_abi__print_function_flags(out, value->flags);
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_function(bl_json_t *json, const function_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "module");
+  bl_json__uint(json, value->module);
+  bl_json__key(json, "type");
+  bl_json__uint(json, value->type);
+  bl_json__key(json, "name");
+  bl_json__uint(json, value->name);
+  bl_json__key(json, "lib");
+  bl_json__uint(json, value->lib);
+  bl_json__key(json, "arity");
+  bl_json__uint(json, value->params.size);
+  bl_json__key(json, "params");
+  bl_json__begin(json, '[');
+  for (uint32_t i = 0; i < value->params.size; i++) {
+    bl_json__item(json);
+    bl_greycat_abi__to_json_fn_param(json, &value->params.elems[i]);
+  }
+  bl_json__end(json, ']');
+  bl_json__key(json, "return_type");
+  bl_json__uint(json, value->return_type);
+  bl_json__key(json, "flags");
+  bl_greycat_abi__to_json_function_flags(json, value->flags);
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_function(const function_t *a, const function_t *b) {
+  if (a->module != b->module) {
+    return false;
+  }
+  if (a->type != b->type) {
+    return false;
+  }
+  if (a->name != b->name) {
+    return false;
+  }
+  if (a->lib != b->lib) {
+    return false;
+  }
+  if (a->params.size != b->params.size) {
+    return false;
+  }
+  for (uint32_t i = 0; i < a->params.size; i++) {
+    if (!bl_greycat_abi__eq_fn_param(a->params.elems + i, b->params.elems + i)) {
+      return false;
+    }
+  }
+  if (a->return_type != b->return_type) {
+    return false;
+  }
+  if (a->flags != b->flags) {
+    return false;
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_function(function_t *dst, const function_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  dst->module = src->module;
+  dst->type = src->type;
+  dst->name = src->name;
+  dst->lib = src->lib;
+  array_reserve(&dst->params, src->params.size);
+  BL_TRY_GOTO(BL_RESERVED(&dst->params), res, fail);
+  if (src->params.size > 0) {
+    memcpy(dst->params.elems, src->params.elems, src->params.size * sizeof *src->params.elems);
+  }
+  dst->return_type = src->return_type;
+  dst->flags = src->flags;
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_function(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_function(const function_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->module);
+  hash = bl_hash__u64(hash, (uint64_t)value->type);
+  hash = bl_hash__u64(hash, (uint64_t)value->name);
+  hash = bl_hash__u64(hash, (uint64_t)value->lib);
+  hash = bl_hash__u64(hash, value->params.size);
+  for (uint32_t i = 0; i < value->params.size; i++) {
+    hash = bl_hash__u64(hash, bl_greycat_abi__hash_fn_param(&value->params.elems[i]));
+  }
+  hash = bl_hash__u64(hash, (uint64_t)value->return_type);
+  hash = bl_hash__u64(hash, (uint64_t)value->flags);
+  return hash;
 }
 bl_result_t bl_greycat_abi__read_functions(bl_slice_t *b, functions_t *value) {
-  BL_TRY(bl_slice__read_u64(b, &value->byte_size));
-  BL_TRY(bl_slice__read_u32(b, &value->functions.size));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_slice__read_u64(b, &value->byte_size), res, fail);
+  BL_TRY_GOTO(bl_slice__read_u32(b, &value->functions.size), res, fail);
+  BL_TRY_GOTO(bl_slice__check_count(b, value->functions.size, 7, sizeof *value->functions.elems), res, fail);
   array_reserve(&value->functions, value->functions.size);
   for (uint32_t i = 0; i < value->functions.size; i++) {
-    BL_TRY(bl_greycat_abi__read_function(b, value->functions.elems + i));
+    res = bl_greycat_abi__read_function(b, value->functions.elems + i);
+    if (res <= 0) {
+      value->functions.size = i;
+      goto fail;
+    }
   }
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_functions(value);
+  return res;
+}
+void bl_greycat_abi__free_functions(functions_t *value) {
+  if (value->functions.elems != NULL) {
+    for (uint32_t i = 0; i < value->functions.size; i++) {
+      bl_greycat_abi__free_function(value->functions.elems + i);
+    }
+  }
+  array_delete(&value->functions);
+}
+void bl_greycat_abi__print_functions(FILE *out, const functions_t *value, int indent) {
+  fprintf(out, "Functions {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "byte_size: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->byte_size);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "functions: [\n");
+  for (uint32_t i = 0; i < value->functions.size; i++) {
+    bl_print__indent(out, indent + 2);
+    bl_greycat_abi__print_function(out, &value->functions.elems[i], indent + 2);
+  }
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "]\n");
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_functions(bl_json_t *json, const functions_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "byte_size");
+  bl_json__uint(json, value->byte_size);
+  bl_json__key(json, "nb_functions");
+  bl_json__uint(json, value->functions.size);
+  bl_json__key(json, "functions");
+  bl_json__begin(json, '[');
+  for (uint32_t i = 0; i < value->functions.size; i++) {
+    bl_json__item(json);
+    bl_greycat_abi__to_json_function(json, &value->functions.elems[i]);
+  }
+  bl_json__end(json, ']');
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_functions(const functions_t *a, const functions_t *b) {
+  if (a->byte_size != b->byte_size) {
+    return false;
+  }
+  if (a->functions.size != b->functions.size) {
+    return false;
+  }
+  for (uint32_t i = 0; i < a->functions.size; i++) {
+    if (!bl_greycat_abi__eq_function(a->functions.elems + i, b->functions.elems + i)) {
+      return false;
+    }
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_functions(functions_t *dst, const functions_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  dst->byte_size = src->byte_size;
+  array_reserve(&dst->functions, src->functions.size);
+  BL_TRY_GOTO(BL_RESERVED(&dst->functions), res, fail);
+  for (uint32_t i = 0; i < src->functions.size; i++) {
+    res = bl_greycat_abi__clone_function(dst->functions.elems + i, src->functions.elems + i);
+    if (res <= 0) {
+      dst->functions.size = i;
+      goto fail;
+    }
+  }
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_functions(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_functions(const functions_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->byte_size);
+  hash = bl_hash__u64(hash, value->functions.size);
+  for (uint32_t i = 0; i < value->functions.size; i++) {
+    hash = bl_hash__u64(hash, bl_greycat_abi__hash_function(&value->functions.elems[i]));
+  }
+  return hash;
+}
+static bl_unused void bl_greycat_abi__print_type_attr_flags(FILE *out, type_attr_flags_t value) {
+  fprintf(out, "0x%02x (", value);
+  const char *sep = "";
+  if (value & TYPE_ATTR_FLAGS_NULLABLE) {
+    fprintf(out, "%snullable", sep);
+    sep = " | ";
+  }
+  if (value & TYPE_ATTR_FLAGS_MAPPED) {
+    fprintf(out, "%smapped", sep);
+    sep = " | ";
+  }
+  fprintf(out, ")\n");
+}
+static bl_unused void bl_greycat_abi__to_json_type_attr_flags(bl_json_t *json, type_attr_flags_t value) {
+  static const char *const names[8] = {"nullable", "mapped", NULL, NULL, NULL, NULL, NULL, NULL};
+  bl_json__flags(json, value, names);
 }
 bl_result_t bl_greycat_abi__read_type_attr(bl_slice_t *b, type_attr_t *value) {
   BL_TRY(bl_slice__read_vu32(b, &value->name));
@@ -40,51 +344,623 @@ bl_result_t bl_greycat_abi__read_type_attr(bl_slice_t *b, type_attr_t *value) {
   BL_TRY(bl_slice__read_u8(b, &value->flags));
   return bl_result_ok;
 }
+void bl_greycat_abi__free_type_attr(type_attr_t *value) {
+  (void)value;
+}
+void bl_greycat_abi__print_type_attr(FILE *out, const type_attr_t *value, int indent) {
+  fprintf(out, "TypeAttr {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "name: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->name);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "abi_type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->abi_type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "prog_type_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->prog_type_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "mapped_any_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->mapped_any_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "mapped_att_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->mapped_att_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "sbi_type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->sbi_type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "precision: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->precision);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "flags: ");
+  bl_greycat_abi__print_type_attr_flags(out, value->flags);
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_type_attr(bl_json_t *json, const type_attr_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "name");
+  bl_json__uint(json, value->name);
+  bl_json__key(json, "abi_type");
+  bl_json__uint(json, value->abi_type);
+  bl_json__key(json, "prog_type_off");
+  bl_json__uint(json, value->prog_type_off);
+  bl_json__key(json, "mapped_any_off");
+  bl_json__uint(json, value->mapped_any_off);
+  bl_json__key(json, "mapped_att_off");
+  bl_json__uint(json, value->mapped_att_off);
+  bl_json__key(json, "sbi_type");
+  bl_json__uint(json, value->sbi_type);
+  bl_json__key(json, "precision");
+  bl_json__uint(json, value->precision);
+  bl_json__key(json, "flags");
+  bl_greycat_abi__to_json_type_attr_flags(json, value->flags);
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_type_attr(const type_attr_t *a, const type_attr_t *b) {
+  if (a->name != b->name) {
+    return false;
+  }
+  if (a->abi_type != b->abi_type) {
+    return false;
+  }
+  if (a->prog_type_off != b->prog_type_off) {
+    return false;
+  }
+  if (a->mapped_any_off != b->mapped_any_off) {
+    return false;
+  }
+  if (a->mapped_att_off != b->mapped_att_off) {
+    return false;
+  }
+  if (a->sbi_type != b->sbi_type) {
+    return false;
+  }
+  if (a->precision != b->precision) {
+    return false;
+  }
+  if (a->flags != b->flags) {
+    return false;
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_type_attr(type_attr_t *dst, const type_attr_t *src) {
+  *dst = *src;
+  return bl_result_ok;
+}
+uint64_t bl_greycat_abi__hash_type_attr(const type_attr_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->name);
+  hash = bl_hash__u64(hash, (uint64_t)value->abi_type);
+  hash = bl_hash__u64(hash, (uint64_t)value->prog_type_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->mapped_any_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->mapped_att_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->sbi_type);
+  hash = bl_hash__u64(hash, (uint64_t)value->precision);
+  hash = bl_hash__u64(hash, (uint64_t)value->flags);
+  return hash;
+}
+static bl_unused void bl_greycat_abi__print_type_flags(FILE *out, type_flags_t value) {
+  fprintf(out, "0x%02x (", value);
+  const char *sep = "";
+  if (value & TYPE_FLAGS_NATIVE) {
+    fprintf(out, "%snative", sep);
+    sep = " | ";
+  }
+  if (value & TYPE_FLAGS_ABSTRACT) {
+    fprintf(out, "%sabstract", sep);
+    sep = " | ";
+  }
+  if (value & TYPE_FLAGS_ENUM) {
+    fprintf(out, "%senum", sep);
+    sep = " | ";
+  }
+  if (value & TYPE_FLAGS_MASKED) {
+    fprintf(out, "%smasked", sep);
+    sep = " | ";
+  }
+  if (value & TYPE_FLAGS_AMBIGUOUS) {
+    fprintf(out, "%sambiguous", sep);
+    sep = " | ";
+  }
+  fprintf(out, ")\n");
+}
+static bl_unused void bl_greycat_abi__to_json_type_flags(bl_json_t *json, type_flags_t value) {
+  static const char *const names[8] = {"native", "abstract", "enum", "masked", "ambiguous", NULL, NULL, NULL};
+  bl_json__flags(json, value, names);
+}
 bl_result_t bl_greycat_abi__read_type(bl_slice_t *b, type_t *value) {
-  BL_TRY(bl_slice__read_vu32(b, &value->module));
-  BL_TRY(bl_slice__read_vu32(b, &value->name));
-  BL_TRY(bl_slice__read_vu32(b, &value->lib));
-  BL_TRY(bl_slice__read_vu32(b, &value->generic_abi_type));
-  BL_TRY(bl_slice__read_vu32(b, &value->g1));
-  BL_TRY(bl_slice__read_vu32(b, &value->g2));
-  BL_TRY(bl_slice__read_vu32(b, &value->super_type));
-  BL_TRY(bl_slice__read_vu32(b, &value->attrs.size));
-  BL_TRY(bl_slice__read_vu32(b, &value->attrs_off));
-  BL_TRY(bl_slice__read_vu32(b, &value->mapped_prog_type_off));
-  BL_TRY(bl_slice__read_vu32(b, &value->mapped_abi_type_off));
-  BL_TRY(bl_slice__read_vu32(b, &value->masked_abi_type_off));
-  BL_TRY(bl_slice__read_vu32(b, &value->nullable_nb_bytes));
-  BL_TRY(bl_slice__read_u8(b, &value->flags));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->module), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->name), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->lib), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->generic_abi_type), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->g1), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->g2), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->super_type), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->attrs.size), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->attrs_off), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->mapped_prog_type_off), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->mapped_abi_type_off), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->masked_abi_type_off), res, fail);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->nullable_nb_bytes), res, fail);
+  BL_TRY_GOTO(bl_slice__read_u8(b, &value->flags), res, fail);
+  BL_TRY_GOTO(bl_slice__check_count(b, value->attrs.size, 8, sizeof *value->attrs.elems), res, fail);
   array_reserve(&value->attrs, value->attrs.size);
   for (uint32_t i = 0; i < value->attrs.size; i++) {
-    BL_TRY(bl_greycat_abi__read_type_attr(b, value->attrs.elems + i));
+    res = bl_greycat_abi__read_type_attr(b, value->attrs.elems + i);
+    if (res <= 0) {
+      value->attrs.size = i;
+      goto fail;
+    }
+  }
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_type(value);
+  return res;
+}
+void bl_greycat_abi__free_type(type_t *value) {
+  array_delete(&value->attrs);
+}
+void bl_greycat_abi__print_type(FILE *out, const type_t *value, int indent) {
+  fprintf(out, "Type {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "module: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->module);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "name: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->name);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "lib: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->lib);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "generic_abi_type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->generic_abi_type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "g1: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->g1);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "g2: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->g2);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "super_type: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->super_type);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "attrs_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->attrs_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "mapped_prog_type_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->mapped_prog_type_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "mapped_abi_type_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->mapped_abi_type_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "masked_abi_type_off: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->masked_abi_type_off);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "nullable_nb_bytes: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->nullable_nb_bytes);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "flags: ");
+  bl_greycat_abi__print_type_flags(out, value->flags);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "attrs: [\n");
+  for (uint32_t i = 0; i < value->attrs.size; i++) {
+    bl_print__indent(out, indent + 2);
+    bl_greycat_abi__print_type_attr(out, &value->attrs.elems[i], indent + 2);
+  }
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "]\n");
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_type(bl_json_t *json, const type_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "module");
+  bl_json__uint(json, value->module);
+  bl_json__key(json, "name");
+  bl_json__uint(json, value->name);
+  bl_json__key(json, "lib");
+  bl_json__uint(json, value->lib);
+  bl_json__key(json, "generic_abi_type");
+  bl_json__uint(json, value->generic_abi_type);
+  bl_json__key(json, "g1");
+  bl_json__uint(json, value->g1);
+  bl_json__key(json, "g2");
+  bl_json__uint(json, value->g2);
+  bl_json__key(json, "super_type");
+  bl_json__uint(json, value->super_type);
+  bl_json__key(json, "nb_attrs");
+  bl_json__uint(json, value->attrs.size);
+  bl_json__key(json, "attrs_off");
+  bl_json__uint(json, value->attrs_off);
+  bl_json__key(json, "mapped_prog_type_off");
+  bl_json__uint(json, value->mapped_prog_type_off);
+  bl_json__key(json, "mapped_abi_type_off");
+  bl_json__uint(json, value->mapped_abi_type_off);
+  bl_json__key(json, "masked_abi_type_off");
+  bl_json__uint(json, value->masked_abi_type_off);
+  bl_json__key(json, "nullable_nb_bytes");
+  bl_json__uint(json, value->nullable_nb_bytes);
+  bl_json__key(json, "flags");
+  bl_greycat_abi__to_json_type_flags(json, value->flags);
+  bl_json__key(json, "attrs");
+  bl_json__begin(json, '[');
+  for (uint32_t i = 0; i < value->attrs.size; i++) {
+    bl_json__item(json);
+    bl_greycat_abi__to_json_type_attr(json, &value->attrs.elems[i]);
+  }
+  bl_json__end(json, ']');
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_type(const type_t *a, const type_t *b) {
+  if (a->module != b->module) {
+    return false;
+  }
+  if (a->name != b->name) {
+    return false;
+  }
+  if (a->lib != b->lib) {
+    return false;
+  }
+  if (a->generic_abi_type != b->generic_abi_type) {
+    return false;
+  }
+  if (a->g1 != b->g1) {
+    return false;
+  }
+  if (a->g2 != b->g2) {
+    return false;
+  }
+  if (a->super_type != b->super_type) {
+    return false;
+  }
+  if (a->attrs_off != b->attrs_off) {
+    return false;
+  }
+  if (a->mapped_prog_type_off != b->mapped_prog_type_off) {
+    return false;
+  }
+  if (a->mapped_abi_type_off != b->mapped_abi_type_off) {
+    return false;
+  }
+  if (a->masked_abi_type_off != b->masked_abi_type_off) {
+    return false;
+  }
+  if (a->nullable_nb_bytes != b->nullable_nb_bytes) {
+    return false;
+  }
+  if (a->flags != b->flags) {
+    return false;
+  }
+  if (a->attrs.size != b->attrs.size) {
+    return false;
+  }
+  for (uint32_t i = 0; i < a->attrs.size; i++) {
+    if (!bl_greycat_abi__eq_type_attr(a->attrs.elems + i, b->attrs.elems + i)) {
+      return false;
+    }
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_type(type_t *dst, const type_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  dst->module = src->module;
+  dst->name = src->name;
+  dst->lib = src->lib;
+  dst->generic_abi_type = src->generic_abi_type;
+  dst->g1 = src->g1;
+  dst->g2 = src->g2;
+  dst->super_type = src->super_type;
+  dst->attrs_off = src->attrs_off;
+  dst->mapped_prog_type_off = src->mapped_prog_type_off;
+  dst->mapped_abi_type_off = src->mapped_abi_type_off;
+  dst->masked_abi_type_off = src->masked_abi_type_off;
+  dst->nullable_nb_bytes = src->nullable_nb_bytes;
+  dst->flags = src->flags;
+  array_reserve(&dst->attrs, src->attrs.size);
+  BL_TRY_GOTO(BL_RESERVED(&dst->attrs), res, fail);
+  if (src->attrs.size > 0) {
+    memcpy(dst->attrs.elems, src->attrs.elems, src->attrs.size * sizeof *src->attrs.elems);
   }
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_type(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_type(const type_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->module);
+  hash = bl_hash__u64(hash, (uint64_t)value->name);
+  hash = bl_hash__u64(hash, (uint64_t)value->lib);
+  hash = bl_hash__u64(hash, (uint64_t)value->generic_abi_type);
+  hash = bl_hash__u64(hash, (uint64_t)value->g1);
+  hash = bl_hash__u64(hash, (uint64_t)value->g2);
+  hash = bl_hash__u64(hash, (uint64_t)value->super_type);
+  hash = bl_hash__u64(hash, (uint64_t)value->attrs_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->mapped_prog_type_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->mapped_abi_type_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->masked_abi_type_off);
+  hash = bl_hash__u64(hash, (uint64_t)value->nullable_nb_bytes);
+  hash = bl_hash__u64(hash, (uint64_t)value->flags);
+  hash = bl_hash__u64(hash, value->attrs.size);
+  for (uint32_t i = 0; i < value->attrs.size; i++) {
+    hash = bl_hash__u64(hash, bl_greycat_abi__hash_type_attr(&value->attrs.elems[i]));
+  }
+  return hash;
 }
 bl_result_t bl_greycat_abi__read_types(bl_slice_t *b, types_t *value) {
-  BL_TRY(bl_slice__read_u64(b, &value->byte_size));
-  BL_TRY(bl_slice__read_u32(b, &value->types.size));
-  BL_TRY(bl_slice__read_u32(b, &value->nb_attrs));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_slice__read_u64(b, &value->byte_size), res, fail);
+  BL_TRY_GOTO(bl_slice__read_u32(b, &value->types.size), res, fail);
+  BL_TRY_GOTO(bl_slice__read_u32(b, &value->nb_attrs), res, fail);
+  BL_TRY_GOTO(bl_slice__check_count(b, value->types.size, 14, sizeof *value->types.elems), res, fail);
   array_reserve(&value->types, value->types.size);
   for (uint32_t i = 0; i < value->types.size; i++) {
-    BL_TRY(bl_greycat_abi__read_type(b, value->types.elems + i));
+    res = bl_greycat_abi__read_type(b, value->types.elems + i);
+    if (res <= 0) {
+      value->types.size = i;
+      goto fail;
+    }
+  }
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_types(value);
+  return res;
+}
+void bl_greycat_abi__free_types(types_t *value) {
+  if (value->types.elems != NULL) {
+    for (uint32_t i = 0; i < value->types.size; i++) {
+      bl_greycat_abi__free_type(value->types.elems + i);
+    }
+  }
+  array_delete(&value->types);
+}
+void bl_greycat_abi__print_types(FILE *out, const types_t *value, int indent) {
+  fprintf(out, "Types {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "byte_size: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->byte_size);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "nb_attrs: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->nb_attrs);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "types: [\n");
+  for (uint32_t i = 0; i < value->types.size; i++) {
+    bl_print__indent(out, indent + 2);
+    bl_greycat_abi__print_type(out, &value->types.elems[i], indent + 2);
+  }
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "]\n");
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_types(bl_json_t *json, const types_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "byte_size");
+  bl_json__uint(json, value->byte_size);
+  bl_json__key(json, "nb_types");
+  bl_json__uint(json, value->types.size);
+  bl_json__key(json, "nb_attrs");
+  bl_json__uint(json, value->nb_attrs);
+  bl_json__key(json, "types");
+  bl_json__begin(json, '[');
+  for (uint32_t i = 0; i < value->types.size; i++) {
+    bl_json__item(json);
+    bl_greycat_abi__to_json_type(json, &value->types.elems[i]);
+  }
+  bl_json__end(json, ']');
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_types(const types_t *a, const types_t *b) {
+  if (a->byte_size != b->byte_size) {
+    return false;
+  }
+  if (a->nb_attrs != b->nb_attrs) {
+    return false;
+  }
+  if (a->types.size != b->types.size) {
+    return false;
+  }
+  for (uint32_t i = 0; i < a->types.size; i++) {
+    if (!bl_greycat_abi__eq_type(a->types.elems + i, b->types.elems + i)) {
+      return false;
+    }
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_types(types_t *dst, const types_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  dst->byte_size = src->byte_size;
+  dst->nb_attrs = src->nb_attrs;
+  array_reserve(&dst->types, src->types.size);
+  BL_TRY_GOTO(BL_RESERVED(&dst->types), res, fail);
+  for (uint32_t i = 0; i < src->types.size; i++) {
+    res = bl_greycat_abi__clone_type(dst->types.elems + i, src->types.elems + i);
+    if (res <= 0) {
+      dst->types.size = i;
+      goto fail;
+    }
   }
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_types(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_types(const types_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->byte_size);
+  hash = bl_hash__u64(hash, (uint64_t)value->nb_attrs);
+  hash = bl_hash__u64(hash, value->types.size);
+  for (uint32_t i = 0; i < value->types.size; i++) {
+    hash = bl_hash__u64(hash, bl_greycat_abi__hash_type(&value->types.elems[i]));
+  }
+  return hash;
 }
 bl_result_t bl_greycat_abi__read_symbol(bl_slice_t *b, symbol_t *value) {
-  BL_TRY(bl_slice__read_vu32(b, &value->text.size));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_slice__read_vu32(b, &value->text.size), res, fail);
+  BL_TRY_GOTO(bl_slice__check_count(b, value->text.size, 1, sizeof *value->text.elems), res, fail);
   array_reserve(&value->text, value->text.size);
-  BL_TRY(bl_slice__read_exact(b, value->text.elems, value->text.size));
+  BL_TRY_GOTO(bl_slice__read_exact(b, value->text.elems, value->text.size), res, fail);
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_symbol(value);
+  return res;
+}
+void bl_greycat_abi__free_symbol(symbol_t *value) {
+  array_delete(&value->text);
+}
+void bl_greycat_abi__print_symbol(FILE *out, const symbol_t *value, int indent) {
+  fprintf(out, "Symbol {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "text: ");
+  bl_print__bytes(out, value->text.elems, value->text.size);
+  fputc('\n', out);
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_symbol(bl_json_t *json, const symbol_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "size");
+  bl_json__uint(json, value->text.size);
+  bl_json__key(json, "text");
+  bl_json__bytes(json, value->text.elems, value->text.size);
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_symbol(const symbol_t *a, const symbol_t *b) {
+  if (a->text.size != b->text.size) {
+    return false;
+  }
+  if (a->text.size > 0 && memcmp(a->text.elems, b->text.elems, a->text.size * sizeof *a->text.elems) != 0) {
+    return false;
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_symbol(symbol_t *dst, const symbol_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  array_reserve(&dst->text, src->text.size);
+  BL_TRY_GOTO(BL_RESERVED(&dst->text), res, fail);
+  if (src->text.size > 0) {
+    memcpy(dst->text.elems, src->text.elems, src->text.size * sizeof *src->text.elems);
+  }
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_symbol(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_symbol(const symbol_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, value->text.size);
+  hash = bl_hash__bytes(hash, value->text.elems, value->text.size * sizeof *value->text.elems);
+  return hash;
 }
 bl_result_t bl_greycat_abi__read_symbols(bl_slice_t *b, symbols_t *value) {
-  BL_TRY(bl_slice__read_u64(b, &value->byte_size));
-  BL_TRY(bl_slice__read_u32(b, &value->symbols.size));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_slice__read_u64(b, &value->byte_size), res, fail);
+  BL_TRY_GOTO(bl_slice__read_u32(b, &value->symbols.size), res, fail);
+  BL_TRY_GOTO(bl_slice__check_count(b, value->symbols.size, 1, sizeof *value->symbols.elems), res, fail);
   array_reserve(&value->symbols, value->symbols.size);
   for (uint32_t i = 0; i < value->symbols.size; i++) {
-    BL_TRY(bl_greycat_abi__read_symbol(b, value->symbols.elems + i));
+    res = bl_greycat_abi__read_symbol(b, value->symbols.elems + i);
+    if (res <= 0) {
+      value->symbols.size = i;
+      goto fail;
+    }
   }
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_symbols(value);
+  return res;
+}
+void bl_greycat_abi__free_symbols(symbols_t *value) {
+  if (value->symbols.elems != NULL) {
+    for (uint32_t i = 0; i < value->symbols.size; i++) {
+      bl_greycat_abi__free_symbol(value->symbols.elems + i);
+    }
+  }
+  array_delete(&value->symbols);
+}
+void bl_greycat_abi__print_symbols(FILE *out, const symbols_t *value, int indent) {
+  fprintf(out, "Symbols {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "byte_size: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->byte_size);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "symbols: [\n");
+  for (uint32_t i = 0; i < value->symbols.size; i++) {
+    bl_print__indent(out, indent + 2);
+    bl_greycat_abi__print_symbol(out, &value->symbols.elems[i], indent + 2);
+  }
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "]\n");
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_symbols(bl_json_t *json, const symbols_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "byte_size");
+  bl_json__uint(json, value->byte_size);
+  bl_json__key(json, "symbols");
+  bl_json__begin(json, '[');
+  for (uint32_t i = 0; i < value->symbols.size; i++) {
+    bl_json__item(json);
+    bl_greycat_abi__to_json_symbol(json, &value->symbols.elems[i]);
+  }
+  bl_json__end(json, ']');
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_symbols(const symbols_t *a, const symbols_t *b) {
+  if (a->byte_size != b->byte_size) {
+    return false;
+  }
+  if (a->symbols.size != b->symbols.size) {
+    return false;
+  }
+  for (uint32_t i = 0; i < a->symbols.size; i++) {
+    if (!bl_greycat_abi__eq_symbol(a->symbols.elems + i, b->symbols.elems + i)) {
+      return false;
+    }
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_symbols(symbols_t *dst, const symbols_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  dst->byte_size = src->byte_size;
+  array_reserve(&dst->symbols, src->symbols.size);
+  BL_TRY_GOTO(BL_RESERVED(&dst->symbols), res, fail);
+  for (uint32_t i = 0; i < src->symbols.size; i++) {
+    res = bl_greycat_abi__clone_symbol(dst->symbols.elems + i, src->symbols.elems + i);
+    if (res <= 0) {
+      dst->symbols.size = i;
+      goto fail;
+    }
+  }
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_symbols(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_symbols(const symbols_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->byte_size);
+  hash = bl_hash__u64(hash, value->symbols.size);
+  for (uint32_t i = 0; i < value->symbols.size; i++) {
+    hash = bl_hash__u64(hash, bl_greycat_abi__hash_symbol(&value->symbols.elems[i]));
+  }
+  return hash;
 }
 bl_result_t bl_greycat_abi__read_headers(bl_slice_t *b, headers_t *value) {
   BL_TRY(bl_slice__read_u16(b, &value->major));
@@ -93,10 +969,145 @@ bl_result_t bl_greycat_abi__read_headers(bl_slice_t *b, headers_t *value) {
   BL_TRY(bl_slice__read_u64(b, &value->crc));
   return bl_result_ok;
 }
+void bl_greycat_abi__free_headers(headers_t *value) {
+  (void)value;
+}
+void bl_greycat_abi__print_headers(FILE *out, const headers_t *value, int indent) {
+  fprintf(out, "Headers {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "major: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->major);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "magic: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->magic);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "version: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->version);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "crc: ");
+  fprintf(out, "%llu\n", (unsigned long long)value->crc);
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_headers(bl_json_t *json, const headers_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "major");
+  bl_json__uint(json, value->major);
+  bl_json__key(json, "magic");
+  bl_json__uint(json, value->magic);
+  bl_json__key(json, "version");
+  bl_json__uint(json, value->version);
+  bl_json__key(json, "crc");
+  bl_json__uint(json, value->crc);
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_headers(const headers_t *a, const headers_t *b) {
+  if (a->major != b->major) {
+    return false;
+  }
+  if (a->magic != b->magic) {
+    return false;
+  }
+  if (a->version != b->version) {
+    return false;
+  }
+  if (a->crc != b->crc) {
+    return false;
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_headers(headers_t *dst, const headers_t *src) {
+  *dst = *src;
+  return bl_result_ok;
+}
+uint64_t bl_greycat_abi__hash_headers(const headers_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, (uint64_t)value->major);
+  hash = bl_hash__u64(hash, (uint64_t)value->magic);
+  hash = bl_hash__u64(hash, (uint64_t)value->version);
+  hash = bl_hash__u64(hash, (uint64_t)value->crc);
+  return hash;
+}
 bl_result_t bl_greycat_abi__read_abi(bl_slice_t *b, abi_t *value) {
-  BL_TRY(bl_greycat_abi__read_headers(b, &value->headers));
-  BL_TRY(bl_greycat_abi__read_symbols(b, &value->symbols));
-  BL_TRY(bl_greycat_abi__read_types(b, &value->types));
-  BL_TRY(bl_greycat_abi__read_functions(b, &value->functions));
+  bl_result_t res;
+  memset(value, 0, sizeof *value);
+  BL_TRY_GOTO(bl_greycat_abi__read_headers(b, &value->headers), res, fail);
+  BL_TRY_GOTO(bl_greycat_abi__read_symbols(b, &value->symbols), res, fail);
+  BL_TRY_GOTO(bl_greycat_abi__read_types(b, &value->types), res, fail);
+  BL_TRY_GOTO(bl_greycat_abi__read_functions(b, &value->functions), res, fail);
   return bl_result_ok;
+fail:
+  bl_greycat_abi__free_abi(value);
+  return res;
+}
+void bl_greycat_abi__free_abi(abi_t *value) {
+  bl_greycat_abi__free_functions(&value->functions);
+  bl_greycat_abi__free_types(&value->types);
+  bl_greycat_abi__free_symbols(&value->symbols);
+}
+void bl_greycat_abi__print_abi(FILE *out, const abi_t *value, int indent) {
+  fprintf(out, "Abi {\n");
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "headers: ");
+  bl_greycat_abi__print_headers(out, &value->headers, indent + 1);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "symbols: ");
+  bl_greycat_abi__print_symbols(out, &value->symbols, indent + 1);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "types: ");
+  bl_greycat_abi__print_types(out, &value->types, indent + 1);
+  bl_print__indent(out, indent + 1);
+  fprintf(out, "functions: ");
+  bl_greycat_abi__print_functions(out, &value->functions, indent + 1);
+  bl_print__indent(out, indent);
+  fprintf(out, "}\n");
+}
+bl_result_t bl_greycat_abi__to_json_abi(bl_json_t *json, const abi_t *value) {
+  bl_json__begin(json, '{');
+  bl_json__key(json, "headers");
+  bl_greycat_abi__to_json_headers(json, &value->headers);
+  bl_json__key(json, "symbols");
+  bl_greycat_abi__to_json_symbols(json, &value->symbols);
+  bl_json__key(json, "types");
+  bl_greycat_abi__to_json_types(json, &value->types);
+  bl_json__key(json, "functions");
+  bl_greycat_abi__to_json_functions(json, &value->functions);
+  bl_json__end(json, '}');
+  return json->failed ? bl_result_err : bl_result_ok;
+}
+bool bl_greycat_abi__eq_abi(const abi_t *a, const abi_t *b) {
+  if (!bl_greycat_abi__eq_headers(&a->headers, &b->headers)) {
+    return false;
+  }
+  if (!bl_greycat_abi__eq_symbols(&a->symbols, &b->symbols)) {
+    return false;
+  }
+  if (!bl_greycat_abi__eq_types(&a->types, &b->types)) {
+    return false;
+  }
+  if (!bl_greycat_abi__eq_functions(&a->functions, &b->functions)) {
+    return false;
+  }
+  return true;
+}
+bl_result_t bl_greycat_abi__clone_abi(abi_t *dst, const abi_t *src) {
+  bl_result_t res;
+  memset(dst, 0, sizeof *dst);
+  dst->headers = src->headers;
+  BL_TRY_GOTO(bl_greycat_abi__clone_symbols(&dst->symbols, &src->symbols), res, fail);
+  BL_TRY_GOTO(bl_greycat_abi__clone_types(&dst->types, &src->types), res, fail);
+  BL_TRY_GOTO(bl_greycat_abi__clone_functions(&dst->functions, &src->functions), res, fail);
+  return bl_result_ok;
+fail:
+  bl_greycat_abi__free_abi(dst);
+  return res;
+}
+uint64_t bl_greycat_abi__hash_abi(const abi_t *value) {
+  uint64_t hash = BL_HASH_INIT;
+  hash = bl_hash__u64(hash, bl_greycat_abi__hash_headers(&value->headers));
+  hash = bl_hash__u64(hash, bl_greycat_abi__hash_symbols(&value->symbols));
+  hash = bl_hash__u64(hash, bl_greycat_abi__hash_types(&value->types));
+  hash = bl_hash__u64(hash, bl_greycat_abi__hash_functions(&value->functions));
+  return hash;
 }
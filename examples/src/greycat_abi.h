@@ -31,6 +31,75 @@ typedef struct Abi abi_t;
 #define TYPE_FLAGS_MASKED (1 << 3)
 #define TYPE_FLAGS_AMBIGUOUS (1 << 4)
 
+/// Wire layout fingerprints
+#define BL_GREYCAT_ABI__ABI_FINGERPRINT UINT64_C(0x59b6be954499586c)
+#define BL_GREYCAT_ABI__HEADERS_FINGERPRINT UINT64_C(0xad989352af62a825)
+#define BL_GREYCAT_ABI__SYMBOLS_FINGERPRINT UINT64_C(0xeb0df6ed183f0a75)
+#define BL_GREYCAT_ABI__SYMBOL_FINGERPRINT UINT64_C(0xdb56925a2f0a1d91)
+#define BL_GREYCAT_ABI__TYPES_FINGERPRINT UINT64_C(0x39bd8cf1342ad09e)
+#define BL_GREYCAT_ABI__TYPE_FINGERPRINT UINT64_C(0x3035ee1013325c17)
+#define BL_GREYCAT_ABI__TYPE_ATTR_FINGERPRINT UINT64_C(0xc766ea7eedfb0d61)
+#define BL_GREYCAT_ABI__FUNCTIONS_FINGERPRINT UINT64_C(0x7d3ed40f6ae309c0)
+#define BL_GREYCAT_ABI__FUNCTION_FINGERPRINT UINT64_C(0x677fc912816596a6)
+#define BL_GREYCAT_ABI__FN_PARAM_FINGERPRINT UINT64_C(0x3064c293212a0b48)
+
+/// Wire layout: Abi, at least 56 bytes
+#define BL_GREYCAT_ABI__ABI_MIN_SIZE 56
+#define BL_GREYCAT_ABI__ABI_HEADERS_OFFSET 0
+#define BL_GREYCAT_ABI__ABI_SYMBOLS_OFFSET 16
+
+/// Wire layout: Headers, 16 bytes
+#define BL_GREYCAT_ABI__HEADERS_MIN_SIZE 16
+#define BL_GREYCAT_ABI__HEADERS_MAX_SIZE 16
+#define BL_GREYCAT_ABI__HEADERS_FIXED_SIZE 16
+#define BL_GREYCAT_ABI__HEADERS_MAJOR_OFFSET 0
+#define BL_GREYCAT_ABI__HEADERS_MAGIC_OFFSET 2
+#define BL_GREYCAT_ABI__HEADERS_VERSION_OFFSET 4
+#define BL_GREYCAT_ABI__HEADERS_CRC_OFFSET 8
+
+/// Wire layout: Symbols, at least 12 bytes
+#define BL_GREYCAT_ABI__SYMBOLS_MIN_SIZE 12
+#define BL_GREYCAT_ABI__SYMBOLS_BYTE_SIZE_OFFSET 0
+#define BL_GREYCAT_ABI__SYMBOLS_SYMBOLS_OFFSET 8
+
+/// Wire layout: Symbol, at least 1 bytes
+#define BL_GREYCAT_ABI__SYMBOL_MIN_SIZE 1
+#define BL_GREYCAT_ABI__SYMBOL_SIZE_OFFSET 0
+
+/// Wire layout: Types, at least 16 bytes
+#define BL_GREYCAT_ABI__TYPES_MIN_SIZE 16
+#define BL_GREYCAT_ABI__TYPES_BYTE_SIZE_OFFSET 0
+#define BL_GREYCAT_ABI__TYPES_NB_TYPES_OFFSET 8
+#define BL_GREYCAT_ABI__TYPES_NB_ATTRS_OFFSET 12
+#define BL_GREYCAT_ABI__TYPES_TYPES_OFFSET 16
+
+/// Wire layout: Type, at least 14 bytes
+#define BL_GREYCAT_ABI__TYPE_MIN_SIZE 14
+#define BL_GREYCAT_ABI__TYPE_MODULE_OFFSET 0
+
+/// Wire layout: TypeAttr, 8..=28 bytes
+#define BL_GREYCAT_ABI__TYPE_ATTR_MIN_SIZE 8
+#define BL_GREYCAT_ABI__TYPE_ATTR_MAX_SIZE 28
+#define BL_GREYCAT_ABI__TYPE_ATTR_NAME_OFFSET 0
+
+/// Wire layout: Functions, at least 12 bytes
+#define BL_GREYCAT_ABI__FUNCTIONS_MIN_SIZE 12
+#define BL_GREYCAT_ABI__FUNCTIONS_BYTE_SIZE_OFFSET 0
+#define BL_GREYCAT_ABI__FUNCTIONS_NB_FUNCTIONS_OFFSET 8
+#define BL_GREYCAT_ABI__FUNCTIONS_FUNCTIONS_OFFSET 12
+
+/// Wire layout: Function, at least 7 bytes
+#define BL_GREYCAT_ABI__FUNCTION_MIN_SIZE 7
+#define BL_GREYCAT_ABI__FUNCTION_MODULE_OFFSET 0
+
+/// Wire layout: FnParam, 3..=11 bytes
+#define BL_GREYCAT_ABI__FN_PARAM_MIN_SIZE 3
+#define BL_GREYCAT_ABI__FN_PARAM_MAX_SIZE 11
+#define BL_GREYCAT_ABI__FN_PARAM_NULLABLE_OFFSET 0
+#define BL_GREYCAT_ABI__FN_PARAM_TYPE_OFFSET 1
+
+_Static_assert(BL_GREYCAT_ABI__HEADERS_FIXED_SIZE == sizeof(uint16_t) + sizeof(uint16_t) + sizeof(uint32_t) + sizeof(uint64_t), "Headers: C types do not match the wire sizes");
+
 struct FnParam {
   uint8_t nullable;
   uint32_t type;
@@ -110,14 +179,74 @@ struct Abi {
 };
 
 bl_result_t bl_greycat_abi__read_fn_param(bl_slice_t *b, fn_param_t *value);
+void bl_greycat_abi__free_fn_param(fn_param_t *value);
+void bl_greycat_abi__print_fn_param(FILE *out, const fn_param_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_fn_param(bl_json_t *json, const fn_param_t *value);
+bool bl_greycat_abi__eq_fn_param(const fn_param_t *a, const fn_param_t *b);
+bl_result_t bl_greycat_abi__clone_fn_param(fn_param_t *dst, const fn_param_t *src);
+uint64_t bl_greycat_abi__hash_fn_param(const fn_param_t *value);
 bl_result_t bl_greycat_abi__read_function(bl_slice_t *b, function_t *value);
+void bl_greycat_abi__free_function(function_t *value);
+void bl_greycat_abi__print_function(FILE *out, const function_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_function(bl_json_t *json, const function_t *value);
+bool bl_greycat_abi__eq_function(const function_t *a, const function_t *b);
+bl_result_t bl_greycat_abi__clone_function(function_t *dst, const function_t *src);
+uint64_t bl_greycat_abi__hash_function(const function_t *value);
 bl_result_t bl_greycat_abi__read_functions(bl_slice_t *b, functions_t *value);
+void bl_greycat_abi__free_functions(functions_t *value);
+void bl_greycat_abi__print_functions(FILE *out, const functions_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_functions(bl_json_t *json, const functions_t *value);
+bool bl_greycat_abi__eq_functions(const functions_t *a, const functions_t *b);
+bl_result_t bl_greycat_abi__clone_functions(functions_t *dst, const functions_t *src);
+uint64_t bl_greycat_abi__hash_functions(const functions_t *value);
 bl_result_t bl_greycat_abi__read_type_attr(bl_slice_t *b, type_attr_t *value);
+void bl_greycat_abi__free_type_attr(type_attr_t *value);
+void bl_greycat_abi__print_type_attr(FILE *out, const type_attr_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_type_attr(bl_json_t *json, const type_attr_t *value);
+bool bl_greycat_abi__eq_type_attr(const type_attr_t *a, const type_attr_t *b);
+bl_result_t bl_greycat_abi__clone_type_attr(type_attr_t *dst, const type_attr_t *src);
+uint64_t bl_greycat_abi__hash_type_attr(const type_attr_t *value);
 bl_result_t bl_greycat_abi__read_type(bl_slice_t *b, type_t *value);
+void bl_greycat_abi__free_type(type_t *value);
+void bl_greycat_abi__print_type(FILE *out, const type_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_type(bl_json_t *json, const type_t *value);
+bool bl_greycat_abi__eq_type(const type_t *a, const type_t *b);
+bl_result_t bl_greycat_abi__clone_type(type_t *dst, const type_t *src);
+uint64_t bl_greycat_abi__hash_type(const type_t *value);
 bl_result_t bl_greycat_abi__read_types(bl_slice_t *b, types_t *value);
+void bl_greycat_abi__free_types(types_t *value);
+void bl_greycat_abi__print_types(FILE *out, const types_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_types(bl_json_t *json, const types_t *value);
+bool bl_greycat_abi__eq_types(const types_t *a, const types_t *b);
+bl_result_t bl_greycat_abi__clone_types(types_t *dst, const types_t *src);
+uint64_t bl_greycat_abi__hash_types(const types_t *value);
 bl_result_t bl_greycat_abi__read_symbol(bl_slice_t *b, symbol_t *value);
+void bl_greycat_abi__free_symbol(symbol_t *value);
+void bl_greycat_abi__print_symbol(FILE *out, const symbol_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_symbol(bl_json_t *json, const symbol_t *value);
+bool bl_greycat_abi__eq_symbol(const symbol_t *a, const symbol_t *b);
+bl_result_t bl_greycat_abi__clone_symbol(symbol_t *dst, const symbol_t *src);
+uint64_t bl_greycat_abi__hash_symbol(const symbol_t *value);
 bl_result_t bl_greycat_abi__read_symbols(bl_slice_t *b, symbols_t *value);
+void bl_greycat_abi__free_symbols(symbols_t *value);
+void bl_greycat_abi__print_symbols(FILE *out, const symbols_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_symbols(bl_json_t *json, const symbols_t *value);
+bool bl_greycat_abi__eq_symbols(const symbols_t *a, const symbols_t *b);
+bl_result_t bl_greycat_abi__clone_symbols(symbols_t *dst, const symbols_t *src);
+uint64_t bl_greycat_abi__hash_symbols(const symbols_t *value);
 bl_result_t bl_greycat_abi__read_headers(bl_slice_t *b, headers_t *value);
+void bl_greycat_abi__free_headers(headers_t *value);
+void bl_greycat_abi__print_headers(FILE *out, const headers_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_headers(bl_json_t *json, const headers_t *value);
+bool bl_greycat_abi__eq_headers(const headers_t *a, const headers_t *b);
+bl_result_t bl_greycat_abi__clone_headers(headers_t *dst, const headers_t *src);
+uint64_t bl_greycat_abi__hash_headers(const headers_t *value);
 bl_result_t bl_greycat_abi__read_abi(bl_slice_t *b, abi_t *value);
+void bl_greycat_abi__free_abi(abi_t *value);
+void bl_greycat_abi__print_abi(FILE *out, const abi_t *value, int indent);
+bl_result_t bl_greycat_abi__to_json_abi(bl_json_t *json, const abi_t *value);
+bool bl_greycat_abi__eq_abi(const abi_t *a, const abi_t *b);
+bl_result_t bl_greycat_abi__clone_abi(abi_t *dst, const abi_t *src);
+uint64_t bl_greycat_abi__hash_abi(const abi_t *value);
 
 #endif // BINLANG_greycat_abi_H_
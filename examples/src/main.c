@@ -34,7 +34,7 @@ int read_file(const char *filepath, byte_vec_t *buf) {
 int32_t main(int32_t argc, char *argv[]) {
   // check that a filepath is provided
   if (argc < 2) {
-    fprintf(stderr, "Usage: %s <filepath>\n\n", argv[1]);
+    fprintf(stderr, "Usage: %s <filepath> [--json]\n\n", argv[1]);
     fprintf(stderr, "  eg. %s gcdata/abi\n", argv[1]);
     return 1;
   }
@@ -44,39 +44,28 @@ int32_t main(int32_t argc, char *argv[]) {
     return 1;
   }
 
-  bl_slice_t b = {.data = buf.elems, .len = buf.size};
+  // an ABI is small, refuse to allocate more than 64MiB whatever the counts say
+  bl_limits_t limits = {.max_total_bytes = 64 << 20};
+  bl_slice_t b = {.data = buf.elems, .len = buf.size, .limits = &limits};
   abi_t abi = {0};
   if (bl_greycat_abi__read_abi(&b, &abi) <= 0) {
+    // the reader already released what it had decoded
     fprintf(stderr, "unable to deserialize file\n");
+    vec_delete(&buf);
     return 1;
   }
 
-  printf("=== headers ===\n");
-  printf("major=%d\n", abi.headers.major);
-  printf("version=%d\n", abi.headers.version);
-  printf("magic=%d\n", abi.headers.magic);
-  printf("crc=%ld\n", abi.headers.crc);
-
-  printf("=== symbols ===\n");
-  for (uint32_t i = 0; i < abi.symbols.symbols.size; i++) {
-    symbol_t *symbol = array_get(&abi.symbols.symbols, i);
-    printf("%.*s=%d\n", symbol->text.size, symbol->text.elems, i);
-  }
-
-  printf("=== types ===\n");
-  for (uint32_t i = 0; i < abi.types.types.size; i++) {
-    type_t *ty = array_get(&abi.types.types, i);
-    symbol_t *name = array_get(&abi.symbols.symbols, ty->name - 1);
-    printf("%.*s=%d\n", name->text.size, name->text.elems, i);
-  }
-
-  printf("=== functions ===\n");
-  for (uint32_t i = 0; i < abi.functions.functions.size; i++) {
-    function_t *fn = array_get(&abi.functions.functions, i);
-    symbol_t *name = array_get(&abi.symbols.symbols, fn->name - 1);
-    printf("%.*s=%d\n", name->text.size, name->text.elems, i);
+  if (argc > 2 && strcmp(argv[2], "--json") == 0) {
+    bl_json_t json = {.pretty = true};
+    if (bl_greycat_abi__to_json_abi(&json, &abi) > 0) {
+      puts(json.text.elems);
+    }
+    bl_json__delete(&json);
+  } else {
+    bl_greycat_abi__print_abi(stdout, &abi, 0);
   }
 
+  bl_greycat_abi__free_abi(&abi);
   vec_delete(&buf);
 
   return 0;
@@ -1,5 +1,7 @@
 #include "alloc.h"
+#include <stddef.h>
 #include <stdlib.h>
+#include <string.h>
 
 static void *bl_malloc_default(size_t size) {
   void *result = malloc(size);
@@ -42,4 +44,80 @@ void bl_set_allocator(void *(*new_malloc)(size_t size),
   bl_current_calloc = new_calloc ? new_calloc : bl_calloc_default;
   bl_current_realloc = new_realloc ? new_realloc : bl_realloc_default;
   bl_current_free = new_free ? new_free : free;
-}
\ No newline at end of file
+}
+
+void *bl_allocator__malloc(bl_allocator_t *allocator, size_t size) {
+  if (allocator == NULL) {
+    return bl_malloc(size);
+  }
+  return (allocator->malloc)(allocator->data, size);
+}
+
+void *bl_allocator__realloc(bl_allocator_t *allocator, void *ptr,
+                            size_t old_size, size_t size) {
+  if (allocator == NULL) {
+    return bl_realloc(ptr, size);
+  }
+  return (allocator->realloc)(allocator->data, ptr, old_size, size);
+}
+
+void bl_allocator__free(bl_allocator_t *allocator, void *ptr) {
+  if (allocator == NULL) {
+    bl_free(ptr);
+    return;
+  }
+  (allocator->free)(allocator->data, ptr);
+}
+
+// binlang:begin allocator
+static void *bl_arena__malloc(void *data, size_t size) {
+  bl_arena_t *arena = data;
+  // aligned like `malloc`, whatever the alignment of the buffer
+  uintptr_t align = _Alignof(max_align_t);
+  uintptr_t base = (uintptr_t)arena->data;
+  size_t start = ((base + arena->used + align - 1) & ~(align - 1)) - base;
+  if (start > arena->len || size > arena->len - start) {
+    return NULL;
+  }
+  arena->last = start;
+  arena->used = start + size;
+  return arena->data + start;
+}
+
+static void *bl_arena__realloc(void *data, void *ptr, size_t old_size,
+                               size_t size) {
+  bl_arena_t *arena = data;
+  if (ptr != NULL && (uint8_t *)ptr == arena->data + arena->last) {
+    // the last allocation grows or shrinks in place
+    if (size > arena->len - arena->last) {
+      return NULL;
+    }
+    arena->used = arena->last + size;
+    return ptr;
+  }
+  void *moved = bl_arena__malloc(data, size);
+  if (moved != NULL && ptr != NULL) {
+    memcpy(moved, ptr, old_size < size ? old_size : size);
+  }
+  return moved;
+}
+
+static void bl_arena__free(void *data, void *ptr) {
+  (void)data;
+  (void)ptr;
+}
+
+bl_allocator_t bl_arena__allocator(bl_arena_t *arena) {
+  return (bl_allocator_t){
+      .malloc = bl_arena__malloc,
+      .realloc = bl_arena__realloc,
+      .free = bl_arena__free,
+      .data = arena,
+  };
+}
+
+void bl_arena__reset(bl_arena_t *arena) {
+  arena->used = 0;
+  arena->last = 0;
+}
+// binlang:end allocator
@@ -6,6 +6,7 @@ extern "C" {
 #endif
 
 #include <stdbool.h>
+#include <stdint.h>
 #include <stdio.h>
 #include <stdlib.h>
 
@@ -28,6 +29,40 @@ extern void (*bl_current_free)(void *ptr);
 #define bl_free    bl_current_free
 #endif
 
+/// Allocation callbacks, each given `data`. Where an allocator is optional,
+/// `NULL` stands for the global hooks above.
+typedef struct {
+  void *(*malloc)(void *data, size_t size);
+  /// `old_size` is the size `ptr` was allocated with
+  void *(*realloc)(void *data, void *ptr, size_t old_size, size_t size);
+  void (*free)(void *data, void *ptr);
+  void *data;
+} bl_allocator_t;
+
+void *bl_allocator__malloc(bl_allocator_t *allocator, size_t size);
+void *bl_allocator__realloc(bl_allocator_t *allocator, void *ptr,
+                            size_t old_size, size_t size);
+void bl_allocator__free(bl_allocator_t *allocator, void *ptr);
+
+// binlang:begin allocator
+/// A bump allocator handing out the bytes of `data` in order, eg. to decode
+/// without the system heap. Its memory is released all at once by
+/// `bl_arena__reset`, freeing a single allocation does nothing.
+typedef struct {
+  uint8_t *data;
+  size_t len;
+  /// bytes handed out so far
+  size_t used;
+  /// offset of the last allocation, which can grow in place
+  size_t last;
+} bl_arena_t;
+
+/// An allocator taking its memory from `arena`
+bl_allocator_t bl_arena__allocator(bl_arena_t *arena);
+/// Releases all the allocations of `arena`
+void bl_arena__reset(bl_arena_t *arena);
+// binlang:end allocator
+
 #ifdef __cplusplus
 }
 #endif
@@ -61,6 +61,15 @@ extern "C" {
 /// memory allocated for the array's elems.
 #define array_delete(self) _array__delete((BlArray *)(self))
 
+/// Like `array_reserve`, allocating the elems with `allocator` (see
+/// `bl_allocator_t`).
+#define array_reserve_in(self, size, allocator)                                \
+  _array__reserve_in((BlArray *)(self), array_elem_size(self), size, allocator)
+
+/// Like `array_delete`, for elems reserved with `array_reserve_in`.
+#define array_delete_in(self, allocator)                                       \
+  _array__delete_in((BlArray *)(self), allocator)
+
 /// Push a new `element` onto the end of the array.
 #define array_push(self, element)                                              \
   (_array__grow((BlArray *)(self), 1, array_elem_size(self)),                  \
@@ -172,6 +181,15 @@ static inline void _array__delete(BlArray *self) {
   }
 }
 
+/// This is not what you're looking for, see `array_delete_in`.
+static inline void _array__delete_in(BlArray *self, bl_allocator_t *allocator) {
+  if (self->elems) {
+    bl_allocator__free(allocator, self->elems);
+    self->elems = NULL;
+    self->size = 0;
+  }
+}
+
 /// This is not what you're looking for, see `array_erase`.
 static inline void _array__erase(BlArray *self, size_t element_size,
                                  uint32_t index) {
@@ -191,6 +209,15 @@ static inline void _array__reserve(BlArray *self, size_t element_size,
   }
 }
 
+/// This is not what you're looking for, see `array_reserve_in`.
+static inline void _array__reserve_in(BlArray *self, size_t element_size,
+                                      uint32_t size, bl_allocator_t *allocator) {
+  if (self->elems == NULL && size > 0) {
+    self->elems = bl_allocator__malloc(allocator, size * element_size);
+    self->size = size;
+  }
+}
+
 /// This is not what you're looking for, see `array_assign`.
 static inline void _array__assign(BlArray *self, const BlArray *other,
                                   size_t element_size) {
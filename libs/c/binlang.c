@@ -5,6 +5,19 @@ inline void bl_slice__advance(bl_slice_t *b, size_t n) {
   b->len -= n;
 }
 
+bl_result_t bl_slice__read_bool(bl_slice_t *b, bool *value) {
+  if (b->len < 1) {
+    return bl_result_eof;
+  }
+  uint8_t byte = b->data[0];
+  if (byte > 1) {
+    return bl_result_err;
+  }
+  *value = byte == 1;
+  bl_slice__advance(b, 1);
+  return bl_result_ok;
+}
+
 bl_result_t bl_slice__read_u8(bl_slice_t *b, uint8_t *value) {
   if (b->len < 1) {
     return bl_result_eof;
@@ -67,6 +80,24 @@ bl_result_t bl_slice__read_u64(bl_slice_t *b, uint64_t *value) {
   return bl_result_ok;
 }
 
+#ifdef __SIZEOF_INT128__
+bl_result_t bl_slice__read_u128(bl_slice_t *b, bl_u128_t *value) {
+  if (b->len < 16) {
+    return bl_result_eof;
+  }
+  uint8_t *data = b->data;
+
+  bl_u128_t tmp = 0;
+  for (int i = 0; i < 16; i++) {
+    tmp |= (bl_u128_t)data[i] << (i * 8);
+  }
+
+  *value = tmp;
+  bl_slice__advance(b, 16);
+  return bl_result_ok;
+}
+#endif
+
 bl_result_t bl_slice__read_i8(bl_slice_t *b, int8_t *value) {
   if (b->len < 1) {
     return bl_result_eof;
@@ -117,6 +148,15 @@ bl_result_t bl_slice__read_i64(bl_slice_t *b, int64_t *value) {
   return bl_result_ok;
 }
 
+#ifdef __SIZEOF_INT128__
+bl_result_t bl_slice__read_i128(bl_slice_t *b, bl_i128_t *value) {
+  bl_u128_t tmp;
+  BL_TRY(bl_slice__read_u128(b, &tmp));
+  *value = (bl_i128_t)tmp;
+  return bl_result_ok;
+}
+#endif
+
 bl_result_t bl_slice__read_vu32(bl_slice_t *b, uint32_t *value) {
   uint32_t result = 0;
   uint32_t shift = 0;
@@ -134,7 +174,8 @@ bl_result_t bl_slice__read_vu32(bl_slice_t *b, uint32_t *value) {
     }
   }
 
-  return bl_result_eof;
+  // out of input, unless there were too many continuation bytes
+  return i < 5 ? bl_result_eof : bl_result_err;
 }
 
 bl_result_t bl_slice__read_vu64(bl_slice_t *b, uint64_t *value) {
@@ -154,7 +195,8 @@ bl_result_t bl_slice__read_vu64(bl_slice_t *b, uint64_t *value) {
     }
   }
 
-  return bl_result_eof;
+  // out of input, unless there were too many continuation bytes
+  return i < 10 ? bl_result_eof : bl_result_err;
 }
 
 bl_result_t bl_slice__read_vi32(bl_slice_t *b, int32_t *value) {
@@ -177,7 +219,8 @@ bl_result_t bl_slice__read_vi32(bl_slice_t *b, int32_t *value) {
     }
   }
 
-  return bl_result_eof;
+  // out of input, unless there were too many continuation bytes
+  return i < 5 ? bl_result_eof : bl_result_err;
 }
 
 bl_result_t bl_slice__read_vi64(bl_slice_t *b, int64_t *value) {
@@ -200,18 +243,390 @@ bl_result_t bl_slice__read_vi64(bl_slice_t *b, int64_t *value) {
     }
   }
 
-  return bl_result_eof;
+  // out of input, unless there were too many continuation bytes
+  return i < 10 ? bl_result_eof : bl_result_err;
 }
 
+bl_result_t bl_slice__read_vzi32(bl_slice_t *b, int32_t *value) {
+  uint32_t tmp;
+  BL_TRY(bl_slice__read_vu32(b, &tmp));
+  *value = (int32_t)((tmp >> 1) ^ -(tmp & 1));
+  return bl_result_ok;
+}
+
+bl_result_t bl_slice__read_vzi64(bl_slice_t *b, int64_t *value) {
+  uint64_t tmp;
+  BL_TRY(bl_slice__read_vu64(b, &tmp));
+  *value = (int64_t)((tmp >> 1) ^ -(tmp & 1));
+  return bl_result_ok;
+}
+
+bl_result_t bl_slice__read_u8_len(bl_slice_t *b, uint32_t *size) {
+  uint8_t len;
+  bl_result_t res = bl_slice__read_u8(b, &len);
+  if (res == bl_result_ok) {
+    *size = len;
+  }
+  return res;
+}
+
+bl_result_t bl_slice__read_u16_len(bl_slice_t *b, uint32_t *size) {
+  uint16_t len;
+  bl_result_t res = bl_slice__read_u16(b, &len);
+  if (res == bl_result_ok) {
+    *size = len;
+  }
+  return res;
+}
+
+#define BL_READ_WIDE_LEN(name, type)                                           \
+  bl_result_t bl_slice__read_##name##_len(bl_slice_t *b, uint32_t *size) {     \
+    type len;                                                                  \
+    bl_result_t res = bl_slice__read_##name(b, &len);                          \
+    if (res != bl_result_ok) {                                                 \
+      return res;                                                              \
+    }                                                                          \
+    if (len > UINT32_MAX) {                                                    \
+      return bl_result_err;                                                    \
+    }                                                                          \
+    *size = (uint32_t)len;                                                     \
+    return bl_result_ok;                                                       \
+  }
+
+BL_READ_WIDE_LEN(u64, uint64_t)
+BL_READ_WIDE_LEN(vu64, uint64_t)
+#ifdef __SIZEOF_INT128__
+BL_READ_WIDE_LEN(u128, bl_u128_t)
+#endif
+#undef BL_READ_WIDE_LEN
+
 bl_result_t bl_slice__read_exact(bl_slice_t *b, uint8_t *buf, uint64_t len) {
   if (b->len < len) {
     return bl_result_eof;
   }
-  memcpy(buf, b->data, len);
+  if (len > 0) {
+    memcpy(buf, b->data, len);
+  }
+  bl_slice__advance(b, len);
+  return bl_result_ok;
+}
+
+bl_result_t bl_slice__check_count(bl_slice_t *b, uint32_t count,
+                                  uint32_t min_size, size_t elem_size) {
+  if ((uint64_t)count * min_size > b->len) {
+    return bl_result_eof;
+  }
+  bl_limits_t *limits = b->limits;
+  if (limits == NULL) {
+    return bl_result_ok;
+  }
+  // less than 2^32 elements of a C type, far from overflowing
+  uint64_t bytes = (uint64_t)count * elem_size;
+  if (limits->max_array_bytes > 0 && bytes > limits->max_array_bytes) {
+    return bl_result_err;
+  }
+  if (limits->max_total_bytes > 0 &&
+      limits->total_bytes + bytes > limits->max_total_bytes) {
+    return bl_result_err;
+  }
+  limits->total_bytes += bytes;
+  return bl_result_ok;
+}
+
+bl_result_t bl_slice__read_view(bl_slice_t *b, bl_view_t *view, uint32_t stride) {
+  uint64_t len = (uint64_t)view->size * stride;
+  if (b->len < len) {
+    return bl_result_eof;
+  }
+  view->elems = b->data;
   bl_slice__advance(b, len);
   return bl_result_ok;
 }
 
+void bl_print__indent(FILE *out, int indent) {
+  for (int i = 0; i < indent; i++) {
+    fputs("  ", out);
+  }
+}
+
+void bl_print__bytes(FILE *out, const uint8_t *bytes, uint32_t len) {
+  bool printable = true;
+  for (uint32_t i = 0; i < len && printable; i++) {
+    printable = bytes[i] >= 0x20 && bytes[i] < 0x7f;
+  }
+  if (printable) {
+    fputc('"', out);
+    for (uint32_t i = 0; i < len; i++) {
+      if (bytes[i] == '"' || bytes[i] == '\\') {
+        fputc('\\', out);
+      }
+      fputc(bytes[i], out);
+    }
+    fputc('"', out);
+    return;
+  }
+  fputc('<', out);
+  for (uint32_t i = 0; i < len; i++) {
+    fprintf(out, "%s%02x", i == 0 ? "" : " ", bytes[i]);
+  }
+  fputc('>', out);
+}
+
+static void bl_json__append(bl_json_t *json, const char *s, size_t len) {
+  if (json->failed) {
+    return;
+  }
+  // room for the text and its NUL terminator
+  uint64_t needed = (uint64_t)json->text.size + len + 1;
+  if (needed > json->text.capacity) {
+    uint64_t capacity = json->text.capacity < 64 ? 64 : json->text.capacity;
+    while (capacity < needed) {
+      capacity *= 2;
+    }
+    char *elems = NULL;
+    if (capacity <= UINT32_MAX) {
+      elems = json->text.elems == NULL
+                  ? bl_malloc(capacity)
+                  : bl_realloc(json->text.elems, capacity);
+    }
+    if (elems == NULL) {
+      json->failed = true;
+      return;
+    }
+    json->text.elems = elems;
+    json->text.capacity = (uint32_t)capacity;
+  }
+  memcpy(json->text.elems + json->text.size, s, len);
+  json->text.size += len;
+  json->text.elems[json->text.size] = '\0';
+}
+
+static void bl_json__newline(bl_json_t *json) {
+  if (!json->pretty) {
+    return;
+  }
+  bl_json__append(json, "\n", 1);
+  for (uint32_t i = 0; i < json->depth; i++) {
+    bl_json__append(json, "  ", 2);
+  }
+}
+
+void bl_json__begin(bl_json_t *json, char open) {
+  bl_json__append(json, &open, 1);
+  json->depth++;
+  json->empty = true;
+}
+
+void bl_json__end(bl_json_t *json, char close) {
+  json->depth--;
+  if (!json->empty) {
+    bl_json__newline(json);
+  }
+  bl_json__append(json, &close, 1);
+  json->empty = false;
+}
+
+void bl_json__item(bl_json_t *json) {
+  if (!json->empty) {
+    bl_json__append(json, ",", 1);
+  }
+  json->empty = false;
+  bl_json__newline(json);
+}
+
+void bl_json__key(bl_json_t *json, const char *key) {
+  bl_json__item(json);
+  // keys are field names, which need no escaping
+  bl_json__append(json, "\"", 1);
+  bl_json__append(json, key, strlen(key));
+  if (json->pretty) {
+    bl_json__append(json, "\": ", 3);
+  } else {
+    bl_json__append(json, "\":", 2);
+  }
+}
+
+void bl_json__bool(bl_json_t *json, bool value) {
+  if (value) {
+    bl_json__append(json, "true", 4);
+  } else {
+    bl_json__append(json, "false", 5);
+  }
+}
+
+void bl_json__uint(bl_json_t *json, uint64_t value) {
+  char buf[24];
+  int n = snprintf(buf, sizeof buf, "%llu", (unsigned long long)value);
+  bl_json__append(json, buf, n);
+}
+
+void bl_json__int(bl_json_t *json, int64_t value) {
+  char buf[24];
+  int n = snprintf(buf, sizeof buf, "%lld", (long long)value);
+  bl_json__append(json, buf, n);
+}
+
+void bl_json__double(bl_json_t *json, double value) {
+  if (value != value || value - value != 0) {
+    bl_json__append(json, "null", 4);
+    return;
+  }
+  char buf[32];
+  int n = snprintf(buf, sizeof buf, "%.17g", value);
+  bl_json__append(json, buf, n);
+}
+
+#ifdef __SIZEOF_INT128__
+/// Writes the decimal digits of `value` as a string
+static void bl_json__decimal128(bl_json_t *json, bool negative,
+                                bl_u128_t value) {
+  char buf[44];
+  size_t i = sizeof buf;
+  buf[--i] = '"';
+  do {
+    buf[--i] = (char)('0' + (int)(value % 10));
+    value /= 10;
+  } while (value > 0);
+  if (negative) {
+    buf[--i] = '-';
+  }
+  buf[--i] = '"';
+  bl_json__append(json, buf + i, sizeof buf - i);
+}
+
+void bl_json__u128(bl_json_t *json, bl_u128_t value) {
+  if (value <= UINT64_MAX) {
+    bl_json__uint(json, (uint64_t)value);
+  } else {
+    bl_json__decimal128(json, false, value);
+  }
+}
+
+void bl_json__i128(bl_json_t *json, bl_i128_t value) {
+  if (value >= INT64_MIN && value <= INT64_MAX) {
+    bl_json__int(json, (int64_t)value);
+  } else if (value < 0) {
+    // negated as unsigned, which also holds the magnitude of the minimum
+    bl_json__decimal128(json, true, -(bl_u128_t)value);
+  } else {
+    bl_json__decimal128(json, false, (bl_u128_t)value);
+  }
+}
+#endif
+
+void bl_json__flags(bl_json_t *json, uint8_t bits, const char *const names[8]) {
+  bl_json__begin(json, '[');
+  for (int i = 0; i < 8; i++) {
+    if (!(bits & (1 << i))) {
+      continue;
+    }
+    bl_json__item(json);
+    if (names[i] != NULL) {
+      // flag names are identifiers, which need no escaping
+      bl_json__append(json, "\"", 1);
+      bl_json__append(json, names[i], strlen(names[i]));
+      bl_json__append(json, "\"", 1);
+    } else {
+      char buf[8];
+      int n = snprintf(buf, sizeof buf, "\"bit%d\"", i);
+      bl_json__append(json, buf, n);
+    }
+  }
+  bl_json__end(json, ']');
+}
+
+/// Length of the UTF-8 sequence at the start of `bytes` when it is a
+/// character other than a control one, 0 otherwise
+static uint32_t bl_json__text_char(const uint8_t *bytes, uint32_t len) {
+  uint8_t byte = bytes[0];
+  if (byte < 0x80) {
+    return byte >= 0x20 && byte != 0x7f ? 1 : 0;
+  }
+  uint32_t n;
+  uint32_t c;
+  if (byte >= 0xc2 && byte <= 0xdf) {
+    n = 2;
+    c = byte & 0x1f;
+  } else if (byte >= 0xe0 && byte <= 0xef) {
+    n = 3;
+    c = byte & 0x0f;
+  } else if (byte >= 0xf0 && byte <= 0xf4) {
+    n = 4;
+    c = byte & 0x07;
+  } else {
+    return 0;
+  }
+  if (len < n) {
+    return 0;
+  }
+  for (uint32_t i = 1; i < n; i++) {
+    if ((bytes[i] & 0xc0) != 0x80) {
+      return 0;
+    }
+    c = (c << 6) | (bytes[i] & 0x3f);
+  }
+  // overlong encodings, surrogates, out of range and C1 control characters
+  static const uint32_t min[5] = {0, 0, 0x80, 0x800, 0x10000};
+  if (c < min[n] || (c >= 0xd800 && c <= 0xdfff) || c > 0x10ffff ||
+      (c >= 0x80 && c <= 0x9f)) {
+    return 0;
+  }
+  return n;
+}
+
+void bl_json__bytes(bl_json_t *json, const uint8_t *bytes, uint32_t len) {
+  bool text = true;
+  for (uint32_t i = 0, n; i < len && text; i += n) {
+    n = bl_json__text_char(bytes + i, len - i);
+    text = n > 0;
+  }
+  if (!text) {
+    bl_json__begin(json, '[');
+    for (uint32_t i = 0; i < len; i++) {
+      bl_json__item(json);
+      bl_json__uint(json, bytes[i]);
+    }
+    bl_json__end(json, ']');
+    return;
+  }
+  bl_json__append(json, "\"", 1);
+  for (uint32_t i = 0; i < len; i++) {
+    if (bytes[i] == '"' || bytes[i] == '\\') {
+      bl_json__append(json, "\\", 1);
+    }
+    bl_json__append(json, (const char *)bytes + i, 1);
+  }
+  bl_json__append(json, "\"", 1);
+}
+
+void bl_json__delete(bl_json_t *json) { vec_delete(&json->text); }
+
+uint64_t bl_hash__bytes(uint64_t hash, const void *bytes, size_t len) {
+  const uint8_t *data = bytes;
+  for (size_t i = 0; i < len; i++) {
+    hash ^= data[i];
+    hash *= 0x00000100000001b3ULL;
+  }
+  return hash;
+}
+
+uint64_t bl_hash__u64(uint64_t hash, uint64_t value) {
+  for (int i = 0; i < 8; i++) {
+    hash ^= (uint8_t)(value >> (i * 8));
+    hash *= 0x00000100000001b3ULL;
+  }
+  return hash;
+}
+
+uint64_t bl_hash__double(uint64_t hash, double value) {
+  if (value == 0) {
+    value = 0;
+  }
+  uint64_t bits;
+  memcpy(&bits, &value, sizeof bits);
+  return bl_hash__u64(hash, bits);
+}
+
 #ifdef FLOAT
 bl_result_t bl_slice__read_f32(bl_slice_t *b, f32_t *value) {
   if (b->len < 4) {
@@ -250,4 +665,168 @@ bl_result_t bl_slice__read_f64(bl_slice_t *b, f64_t *value) {
   bl_slice__advance(b, 8);
   return bl_result_ok;
 }
-#endif
\ No newline at end of file
+#endif
+
+// binlang:begin stream
+bl_result_t bl_stream__reserve(BlArray *array, size_t elem_size, uint64_t needed,
+                               uint32_t count, bl_allocator_t *allocator) {
+  if (needed > count) {
+    needed = count;
+  }
+  if (needed <= array->size) {
+    return bl_result_ok;
+  }
+  uint64_t size = array->size < 8 ? 8 : (uint64_t)array->size * 2;
+  if (size > count) {
+    size = count;
+  }
+  if (size < needed) {
+    size = needed;
+  }
+  size_t old_bytes = array->size * elem_size;
+  uint8_t *elems = bl_allocator__realloc(allocator, array->elems, old_bytes,
+                                         size * elem_size);
+  if (elems == NULL) {
+    return bl_result_err;
+  }
+  memset(elems + old_bytes, 0, size * elem_size - old_bytes);
+  array->elems = elems;
+  array->size = (uint32_t)size;
+  return bl_result_ok;
+}
+
+bl_result_t bl_stream__read_bytes(bl_slice_t *b, uint8_t *buf, uint32_t len,
+                                  uint32_t *done) {
+  uint32_t n = len - *done;
+  if (n > b->len) {
+    n = b->len;
+  }
+  if (n > 0) {
+    memcpy(buf + *done, b->data, n);
+  }
+  bl_slice__advance(b, n);
+  *done += n;
+  return *done == len ? bl_result_ok : bl_result_eof;
+}
+
+bl_result_t bl_stream__feed(bl_stream_t *s, bl_stream_fn_t fn, void *value,
+                            const uint8_t *data, uint32_t len) {
+  bl_result_t res;
+  uint32_t offset = 0;
+
+  if (s->pending_len > 0) {
+    // finish the value split across chunks from a copy of its first bytes
+    uint8_t scratch[2 * BL_STREAM_MAX_PENDING];
+    uint32_t n = len < BL_STREAM_MAX_PENDING ? len : BL_STREAM_MAX_PENDING;
+    memcpy(scratch, s->pending, s->pending_len);
+    memcpy(scratch + s->pending_len, data, n);
+    uint32_t total = s->pending_len + n;
+    bl_slice_t b = {.data = scratch, .len = total, .limits = s->limits};
+    res = fn(s, &b, value);
+    if (res == bl_result_err) {
+      return res;
+    }
+    uint32_t consumed = total - b.len;
+    if (consumed < s->pending_len) {
+      // still incomplete, which only happens when the whole chunk was copied
+      if (n < len || b.len > BL_STREAM_MAX_PENDING) {
+        return bl_result_err;
+      }
+      memmove(s->pending, b.data, b.len);
+      s->pending_len = b.len;
+      return bl_result_eof;
+    }
+    offset = consumed - s->pending_len;
+    s->pending_len = 0;
+    if (res == bl_result_ok) {
+      s->rest = len - offset;
+      return res;
+    }
+  }
+
+  bl_slice_t b = {
+      .data = (uint8_t *)data + offset, .len = len - offset, .limits = s->limits};
+  res = fn(s, &b, value);
+  if (res == bl_result_ok) {
+    s->rest = b.len;
+  } else if (res == bl_result_eof) {
+    // keep the first bytes of the incomplete value for the next chunk
+    if (b.len > BL_STREAM_MAX_PENDING) {
+      return bl_result_err;
+    }
+    memcpy(s->pending, b.data, b.len);
+    s->pending_len = b.len;
+  }
+  return res;
+}
+// binlang:end stream
+
+// binlang:begin errors
+void bl_error__record(bl_error_t *err, bl_result_t res, bl_error_kind_t kind,
+                      const bl_slice_t *b, const char *field, uint32_t index) {
+  if (err == NULL) {
+    return;
+  }
+  if (err->kind == bl_error_none) {
+    err->kind = res == bl_result_eof ? bl_error_eof : kind;
+    err->offset = err->base != NULL ? (uint64_t)(b->data - err->base) : 0;
+  }
+  if (err->depth < BL_ERROR_MAX_DEPTH) {
+    err->path[err->depth].field = field;
+    err->path[err->depth].index = index;
+  }
+  err->depth++;
+}
+
+int bl_error__format(const bl_error_t *err, char *buf, size_t len) {
+  const char *kind;
+  switch (err->kind) {
+  case bl_error_none:
+    return snprintf(buf, len, "no error");
+  case bl_error_eof:
+    kind = "unexpected end of input";
+    break;
+  case bl_error_varint_overflow:
+    kind = "varint overflows its type";
+    break;
+  case bl_error_constraint:
+    kind = "constraint violated";
+    break;
+  case bl_error_alloc:
+    kind = "allocation failed";
+    break;
+  case bl_error_limit:
+    kind = "array exceeds the allocation limits";
+    break;
+  default:
+    kind = "unknown error";
+    break;
+  }
+
+  // appends to `buf` while counting the length of the whole message
+  int n = snprintf(buf, len, "%s at byte %llu (0x%llx)", kind,
+                   (unsigned long long)err->offset,
+                   (unsigned long long)err->offset);
+#define BL_APPEND(...)                                                         \
+  n += snprintf((size_t)n < len ? buf + n : NULL,                              \
+                (size_t)n < len ? len - n : 0, __VA_ARGS__)
+  if (err->depth > 0) {
+    BL_APPEND(" in '");
+    uint32_t depth = err->depth;
+    if (depth > BL_ERROR_MAX_DEPTH) {
+      depth = BL_ERROR_MAX_DEPTH;
+      BL_APPEND("...");
+    }
+    for (uint32_t i = depth; i > 0; i--) {
+      const bl_error_segment_t *segment = &err->path[i - 1];
+      BL_APPEND("%s%s", i == depth ? "" : ".", segment->field);
+      if (segment->index != BL_ERROR_NO_INDEX) {
+        BL_APPEND("[%u]", segment->index);
+      }
+    }
+    BL_APPEND("'");
+  }
+#undef BL_APPEND
+  return n;
+}
+// binlang:end errors
@@ -13,11 +13,34 @@ typedef double f64_t;
 #include "array.h"
 #include "vec.h"
 
+#ifdef __SIZEOF_INT128__
+typedef unsigned __int128 bl_u128_t;
+typedef __int128 bl_i128_t;
+#endif
+
+/// Allocation budgets of a decoding, a limit of 0 means unlimited
+typedef struct {
+  /// most bytes allocated for the elements of a single array
+  uint64_t max_array_bytes;
+  /// most bytes allocated for all the arrays of the decoding
+  uint64_t max_total_bytes;
+  /// bytes allocated so far, counted by the readers
+  uint64_t total_bytes;
+} bl_limits_t;
+
 typedef struct {
   uint8_t *data;
   uint32_t len;
+  /// budgets checked before allocating arrays, unlimited when NULL
+  bl_limits_t *limits;
 } bl_slice_t;
 
+/// `size` elements left encoded in a decoded buffer, which must outlive the view
+typedef struct {
+  const uint8_t *elems;
+  uint32_t size;
+} bl_view_t;
+
 typedef enum {
   bl_result_err = -1,
   bl_result_eof = 0,
@@ -32,7 +55,82 @@ typedef enum {
     }                                                                          \
   } while (0);
 
+/// Like `BL_TRY`, but stores the failed result in `res` and jumps to `label`, for readers that
+/// must release what they allocated before returning
+#define BL_TRY_GOTO(x, res, label)                                             \
+  do {                                                                         \
+    res = (x);                                                                 \
+    if (res <= 0) {                                                            \
+      goto label;                                                              \
+    }                                                                          \
+  } while (0);
+
+// binlang:begin errors
+/// Why a reader failed, see `bl_error_t`
+typedef enum {
+  bl_error_none = 0,
+  bl_error_eof,
+  bl_error_varint_overflow,
+  /// a value the type does not allow, eg. a bool other than 0 or 1
+  bl_error_constraint,
+  bl_error_alloc,
+  /// an array over the budgets of `bl_limits_t`
+  bl_error_limit,
+} bl_error_kind_t;
+
+/// Index of a path segment that is not an array element
+#define BL_ERROR_NO_INDEX UINT32_MAX
+/// Longest path kept by `bl_error_t`, the outermost segments are dropped
+#define BL_ERROR_MAX_DEPTH 16
+
+typedef struct {
+  const char *field;
+  /// element of the array `field`, or `BL_ERROR_NO_INDEX`
+  uint32_t index;
+} bl_error_segment_t;
+
+/// Where and why a reader failed, filled by readers generated with `-O errors=true`
+typedef struct {
+  bl_error_kind_t kind;
+  /// start of the input, set by the caller so that `offset` can be computed
+  const uint8_t *base;
+  /// offset of the offending byte from `base`
+  uint64_t offset;
+  /// path of the failed value, innermost segment first
+  bl_error_segment_t path[BL_ERROR_MAX_DEPTH];
+  /// number of segments of the path, including the dropped ones
+  uint32_t depth;
+} bl_error_t;
+
+/// Records that reading `field` (or its element `index`) failed with `res` at
+/// the position of `b`. Only the innermost failure sets the kind and offset,
+/// enclosing readers add their field to the path. Does nothing without `err`.
+void bl_error__record(bl_error_t *err, bl_result_t res, bl_error_kind_t kind,
+                      const bl_slice_t *b, const char *field, uint32_t index);
+/// Writes eg. "unexpected end of input at byte 42 (0x2a) in 'types[3].name'"
+/// into `buf`, returns the length of the whole message like `snprintf`
+int bl_error__format(const bl_error_t *err, char *buf, size_t len);
+
+/// Like `BL_TRY_GOTO`, but also records the failure of `field` in `err`, see
+/// `bl_error__record`
+#define BL_TRY_ERR(x, res, label, err, b, kind, field)                         \
+  do {                                                                         \
+    res = (x);                                                                 \
+    if (res <= 0) {                                                            \
+      bl_error__record(err, res, kind, b, field, BL_ERROR_NO_INDEX);           \
+      goto label;                                                              \
+    }                                                                          \
+  } while (0);
+// binlang:end errors
+
+/// `bl_result_err` when the elements of `array` could not be allocated
+#define BL_RESERVED(array)                                                     \
+  (((array)->elems != NULL || (array)->size == 0) ? bl_result_ok               \
+                                                  : bl_result_err)
+
 void bl_slice__advance(bl_slice_t *b, size_t n);
+/// Reads a boolean stored as a single byte, anything other than 0 or 1 is an error
+bl_result_t bl_slice__read_bool(bl_slice_t *b, bool *value);
 /// Reads an unsigned 8-bit
 bl_result_t bl_slice__read_u8(bl_slice_t *b, uint8_t *value);
 /// Reads an unsigned 16-bit (little endian)
@@ -41,22 +139,178 @@ bl_result_t bl_slice__read_u16(bl_slice_t *b, uint16_t *value);
 bl_result_t bl_slice__read_u32(bl_slice_t *b, uint32_t *value);
 /// Reads an unsigned 64-bit (little endian)
 bl_result_t bl_slice__read_u64(bl_slice_t *b, uint64_t *value);
+#ifdef __SIZEOF_INT128__
+/// Reads an unsigned 128-bit (little endian)
+bl_result_t bl_slice__read_u128(bl_slice_t *b, bl_u128_t *value);
+#endif
 /// Reads a signed 8-bit
 bl_result_t bl_slice__read_i8(bl_slice_t *b, int8_t *value);
 /// Reads a signed 32-bit (little endian)
 bl_result_t bl_slice__read_i32(bl_slice_t *b, int32_t *value);
 /// Reads a signed 64-bit (little endian)
 bl_result_t bl_slice__read_i64(bl_slice_t *b, int64_t *value);
+#ifdef __SIZEOF_INT128__
+/// Reads a signed 128-bit (little endian)
+bl_result_t bl_slice__read_i128(bl_slice_t *b, bl_i128_t *value);
+#endif
 /// Reads a LEB128-encoded unsigned 32-bit
 bl_result_t bl_slice__read_vu32(bl_slice_t *b, uint32_t *value);
 /// Reads a LEB128-encoded unsigned 64-bit
 bl_result_t bl_slice__read_vu64(bl_slice_t *b, uint64_t *value);
-/// Reads a LEB128-encoded signed 32-bit
+/// Reads a sign-extended LEB128-encoded signed 32-bit
 bl_result_t bl_slice__read_vi32(bl_slice_t *b, int32_t *value);
-/// Reads a LEB128-encoded signed 64-bit
+/// Reads a sign-extended LEB128-encoded signed 64-bit
 bl_result_t bl_slice__read_vi64(bl_slice_t *b, int64_t *value);
+/// Reads a zigzag LEB128-encoded signed 32-bit (protobuf's `sint32`)
+bl_result_t bl_slice__read_vzi32(bl_slice_t *b, int32_t *value);
+/// Reads a zigzag LEB128-encoded signed 64-bit (protobuf's `sint64`)
+bl_result_t bl_slice__read_vzi64(bl_slice_t *b, int64_t *value);
+/// Read a length field of another type than u32 or vu32 into the size of its
+/// array, failing with `bl_result_err` when it exceeds `UINT32_MAX`
+bl_result_t bl_slice__read_u8_len(bl_slice_t *b, uint32_t *size);
+bl_result_t bl_slice__read_u16_len(bl_slice_t *b, uint32_t *size);
+bl_result_t bl_slice__read_u64_len(bl_slice_t *b, uint32_t *size);
+bl_result_t bl_slice__read_vu64_len(bl_slice_t *b, uint32_t *size);
+#ifdef __SIZEOF_INT128__
+bl_result_t bl_slice__read_u128_len(bl_slice_t *b, uint32_t *size);
+#endif
 /// Copies exactly `len` bytes from `b` into `buf`
 bl_result_t bl_slice__read_exact(bl_slice_t *b, uint8_t *buf, uint64_t len);
+/// Checks a declared count of array elements before they are allocated: fails
+/// with `bl_result_eof` when `count` elements of at least `min_size` encoded
+/// bytes cannot fit in the rest of `b`, and with `bl_result_err` when
+/// allocating them as `elem_size` bytes exceeds `b->limits`. The allocation is
+/// then counted against the total budget.
+bl_result_t bl_slice__check_count(bl_slice_t *b, uint32_t count,
+                                  uint32_t min_size, size_t elem_size);
+/// Points `view` at its `view->size` elements of `stride` bytes each, without copying them
+bl_result_t bl_slice__read_view(bl_slice_t *b, bl_view_t *view, uint32_t stride);
+
+/// Writes `indent` levels of indentation of the generated printers
+void bl_print__indent(FILE *out, int indent);
+/// Writes `len` bytes as a quoted string when they are printable ASCII, as hex
+/// otherwise, eg. `"abc"` or `<01 ff>`
+void bl_print__bytes(FILE *out, const uint8_t *bytes, uint32_t len);
+
+/// JSON text written by the generated `to_json` functions, zero-initialized
+/// before the first value, eg. `bl_json_t json = {.pretty = true};`
+typedef struct {
+  /// the text written so far, NUL-terminated
+  BlVec(char) text;
+  /// indent the members of objects and arrays
+  bool pretty;
+  /// nesting level of the value being written
+  uint32_t depth;
+  /// nothing was written in the current object or array yet
+  bool empty;
+  /// an allocation or the decoding of a view element failed, the text is
+  /// truncated
+  bool failed;
+} bl_json_t;
+
+/// Opens an object (`{`) or an array (`[`)
+void bl_json__begin(bl_json_t *json, char open);
+/// Closes the object (`}`) or the array (`]`) opened last
+void bl_json__end(bl_json_t *json, char close);
+/// Writes the key of the next member of an object
+void bl_json__key(bl_json_t *json, const char *key);
+/// Separates the next element of an array from the previous one
+void bl_json__item(bl_json_t *json);
+void bl_json__bool(bl_json_t *json, bool value);
+void bl_json__uint(bl_json_t *json, uint64_t value);
+void bl_json__int(bl_json_t *json, int64_t value);
+/// Writes `null` for NaN and infinities, which JSON cannot represent
+void bl_json__double(bl_json_t *json, double value);
+#ifdef __SIZEOF_INT128__
+/// Writes a 128-bit integer as a number when it fits in 64 bits, as a decimal
+/// string otherwise since most JSON parsers lose the precision of such numbers
+void bl_json__u128(bl_json_t *json, bl_u128_t value);
+void bl_json__i128(bl_json_t *json, bl_i128_t value);
+#endif
+/// Writes the flags set in `bits` as an array of their `names`, `"bit<n>"`
+/// for the bits without a name (NULL)
+void bl_json__flags(bl_json_t *json, uint8_t bits, const char *const names[8]);
+/// Writes bytes as a string when they are UTF-8 text without control
+/// characters, as an array of numbers otherwise
+void bl_json__bytes(bl_json_t *json, const uint8_t *bytes, uint32_t len);
+/// Releases the text
+void bl_json__delete(bl_json_t *json);
+
+/// Start of the hashes of the generated `hash` functions, which are 64-bit
+/// FNV-1a
+#define BL_HASH_INIT 0xcbf29ce484222325ULL
+
+/// Mixes `len` bytes into `hash`
+uint64_t bl_hash__bytes(uint64_t hash, const void *bytes, size_t len);
+/// Mixes an integer into `hash`
+uint64_t bl_hash__u64(uint64_t hash, uint64_t value);
+/// Mixes a floating-point number into `hash`, 0.0 and -0.0 hashing the same
+/// as they are equal
+uint64_t bl_hash__double(uint64_t hash, double value);
+
+// binlang:begin stream
+/// Deepest nesting of messages a streaming reader can resume
+#define BL_STREAM_MAX_DEPTH 32
+/// Longest value that is not read in pieces (a u128)
+#define BL_STREAM_MAX_PENDING 16
+
+/// Where a streaming reader stopped in a message
+typedef struct {
+  /// next step, 0 being the initialization of the message
+  uint32_t field;
+  /// next element of the array being read
+  uint32_t index;
+  /// elements of the array being read, which grows as they arrive
+  uint32_t count;
+} bl_stream_frame_t;
+
+/// State of a streaming reader, zero-initialized before the first chunk
+typedef struct {
+  bl_stream_frame_t frames[BL_STREAM_MAX_DEPTH];
+  /// first bytes of a value split across chunks
+  uint8_t pending[BL_STREAM_MAX_PENDING];
+  uint32_t pending_len;
+  /// budgets of the readers, see `bl_slice_t`. Arrays grow with the input
+  /// received, but nothing else bounds the memory of a stream: set them when
+  /// the input is untrusted.
+  bl_limits_t *limits;
+  /// allocator of the readers generated with `-O allocator=true`, the global
+  /// hooks when NULL
+  bl_allocator_t *allocator;
+  /// bytes left unread at the end of the last chunk once the message is complete
+  uint32_t rest;
+} bl_stream_t;
+
+/// Like `BL_TRY` for streaming readers: returns on EOF so that the reader can be resumed with
+/// the next chunk, stores the result in `res` and jumps to `label` on errors
+#define BL_STREAM_TRY(x, res, label)                                           \
+  do {                                                                         \
+    res = (x);                                                                 \
+    if (res == bl_result_eof) {                                                \
+      return res;                                                              \
+    }                                                                          \
+    if (res == bl_result_err) {                                                \
+      goto label;                                                              \
+    }                                                                          \
+  } while (0);
+
+typedef bl_result_t (*bl_stream_fn_t)(bl_stream_t *s, bl_slice_t *b,
+                                      void *value);
+/// Resumes `fn` with the next chunk of input, returns `bl_result_eof` until
+/// the message is complete
+bl_result_t bl_stream__feed(bl_stream_t *s, bl_stream_fn_t fn, void *value,
+                            const uint8_t *data, uint32_t len);
+/// Grows `array` of `count` elements to at least `needed` of them (at most
+/// `count`), doubling its size so that it stays within twice the elements
+/// received. New elements are zeroed, `allocator` may be NULL.
+bl_result_t bl_stream__reserve(BlArray *array, size_t elem_size, uint64_t needed,
+                               uint32_t count, bl_allocator_t *allocator);
+/// Copies the bytes of `b` into `buf` until `len` bytes were copied, `done`
+/// counts them
+bl_result_t bl_stream__read_bytes(bl_slice_t *b, uint8_t *buf, uint32_t len,
+                                  uint32_t *done);
+// binlang:end stream
+
 #ifdef FLOAT
 /// Reads a 32-bit floating-point number (little endian)
 bl_result_t bl_slice__read_f32(bl_slice_t *b, f32_t *value);
@@ -1,6 +1,10 @@
+use crate::lexer::Span;
+
 #[derive(Debug)]
 pub struct File {
     pub defs: Vec<TopLevel>,
+    /// comments after the last definition
+    pub end_comments: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -11,27 +15,67 @@ pub enum TopLevel {
 
 #[derive(Debug)]
 pub struct Message {
+    pub decorator: Option<Decorator>,
     pub name: String,
     pub fields: Vec<Field>,
+    /// from the `message` keyword to the closing brace
+    pub span: Span,
+    pub name_span: Span,
+    pub comments: Comments,
+    /// comments between the last field and the closing brace
+    pub end_comments: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct Bitfield {
+    pub decorator: Option<Decorator>,
     pub name: String,
     pub flags: Vec<BitFlag>,
+    /// from the `bitfield` keyword to the closing brace
+    pub span: Span,
+    pub name_span: Span,
+    pub comments: Comments,
+    /// comments between the last flag and the closing brace
+    pub end_comments: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct BitFlag {
     pub name: String,
     pub offset: u8,
+    pub name_span: Span,
+    pub comments: Comments,
 }
 
 #[derive(Debug)]
 pub struct Field {
-    pub decorator: Option<String>,
+    pub decorator: Option<Decorator>,
     pub name: String,
     pub ty: TypeExpr,
+    /// from the name (or decorator) to the end of the type expression
+    pub span: Span,
+    pub name_span: Span,
+    /// span of the element type identifier
+    pub ty_span: Span,
+    /// span of the associated field identifier in `T[field]`
+    pub size_span: Option<Span>,
+    pub comments: Comments,
+}
+
+/// `@name` or `@name("arg")`, applied to the definition or field that follows
+#[derive(Debug, Clone)]
+pub struct Decorator {
+    pub name: String,
+    pub arg: Option<String>,
+}
+
+/// Comment trivia attached to a node, kept verbatim (including the leading `//`)
+#[derive(Debug, Default)]
+pub struct Comments {
+    /// comments on the lines preceding the node
+    pub leading: Vec<String>,
+    /// comment on the same line, after the node
+    pub trailing: Option<String>,
 }
 
 #[derive(Debug)]
@@ -47,20 +91,93 @@ pub enum TypeIdent {
     Custom(String),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NativeType {
+    Bool,
     U8,
     U16,
     U32,
     U64,
+    U128,
     I8,
     I16,
     I32,
     I64,
+    I128,
     VU32,
     VU64,
+    /// sign-extended LEB128
     VI32,
+    /// sign-extended LEB128
     VI64,
+    /// zigzag-encoded LEB128 (protobuf's `sint32`)
+    VZI32,
+    /// zigzag-encoded LEB128 (protobuf's `sint64`)
+    VZI64,
     F32,
     F64,
 }
+
+impl NativeType {
+    /// Whether the values of the type are unsigned integers, the types of length fields
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::U128 | Self::VU32 | Self::VU64
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::VU32 => "vu32",
+            Self::VU64 => "vu64",
+            Self::VI32 => "vi32",
+            Self::VI64 => "vi64",
+            Self::VZI32 => "vzi32",
+            Self::VZI64 => "vzi64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+}
+
+impl std::fmt::Display for TypeIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Native(ty) => f.write_str(ty.name()),
+            Self::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
+impl std::fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident(ty) => write!(f, "{ty}"),
+            Self::ArrayNoField(ty) => write!(f, "{ty}[]"),
+            Self::ArrayWithField(ty, field) => write!(f, "{ty}[{field}]"),
+        }
+    }
+}
+
+impl std::fmt::Display for Decorator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.name)?;
+        if let Some(arg) = &self.arg {
+            let arg = arg.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "(\"{arg}\")")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,445 @@
+//! Compatibility of two versions of a schema.
+//!
+//! Messages are encoded without tags, fields are only identified by their position: names can
+//! change freely but adding, removing or changing the type of a field usually changes how the
+//! following bytes are read. Readers reject trailing bytes
+//! ([`crate::error::DecodeErrorKind::TrailingBytes`]), so even fields added or removed at the end
+//! of a message break the other version.
+
+use std::collections::HashSet;
+
+use crate::schema::{ArrayLength, Bitfield, Definition, Message, NativeType, Schema, Type};
+
+/// How data encoded with one version of a schema can be read with the other one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Compatibility {
+    /// the encoding is unchanged
+    Wire,
+    /// new readers can read old data
+    Backward,
+    /// old readers can read new data
+    Forward,
+    Breaking,
+}
+
+impl Compatibility {
+    /// The compatibility of a set of changes
+    pub fn of<'a>(changes: impl IntoIterator<Item = &'a Change>) -> Self {
+        let (mut backward, mut forward) = (true, true);
+        for change in changes {
+            backward &= matches!(change.compatibility, Self::Wire | Self::Backward);
+            forward &= matches!(change.compatibility, Self::Wire | Self::Forward);
+        }
+        match (backward, forward) {
+            (true, true) => Self::Wire,
+            (true, false) => Self::Backward,
+            (false, true) => Self::Forward,
+            (false, false) => Self::Breaking,
+        }
+    }
+}
+
+impl std::fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Wire => "wire-compatible",
+            Self::Backward => "backward-compatible",
+            Self::Forward => "forward-compatible",
+            Self::Breaking => "breaking",
+        })
+    }
+}
+
+/// A change between two versions of a schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub compatibility: Compatibility,
+    /// what changed, named after the old schema (eg. `Types.nb_types` or `TypeFlags.native`)
+    pub path: String,
+    pub description: String,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<19} {}: {}",
+            self.compatibility.to_string(),
+            self.path,
+            self.description
+        )
+    }
+}
+
+/// Lists the changes between two versions of a schema.
+///
+/// Definitions are matched by name, along with the ones a field switched to (eg. `Type` when
+/// `types: OldType[]` becomes `types: Type[]`). Fields are matched by name, unmatched fields
+/// sitting at the same place are considered renamed.
+pub fn compat(old: &Schema, new: &Schema) -> Vec<Change> {
+    let mut checker = Checker {
+        old_embedded: embedded(old),
+        aligned: Vec::new(),
+        pending: Vec::new(),
+        seen: HashSet::new(),
+        changes: Vec::new(),
+    };
+
+    for old_def in old.definitions() {
+        let name = def_name(old_def);
+        match new.definitions().find(|def| def_name(*def) == name) {
+            Some(new_def) => checker.schedule(old_def, new_def),
+            None if !checker.old_embedded.contains(name) => checker.change(
+                Compatibility::Breaking,
+                name.to_string(),
+                "top-level definition removed".to_string(),
+            ),
+            // its uses are reported as field changes
+            None => {}
+        }
+    }
+    for new_def in new.definitions() {
+        let name = def_name(new_def);
+        if old.definitions().all(|def| def_name(def) != name) {
+            checker.change(
+                Compatibility::Wire,
+                name.to_string(),
+                "definition added".to_string(),
+            );
+        }
+    }
+
+    while let Some((old_def, new_def)) = checker.pending.pop() {
+        match (old_def, new_def) {
+            (Definition::Message(old), Definition::Message(new)) => checker.message(old, new),
+            (Definition::Bitfield(old), Definition::Bitfield(new)) => checker.bitfield(old, new),
+            _ => checker.change(
+                Compatibility::Breaking,
+                def_name(old_def).to_string(),
+                format!("changed from {} to {}", kind(old_def), kind(new_def)),
+            ),
+        }
+    }
+    checker.changes
+}
+
+struct Checker<'o, 'n> {
+    /// messages used by a field in the old schema
+    old_embedded: HashSet<&'o str>,
+    /// fields of the messages being compared matched so far, by name or by position
+    aligned: Vec<(&'o str, &'n str)>,
+    pending: Vec<(Definition<'o>, Definition<'n>)>,
+    seen: HashSet<(&'o str, &'n str)>,
+    changes: Vec<Change>,
+}
+
+impl<'o, 'n> Checker<'o, 'n> {
+    fn change(&mut self, compatibility: Compatibility, path: String, description: String) {
+        self.changes.push(Change {
+            compatibility,
+            path,
+            description,
+        });
+    }
+
+    /// Queues the comparison of two definitions, unless it was already done
+    fn schedule(&mut self, old: Definition<'o>, new: Definition<'n>) {
+        if self.seen.insert((def_name(old), def_name(new))) {
+            self.pending.push((old, new));
+        }
+    }
+
+    fn message(&mut self, old: Message<'o>, new: Message<'n>) {
+        let old_fields: Vec<_> = old.fields().collect();
+        let new_fields: Vec<_> = new.fields().collect();
+        let old_names: Vec<_> = old_fields.iter().map(|field| field.name()).collect();
+        let new_names: Vec<_> = new_fields.iter().map(|field| field.name()).collect();
+        let path = |field: &str| format!("{}.{field}", old.name());
+        self.aligned.clear();
+
+        // fields in both versions keeping their relative order are anchors, the other ones sit in
+        // the gaps between anchors
+        let anchors = lcs(&old_names, &new_names);
+        let (mut i, mut j) = (0, 0);
+        for (anchor_i, anchor_j) in anchors
+            .iter()
+            .copied()
+            .chain([(old_fields.len(), new_fields.len())])
+        {
+            let last_gap = anchor_i == old_fields.len();
+            let mut removed = Vec::new();
+            for field in &old_fields[i..anchor_i] {
+                if new_names.contains(&field.name()) {
+                    self.change(
+                        Compatibility::Breaking,
+                        path(field.name()),
+                        "field moved".to_string(),
+                    );
+                } else {
+                    removed.push(*field);
+                }
+            }
+            let added: Vec<_> = new_fields[j..anchor_j]
+                .iter()
+                .filter(|field| !old_names.contains(&field.name()))
+                .collect();
+
+            // fields replacing each other at the same place are renamed
+            let renamed = removed.len().min(added.len());
+            for (old_field, new_field) in removed.iter().zip(&added) {
+                self.aligned.push((old_field.name(), new_field.name()));
+                self.change(
+                    Compatibility::Wire,
+                    path(old_field.name()),
+                    format!("renamed to '{}'", new_field.name()),
+                );
+                self.field_type(path(old_field.name()), old_field.ty(), new_field.ty());
+            }
+            for field in &removed[renamed..] {
+                self.change(
+                    Compatibility::Breaking,
+                    path(field.name()),
+                    "field removed".to_string(),
+                );
+            }
+            for field in &added[renamed..] {
+                let description = if last_gap {
+                    "field appended"
+                } else {
+                    "field inserted"
+                };
+                let at = format!("{}.{}", old.name(), field.name());
+                self.change(Compatibility::Breaking, at, description.to_string());
+            }
+
+            if !last_gap {
+                self.aligned
+                    .push((old_fields[anchor_i].name(), new_fields[anchor_j].name()));
+                self.field_type(
+                    path(old_fields[anchor_i].name()),
+                    old_fields[anchor_i].ty(),
+                    new_fields[anchor_j].ty(),
+                );
+            }
+            (i, j) = (anchor_i + 1, anchor_j + 1);
+        }
+    }
+
+    fn field_type(&mut self, path: String, old: Type<'o>, new: Type<'n>) {
+        if let Some((compatibility, reason)) = self.classify(old, new) {
+            let description = format!("type changed from {old} to {new} ({reason})");
+            self.change(compatibility, path, description);
+        }
+    }
+
+    /// How reading a value of type `old` as `new` (and the other way around) behaves, `None` when
+    /// the encoding is the same
+    fn classify(&mut self, old: Type<'o>, new: Type<'n>) -> Option<(Compatibility, &'static str)> {
+        match (old, new) {
+            (Type::Native(old), Type::Native(new)) => classify_native(old, new),
+            (Type::Message(old), Type::Message(new)) => {
+                self.schedule(Definition::Message(old), Definition::Message(new));
+                (old.name() != new.name()).then_some((Compatibility::Wire, "renamed type"))
+            }
+            (Type::Bitfield(old), Type::Bitfield(new)) => {
+                self.schedule(Definition::Bitfield(old), Definition::Bitfield(new));
+                (old.name() != new.name()).then_some((Compatibility::Wire, "renamed type"))
+            }
+            (Type::Array(old), Type::Array(new)) => {
+                match (old.length(), new.length()) {
+                    (ArrayLength::Prefixed, ArrayLength::Prefixed) => {}
+                    // length fields come first, they are already aligned
+                    (ArrayLength::Field(old_len), ArrayLength::Field(new_len)) => {
+                        if !self.aligned.contains(&(old_len, new_len)) {
+                            return Some((
+                                Compatibility::Breaking,
+                                "length given by another field",
+                            ));
+                        }
+                    }
+                    _ => return Some((Compatibility::Breaking, "length encoded differently")),
+                }
+                self.classify(old.element(), new.element())
+            }
+            _ => Some((Compatibility::Breaking, "different kind of type")),
+        }
+    }
+
+    fn bitfield(&mut self, old: Bitfield<'o>, new: Bitfield<'n>) {
+        let path = |flag: &str| format!("{}.{flag}", old.name());
+        for flag in old.flags() {
+            if let Some(moved) = new.flags().find(|other| other.name() == flag.name()) {
+                if moved.offset() != flag.offset() {
+                    let description = format!(
+                        "offset changed from {} to {}",
+                        flag.offset(),
+                        moved.offset()
+                    );
+                    self.change(Compatibility::Breaking, path(flag.name()), description);
+                }
+                continue;
+            }
+            let renamed = new.flags().find(|other| {
+                other.offset() == flag.offset() && old.flags().all(|f| f.name() != other.name())
+            });
+            match renamed {
+                Some(renamed) => self.change(
+                    Compatibility::Wire,
+                    path(flag.name()),
+                    format!("renamed to '{}'", renamed.name()),
+                ),
+                None => self.change(
+                    Compatibility::Wire,
+                    path(flag.name()),
+                    "flag removed, its bit is ignored".to_string(),
+                ),
+            }
+        }
+        for flag in new.flags() {
+            let known = old
+                .flags()
+                .any(|other| other.name() == flag.name() || other.offset() == flag.offset());
+            if !known {
+                self.change(
+                    Compatibility::Wire,
+                    path(flag.name()),
+                    format!("flag added at offset {}", flag.offset()),
+                );
+            }
+        }
+    }
+}
+
+fn classify_native(old: NativeType, new: NativeType) -> Option<(Compatibility, &'static str)> {
+    use NativeType::*;
+
+    if old == new {
+        return None;
+    }
+    let widened = |from: NativeType, to: NativeType| {
+        matches!(
+            (from, to),
+            (VU32, VU64) | (VI32, VI64) | (VZI32, VZI64) | (Bool, U8)
+        )
+    };
+    if widened(old, new) {
+        Some((Compatibility::Backward, "widened, old values still decode"))
+    } else if widened(new, old) {
+        Some((Compatibility::Forward, "narrowed, new values still decode"))
+    } else if old.wire_size() == new.wire_size() && old.wire_size().is_fixed() {
+        Some((
+            Compatibility::Breaking,
+            "same size, values are read differently",
+        ))
+    } else {
+        Some((Compatibility::Breaking, "encoded differently"))
+    }
+}
+
+/// Names of the messages used by a field
+fn embedded(schema: &Schema) -> HashSet<&str> {
+    fn visit<'a>(ty: Type<'a>, out: &mut HashSet<&'a str>) {
+        match ty {
+            Type::Message(msg) => {
+                out.insert(msg.name());
+            }
+            Type::Array(array) => visit(array.element(), out),
+            Type::Native(_) | Type::Bitfield(_) => {}
+        }
+    }
+
+    let mut out = HashSet::new();
+    for msg in schema.messages() {
+        for field in msg.fields() {
+            visit(field.ty(), &mut out);
+        }
+    }
+    out
+}
+
+fn def_name(def: Definition<'_>) -> &str {
+    match def {
+        Definition::Message(msg) => msg.name(),
+        Definition::Bitfield(bf) => bf.name(),
+    }
+}
+
+fn kind(def: Definition<'_>) -> &'static str {
+    match def {
+        Definition::Message(_) => "message",
+        Definition::Bitfield(_) => "bitfield",
+    }
+}
+
+/// Index pairs of a longest common subsequence of `a` and `b`
+fn lcs(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[test]
+fn classify_changes() {
+    let old = crate::compile([crate::Source::new(
+        "old.bl",
+        "message Root { header: Header, a: u16, b: vu32, c: u8, d: u8[], }
+         message Header { magic: u16, }
+         message Len { n: u32, m: u32, d: u8[n], }
+         message Tail { a: u8, b: u32, }
+         bitfield Flags { x: 0, y: 1, z: 2, }",
+    )])
+    .unwrap();
+    let new = crate::compile([crate::Source::new(
+        "new.bl",
+        "message Root { head: Head, a: u32, b: vu64, d: u8[], c: u8, e: vi32, }
+         message Head { magic: u16, version: u8, }
+         message Len { n: u32, m: u32, d: u8[m], }
+         message Tail { a: u8, }
+         bitfield Flags { x: 0, w: 1, z: 3, }",
+    )])
+    .unwrap();
+
+    let mut changes: Vec<_> = compat(&old, &new).iter().map(ToString::to_string).collect();
+    changes.sort();
+    let expected = [
+        "backward-compatible Root.b: type changed from vu32 to vu64 (widened, old values still decode)",
+        "breaking            Flags.z: offset changed from 2 to 3",
+        "breaking            Header.version: field appended",
+        "breaking            Len.d: type changed from u8[n] to u8[m] (length given by another field)",
+        "breaking            Root.a: type changed from u16 to u32 (encoded differently)",
+        "breaking            Root.c: field moved",
+        "breaking            Root.e: field appended",
+        "breaking            Tail.b: field removed",
+        "wire-compatible     Flags.y: renamed to 'w'",
+        "wire-compatible     Head: definition added",
+        "wire-compatible     Root.header: renamed to 'head'",
+        "wire-compatible     Root.header: type changed from Header to Head (renamed type)",
+    ];
+    assert_eq!(changes, expected);
+    assert_eq!(
+        Compatibility::of(&compat(&old, &new)),
+        Compatibility::Breaking
+    );
+    assert!(compat(&old, &old).is_empty());
+}
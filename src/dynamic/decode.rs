@@ -0,0 +1,358 @@
+use crate::{
+    error::{DecodeError, DecodeErrorKind},
+    schema::{ArrayLength, Bitfield, Message, NativeType, Schema, Type},
+};
+
+use super::{Node, Path, Segment, Value};
+
+/// Maximum nesting of messages, guards against recursive schemas
+const MAX_DEPTH: usize = 256;
+
+/// Decodes `bytes` as an instance of the message `root`, the whole input must be consumed
+pub fn decode(schema: &Schema, root: &str, bytes: &[u8]) -> Result<Node, DecodeError> {
+    let Some(msg) = schema.message(root) else {
+        return Err(DecodeError {
+            path: String::new(),
+            offset: 0,
+            kind: DecodeErrorKind::UnknownMessage(root.to_string()),
+            partial: None,
+        });
+    };
+
+    let mut decoder = Decoder {
+        bytes,
+        offset: 0,
+        path: Path::default(),
+        depth: 0,
+    };
+    let node = decoder.message(msg)?;
+    if decoder.offset < bytes.len() {
+        let trailing = bytes.len() - decoder.offset;
+        let mut err = decoder.error(DecodeErrorKind::TrailingBytes(trailing));
+        err.partial = Some(Box::new(node));
+        return Err(err);
+    }
+    Ok(node)
+}
+
+struct Decoder<'a, 's> {
+    bytes: &'a [u8],
+    offset: usize,
+    path: Path<'s>,
+    depth: usize,
+}
+
+impl<'s> Decoder<'_, 's> {
+    fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        self.error_at(self.offset, kind)
+    }
+
+    fn error_at(&self, offset: usize, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError {
+            path: self.path.to_string(),
+            offset,
+            kind,
+            partial: None,
+        }
+    }
+
+    /// Stores the values of the current container decoded so far as the partial result of `err`
+    fn partial(&self, mut err: DecodeError, start: usize, value: Value) -> DecodeError {
+        err.partial = Some(Box::new(Node {
+            value,
+            range: start..self.offset,
+        }));
+        err
+    }
+
+    fn message(&mut self, msg: Message<'s>) -> Result<Node, DecodeError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(DecodeErrorKind::TooDeep));
+        }
+        self.depth += 1;
+
+        let start = self.offset;
+        let mut fields: Vec<(String, Node)> = Vec::new();
+        for field in msg.fields() {
+            self.path.push(Segment::Field(field.name()));
+            let node = match self.value(field.ty(), &fields) {
+                Ok(node) => node,
+                Err(mut err) => {
+                    if let Some(child) = err.partial.take() {
+                        fields.push((field.name().to_string(), *child));
+                    }
+                    return Err(self.partial(err, start, Value::Message(fields)));
+                }
+            };
+            self.path.pop();
+            fields.push((field.name().to_string(), node));
+        }
+
+        self.depth -= 1;
+        Ok(Node {
+            value: Value::Message(fields),
+            range: start..self.offset,
+        })
+    }
+
+    /// Decodes a value of type `ty`, `siblings` are the previous fields of the enclosing message
+    fn value(&mut self, ty: Type<'s>, siblings: &[(String, Node)]) -> Result<Node, DecodeError> {
+        let start = self.offset;
+        let value = match ty {
+            Type::Native(ty) => self.native(ty)?,
+            Type::Message(msg) => return self.message(msg),
+            Type::Bitfield(bitfield) => self.bitfield(bitfield)?,
+            Type::Array(array) => {
+                let len = match array.length() {
+                    ArrayLength::Prefixed => u32::from_le_bytes(self.take()?) as usize,
+                    ArrayLength::Field(name) => {
+                        let value = siblings
+                            .iter()
+                            .find(|(sibling, _)| sibling == name)
+                            .map(|(_, node)| &node.value)
+                            .expect("length fields are checked by the compiler");
+                        value.as_len().ok_or_else(|| {
+                            self.error(DecodeErrorKind::InvalidLength(value.to_string()))
+                        })?
+                    }
+                };
+                self.array(array.element(), len, start)?
+            }
+        };
+        Ok(Node {
+            value,
+            range: start..self.offset,
+        })
+    }
+
+    fn array(&mut self, elem: Type<'s>, len: usize, start: usize) -> Result<Value, DecodeError> {
+        if let Type::Native(NativeType::U8) = elem {
+            return Ok(Value::Bytes(self.slice(len)?.to_vec()));
+        }
+
+        // the length comes from the input, do not trust it: each element takes at least a byte,
+        // even those encoded as nothing which could otherwise be repeated forever
+        let remaining = self.bytes.len() - self.offset;
+        if len > remaining / elem.wire_size().min.max(1) {
+            return Err(self.error_at(start, DecodeErrorKind::InvalidLength(len.to_string())));
+        }
+        let mut items = Vec::with_capacity(len);
+        for i in 0..len {
+            self.path.push(Segment::Index(i));
+            match self.value(elem, &[]) {
+                Ok(item) => items.push(item),
+                Err(mut err) => {
+                    items.extend(err.partial.take().map(|item| *item));
+                    return Err(self.partial(err, start, Value::Array(items)));
+                }
+            }
+            self.path.pop();
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn bitfield(&mut self, bitfield: Bitfield<'s>) -> Result<Value, DecodeError> {
+        let [bits] = self.take()?;
+        let names = (0..8)
+            .filter(|offset| bits & (1 << offset) != 0)
+            .map(|offset| {
+                bitfield
+                    .flags()
+                    .find(|flag| flag.offset() == offset)
+                    .map_or_else(|| format!("bit{offset}"), |flag| flag.name().to_string())
+            })
+            .collect();
+        Ok(Value::Flags { bits, names })
+    }
+
+    fn native(&mut self, ty: NativeType) -> Result<Value, DecodeError> {
+        let start = self.offset;
+        let value = match ty {
+            NativeType::Bool => match self.take()? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                [byte] => return Err(self.error_at(start, DecodeErrorKind::InvalidBool(byte))),
+            },
+            NativeType::U8 => Value::UInt(u8::from_le_bytes(self.take()?).into()),
+            NativeType::U16 => Value::UInt(u16::from_le_bytes(self.take()?).into()),
+            NativeType::U32 => Value::UInt(u32::from_le_bytes(self.take()?).into()),
+            NativeType::U64 => Value::UInt(u64::from_le_bytes(self.take()?).into()),
+            NativeType::U128 => Value::UInt(u128::from_le_bytes(self.take()?)),
+            NativeType::I8 => Value::Int(i8::from_le_bytes(self.take()?).into()),
+            NativeType::I16 => Value::Int(i16::from_le_bytes(self.take()?).into()),
+            NativeType::I32 => Value::Int(i32::from_le_bytes(self.take()?).into()),
+            NativeType::I64 => Value::Int(i64::from_le_bytes(self.take()?).into()),
+            NativeType::I128 => Value::Int(i128::from_le_bytes(self.take()?)),
+            NativeType::F32 => Value::Float(f32::from_le_bytes(self.take()?).into()),
+            NativeType::F64 => Value::Float(f64::from_le_bytes(self.take()?)),
+            NativeType::VU32 => Value::UInt(self.varint::<u32>(5)?.0.into()),
+            NativeType::VU64 => Value::UInt(self.varint::<u64>(10)?.0.into()),
+            NativeType::VI32 => Value::Int(self.signed_varint::<i32>(5)?.into()),
+            NativeType::VI64 => Value::Int(self.signed_varint::<i64>(10)?.into()),
+            NativeType::VZI32 => {
+                let n = self.varint::<u32>(5)?.0;
+                Value::Int(((n >> 1) as i32 ^ -((n & 1) as i32)).into())
+            }
+            NativeType::VZI64 => {
+                let n = self.varint::<u64>(10)?.0;
+                Value::Int(((n >> 1) as i64 ^ -((n & 1) as i64)).into())
+            }
+        };
+        Ok(value)
+    }
+
+    /// Reads an unsigned LEB128 of at most `max_len` bytes, returns its value and bit length
+    fn varint<T: TryFrom<u64>>(&mut self, max_len: usize) -> Result<(T, u32), DecodeError> {
+        let start = self.offset;
+        // at most 10 bytes, 70 bits
+        let mut result = 0u128;
+        let mut shift = 0;
+        // errors point at the first byte of the varint, which is only consumed once complete
+        for (i, &byte) in self.bytes[start..].iter().take(max_len).enumerate() {
+            result |= u128::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                self.offset += i + 1;
+                return u64::try_from(result)
+                    .ok()
+                    .and_then(|value| T::try_from(value).ok())
+                    .map(|value| (value, shift))
+                    .ok_or_else(|| self.error_at(start, DecodeErrorKind::VarintOverflow));
+            }
+        }
+        if self.bytes.len() - start < max_len {
+            return Err(self.error_at(start, DecodeErrorKind::UnexpectedEof { needed: 1 }));
+        }
+        Err(self.error_at(start, DecodeErrorKind::VarintOverflow))
+    }
+
+    /// Reads a sign-extended LEB128 of at most `max_len` bytes
+    fn signed_varint<T: TryFrom<i64>>(&mut self, max_len: usize) -> Result<T, DecodeError> {
+        let start = self.offset;
+        let (raw, shift) = self.varint::<u64>(max_len)?;
+        let mut value = raw as i64;
+        if shift < 64 && raw & (1 << (shift - 1)) != 0 {
+            value |= -1 << shift;
+        }
+        T::try_from(value).map_err(|_| self.error_at(start, DecodeErrorKind::VarintOverflow))
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.slice(N)?.try_into().unwrap())
+    }
+
+    fn slice(&mut self, len: usize) -> Result<&[u8], DecodeError> {
+        let remaining = self.bytes.len() - self.offset;
+        if len > remaining {
+            return Err(self.error(DecodeErrorKind::UnexpectedEof {
+                needed: len - remaining,
+            }));
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+}
+
+#[test]
+fn decode_values() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root {
+           header: u16,
+           size  : vu32,
+           name  : u8[size],
+           delta : vi32,
+           zz    : vzi64,
+           flags : Flags,
+           items : Item[],
+         }
+         message Item { ok: bool, }
+         bitfield Flags { a: 0, c: 2, }",
+    )])
+    .unwrap();
+
+    let bytes = [
+        0x01,
+        0x02, // header
+        0x02,
+        b'h',
+        b'i',        // size + name
+        0x7F,        // delta = -1
+        0x03,        // zz = -2
+        0b1000_0101, // flags
+        0x02,
+        0x00,
+        0x00,
+        0x00,
+        0x01,
+        0x00, // items
+    ];
+    let root = decode(&schema, "Root", &bytes).unwrap();
+    assert_eq!(root.range, 0..bytes.len());
+    assert_eq!(
+        root.to_json(),
+        serde_json::json!({
+            "header": 0x0201,
+            "size": 2,
+            "name": "hi",
+            "delta": -1,
+            "zz": -2,
+            "flags": ["a", "c", "bit7"],
+            "items": [{ "ok": true }, { "ok": false }],
+        })
+    );
+    let Value::Message(fields) = &root.value else {
+        panic!("expected a message");
+    };
+    assert_eq!(fields[2].1.range, 3..5);
+}
+
+#[test]
+fn decode_errors() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root { items: Item[], }
+         message Item { ok: bool, n: vu32, }",
+    )])
+    .unwrap();
+
+    let err = decode(&schema, "Root", &[0x02, 0, 0, 0, 0x01, 0x00, 0x02, 0x00]).unwrap_err();
+    assert_eq!(err.path, "items[1].ok");
+    assert_eq!(err.offset, 6);
+    assert_eq!(err.kind, DecodeErrorKind::InvalidBool(2));
+
+    let err = decode(&schema, "Root", &[0x01, 0, 0, 0, 0x01, 0xFF]).unwrap_err();
+    assert_eq!(err.path, "items[0].n");
+    assert_eq!(err.kind, DecodeErrorKind::UnexpectedEof { needed: 1 });
+
+    let err = decode(
+        &schema,
+        "Root",
+        &[0x01, 0, 0, 0, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F],
+    );
+    assert_eq!(err.unwrap_err().kind, DecodeErrorKind::VarintOverflow);
+
+    let err = decode(&schema, "Root", &[0x00, 0, 0, 0, 0x00]).unwrap_err();
+    assert_eq!(
+        (err.offset, err.kind),
+        (4, DecodeErrorKind::TrailingBytes(1))
+    );
+
+    let err = decode(&schema, "Root", &[0x03, 0, 0, 0, 0x01, 0x00]).unwrap_err();
+    assert_eq!(
+        (err.offset, err.kind),
+        (0, DecodeErrorKind::InvalidLength("3".into()))
+    );
+
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root { xs: Empty[], } message Empty { }",
+    )])
+    .unwrap();
+    let err = decode(&schema, "Root", &[0xFF, 0xFF, 0xFF, 0x7F]).unwrap_err();
+    assert_eq!(
+        err.kind,
+        DecodeErrorKind::InvalidLength("2147483647".into())
+    );
+}
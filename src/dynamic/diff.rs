@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::schema::{Schema, Type};
+
+use super::{Node, Path, Segment, Value};
+
+/// How two decoded messages are compared
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// arrays of the messages named by the keys are aligned by the value of the given field
+    /// instead of by index, eg. `Type` → `name`
+    pub keys: BTreeMap<String, String>,
+    /// fields left out of the comparison, as `Message.field`
+    pub ignore: BTreeSet<String>,
+}
+
+/// A difference between two decoded messages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// path of the value (eg. `types.types[12].flags`), keyed array elements are written
+    /// `types.types[name=12]`
+    pub path: String,
+    /// `None` when the value was added
+    pub old: Option<String>,
+    /// `None` when the value was removed
+    pub new: Option<String>,
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "~ {}: {old} -> {new}", self.path),
+            (Some(old), None) => write!(f, "- {}: {old}", self.path),
+            (None, Some(new)) => write!(f, "+ {}: {new}", self.path),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// Compares two decoded `root` messages field by field, returns the differences in wire order
+pub fn diff(
+    schema: &Schema,
+    root: &str,
+    old: &Node,
+    new: &Node,
+    options: &DiffOptions,
+) -> Vec<Difference> {
+    let Some(msg) = schema.message(root) else {
+        return Vec::new();
+    };
+    let mut differ = Differ {
+        options,
+        path: Path::default(),
+        differences: Vec::new(),
+    };
+    differ.node(Type::Message(msg), old, new);
+    differ.differences
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    path: Path<'a>,
+    differences: Vec<Difference>,
+}
+
+impl<'a> Differ<'a> {
+    fn node(&mut self, ty: Type<'_>, old: &'a Node, new: &'a Node) {
+        match (ty, &old.value, &new.value) {
+            (Type::Message(msg), Value::Message(old_fields), Value::Message(new_fields)) => {
+                for (name, old) in old_fields {
+                    let Some(field) = msg.field(name) else {
+                        continue;
+                    };
+                    if self
+                        .options
+                        .ignore
+                        .contains(&format!("{}.{name}", msg.name()))
+                    {
+                        continue;
+                    }
+                    let new = new_fields.iter().find(|(other, _)| other == name);
+                    self.path.push(Segment::Field(name));
+                    match new {
+                        Some((_, new)) => self.node(field.ty(), old, new),
+                        None => self.removed(old),
+                    }
+                    self.path.pop();
+                }
+            }
+            (Type::Array(array), Value::Array(old_items), Value::Array(new_items)) => {
+                let key = match array.element() {
+                    Type::Message(msg) => self.options.keys.get(msg.name()),
+                    _ => None,
+                };
+                match key
+                    .and_then(|key| Some((key, keyed(old_items, key)?, keyed(new_items, key)?)))
+                {
+                    Some((key, old_keys, new_keys)) => self.keyed(
+                        array.element(),
+                        key,
+                        old_items,
+                        new_items,
+                        old_keys,
+                        new_keys,
+                    ),
+                    None => self.indexed(array.element(), old_items, new_items),
+                }
+            }
+            _ => {
+                if old.value != new.value {
+                    self.differences.push(Difference {
+                        path: self.path.to_string(),
+                        old: Some(old.value.to_string()),
+                        new: Some(new.value.to_string()),
+                    });
+                }
+            }
+        }
+    }
+
+    fn indexed(&mut self, elem: Type<'_>, old: &'a [Node], new: &'a [Node]) {
+        for i in 0..old.len().max(new.len()) {
+            self.path.push(Segment::Index(i));
+            match (old.get(i), new.get(i)) {
+                (Some(old), Some(new)) => self.node(elem, old, new),
+                (Some(old), None) => self.removed(old),
+                (None, Some(new)) => self.added(new),
+                (None, None) => unreachable!(),
+            }
+            self.path.pop();
+        }
+    }
+
+    /// Compares the elements with the same key in the old order, then lists the added ones
+    fn keyed(
+        &mut self,
+        elem: Type<'_>,
+        key: &'a str,
+        old: &'a [Node],
+        new: &'a [Node],
+        old_keys: HashMap<String, usize>,
+        new_keys: HashMap<String, usize>,
+    ) {
+        let mut old_order: Vec<_> = old_keys.iter().collect();
+        old_order.sort_by_key(|(_, i)| **i);
+        for (value, i) in old_order {
+            self.path.push(Segment::Key(key, value.clone()));
+            match new_keys.get(value) {
+                Some(j) => self.node(elem, &old[*i], &new[*j]),
+                None => self.removed(&old[*i]),
+            }
+            self.path.pop();
+        }
+
+        let mut added: Vec<_> = new_keys
+            .iter()
+            .filter(|(value, _)| !old_keys.contains_key(*value))
+            .collect();
+        added.sort_by_key(|(_, i)| **i);
+        for (value, i) in added {
+            self.path.push(Segment::Key(key, value.clone()));
+            self.added(&new[*i]);
+            self.path.pop();
+        }
+    }
+
+    fn removed(&mut self, node: &Node) {
+        self.differences.push(Difference {
+            path: self.path.to_string(),
+            old: Some(node.value.to_string()),
+            new: None,
+        });
+    }
+
+    fn added(&mut self, node: &Node) {
+        self.differences.push(Difference {
+            path: self.path.to_string(),
+            old: None,
+            new: Some(node.value.to_string()),
+        });
+    }
+}
+
+/// Indexes the elements by the value of their `key` field, `None` if the keys are not unique
+fn keyed(items: &[Node], key: &str) -> Option<HashMap<String, usize>> {
+    let mut keys = HashMap::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let Value::Message(fields) = &item.value else {
+            return None;
+        };
+        let (_, value) = fields.iter().find(|(name, _)| name == key)?;
+        if keys.insert(value.value.to_string(), i).is_some() {
+            return None;
+        }
+    }
+    Some(keys)
+}
+
+#[test]
+fn diff_by_key() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root { crc: u8, items: Item[], }
+         message Item { id: u8, v: vu32, }",
+    )])
+    .unwrap();
+    let decode = |bytes: &[u8]| super::decode(&schema, "Root", bytes).unwrap();
+    let old = decode(&[0x01, 0x03, 0, 0, 0, 0x01, 0x0A, 0x02, 0x0B, 0x03, 0x0C]);
+    let new = decode(&[
+        0x02, 0x03, 0, 0, 0, 0x02, 0x0B, 0x01, 0x80, 0x01, 0x04, 0x0D,
+    ]);
+
+    let to_strings =
+        |diffs: Vec<Difference>| diffs.iter().map(ToString::to_string).collect::<Vec<_>>();
+    let by_index = diff(&schema, "Root", &old, &new, &DiffOptions::default());
+    assert_eq!(to_strings(by_index).len(), 7);
+
+    let options = DiffOptions {
+        keys: [("Item".to_string(), "id".to_string())].into(),
+        ignore: ["Root.crc".to_string()].into(),
+    };
+    let by_key = diff(&schema, "Root", &old, &new, &options);
+    assert_eq!(
+        to_strings(by_key),
+        [
+            "~ items[id=1].v: 10 -> 128",
+            "- items[id=3]: {2 field(s)}",
+            "+ items[id=4]: {2 field(s)}",
+        ]
+    );
+}
@@ -0,0 +1,338 @@
+use serde_json::Value as Json;
+
+use crate::{
+    error::{EncodeError, EncodeErrorKind},
+    schema::{ArrayLength, Bitfield, Message, NativeType, Schema, Type},
+};
+
+use super::{Path, Segment};
+
+/// Encodes `value`, a document describing an instance of the message `root`.
+///
+/// The document uses the representation produced by [`Node::to_json`](super::Node::to_json),
+/// except that the fields giving the length of an array can be omitted.
+pub fn encode(schema: &Schema, root: &str, value: &Json) -> Result<Vec<u8>, EncodeError> {
+    let Some(msg) = schema.message(root) else {
+        return Err(EncodeError {
+            path: String::new(),
+            kind: EncodeErrorKind::UnknownMessage(root.to_string()),
+        });
+    };
+
+    let mut encoder = Encoder {
+        out: Vec::new(),
+        path: Path::default(),
+    };
+    encoder.message(msg, value)?;
+    Ok(encoder.out)
+}
+
+struct Encoder<'s> {
+    out: Vec<u8>,
+    path: Path<'s>,
+}
+
+impl<'s> Encoder<'s> {
+    fn error(&self, kind: EncodeErrorKind) -> EncodeError {
+        EncodeError {
+            path: self.path.to_string(),
+            kind,
+        }
+    }
+
+    fn invalid_type(&self, expected: &'static str, got: &Json) -> EncodeError {
+        let got = match got {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        };
+        self.error(EncodeErrorKind::InvalidType {
+            expected,
+            got: got.to_string(),
+        })
+    }
+
+    fn message(&mut self, msg: Message<'s>, value: &Json) -> Result<(), EncodeError> {
+        let Json::Object(map) = value else {
+            return Err(self.invalid_type("an object", value));
+        };
+        if let Some(name) = map.keys().find(|name| msg.field(name).is_none()) {
+            return Err(self.error(EncodeErrorKind::UnknownField(name.clone())));
+        }
+
+        for field in msg.fields() {
+            let given = map.get(field.name());
+            // length fields are filled from the array they size
+            let len = field
+                .length_of()
+                .map(|array| map.get(array).and_then(array_len).ok_or(array));
+            let value = match (given, len) {
+                (Some(value), Some(Ok(len))) => {
+                    if value.as_u64() != Some(len as u64) {
+                        self.path.push(Segment::Field(field.name()));
+                        return Err(self.error(EncodeErrorKind::LengthMismatch {
+                            expected: len,
+                            got: value.to_string(),
+                        }));
+                    }
+                    value.clone()
+                }
+                (Some(value), _) => value.clone(),
+                (None, Some(Ok(len))) => Json::from(len),
+                (None, Some(Err(array))) => {
+                    return Err(self.error(EncodeErrorKind::MissingField(array.to_string())));
+                }
+                (None, None) => {
+                    return Err(self.error(EncodeErrorKind::MissingField(field.name().to_string())));
+                }
+            };
+
+            self.path.push(Segment::Field(field.name()));
+            self.value(field.ty(), &value)?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, ty: Type<'s>, value: &Json) -> Result<(), EncodeError> {
+        match ty {
+            Type::Native(ty) => self.native(ty, value),
+            Type::Message(msg) => self.message(msg, value),
+            Type::Bitfield(bitfield) => self.bitfield(bitfield, value),
+            Type::Array(array) => {
+                let elem = array.element();
+                if let (Type::Native(NativeType::U8), Json::String(text)) = (elem, value) {
+                    if array.length() == ArrayLength::Prefixed {
+                        self.prefix(text.len())?;
+                    }
+                    self.out.extend_from_slice(text.as_bytes());
+                    return Ok(());
+                }
+
+                let Json::Array(items) = value else {
+                    return Err(self.invalid_type("an array", value));
+                };
+                if array.length() == ArrayLength::Prefixed {
+                    self.prefix(items.len())?;
+                }
+                for (i, item) in items.iter().enumerate() {
+                    self.path.push(Segment::Index(i));
+                    self.value(elem, item)?;
+                    self.path.pop();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes the `u32` element count of a `T[]` array
+    fn prefix(&mut self, len: usize) -> Result<(), EncodeError> {
+        let len = u32::try_from(len).map_err(|_| {
+            self.error(EncodeErrorKind::OutOfRange {
+                ty: "u32",
+                value: len.to_string(),
+            })
+        })?;
+        self.out.extend_from_slice(&len.to_le_bytes());
+        Ok(())
+    }
+
+    fn bitfield(&mut self, bitfield: Bitfield<'s>, value: &Json) -> Result<(), EncodeError> {
+        let bits = match value {
+            Json::Number(_) => self.integer::<u8>(value, "u8")?,
+            Json::Array(names) => {
+                let mut bits = 0u8;
+                for name in names {
+                    let Json::String(name) = name else {
+                        return Err(self.invalid_type("a flag name", name));
+                    };
+                    let offset = bitfield
+                        .flags()
+                        .find(|flag| flag.name() == name)
+                        .map(|flag| flag.offset())
+                        .or_else(|| name.strip_prefix("bit")?.parse().ok())
+                        .filter(|offset| *offset < 8)
+                        .ok_or_else(|| self.error(EncodeErrorKind::UnknownFlag(name.clone())))?;
+                    bits |= 1 << offset;
+                }
+                bits
+            }
+            _ => return Err(self.invalid_type("an array of flag names", value)),
+        };
+        self.out.push(bits);
+        Ok(())
+    }
+
+    fn native(&mut self, ty: NativeType, value: &Json) -> Result<(), EncodeError> {
+        let name = ty.name();
+        match ty {
+            NativeType::Bool => match value {
+                Json::Bool(b) => self.out.push(*b as u8),
+                _ => return Err(self.invalid_type("a boolean", value)),
+            },
+            NativeType::U8 => self.write(self.integer::<u8>(value, name)?.to_le_bytes()),
+            NativeType::U16 => self.write(self.integer::<u16>(value, name)?.to_le_bytes()),
+            NativeType::U32 => self.write(self.integer::<u32>(value, name)?.to_le_bytes()),
+            NativeType::U64 => self.write(self.integer::<u64>(value, name)?.to_le_bytes()),
+            NativeType::U128 => self.write(self.integer::<u128>(value, name)?.to_le_bytes()),
+            NativeType::I8 => self.write(self.integer::<i8>(value, name)?.to_le_bytes()),
+            NativeType::I16 => self.write(self.integer::<i16>(value, name)?.to_le_bytes()),
+            NativeType::I32 => self.write(self.integer::<i32>(value, name)?.to_le_bytes()),
+            NativeType::I64 => self.write(self.integer::<i64>(value, name)?.to_le_bytes()),
+            NativeType::I128 => self.write(self.integer::<i128>(value, name)?.to_le_bytes()),
+            NativeType::F32 => self.write((self.float(value)? as f32).to_le_bytes()),
+            NativeType::F64 => self.write(self.float(value)?.to_le_bytes()),
+            NativeType::VU32 => self.varint(self.integer::<u32>(value, name)?.into()),
+            NativeType::VU64 => self.varint(self.integer::<u64>(value, name)?),
+            NativeType::VI32 => self.signed_varint(self.integer::<i32>(value, name)?.into()),
+            NativeType::VI64 => self.signed_varint(self.integer::<i64>(value, name)?),
+            NativeType::VZI32 => {
+                let n = self.integer::<i32>(value, name)?;
+                self.varint(((n << 1) ^ (n >> 31)) as u32 as u64)
+            }
+            NativeType::VZI64 => {
+                let n = self.integer::<i64>(value, name)?;
+                self.varint(((n << 1) ^ (n >> 63)) as u64)
+            }
+        }
+        Ok(())
+    }
+
+    fn write<const N: usize>(&mut self, bytes: [u8; N]) {
+        self.out.extend_from_slice(&bytes);
+    }
+
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.out.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.out.push(n as u8);
+    }
+
+    fn signed_varint(&mut self, mut n: i64) {
+        loop {
+            let byte = n as u8 & 0x7F;
+            n >>= 7;
+            if (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0) {
+                self.out.push(byte);
+                return;
+            }
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    /// Reads an integer of type `T`, 128-bit integers can also be given as strings
+    fn integer<T>(&self, value: &Json, ty: &'static str) -> Result<T, EncodeError>
+    where
+        T: TryFrom<i128> + TryFrom<u128>,
+    {
+        let out_of_range = || {
+            self.error(EncodeErrorKind::OutOfRange {
+                ty,
+                value: value.to_string(),
+            })
+        };
+        let n = match value {
+            Json::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(n), _) => T::try_from(u128::from(n)).ok(),
+                (None, Some(n)) => T::try_from(i128::from(n)).ok(),
+                (None, None) => return Err(self.invalid_type("an integer", value)),
+            },
+            Json::String(text) => match (text.parse::<u128>(), text.parse::<i128>()) {
+                (Ok(n), _) => T::try_from(n).ok(),
+                (Err(_), Ok(n)) => T::try_from(n).ok(),
+                _ => return Err(self.invalid_type("an integer", value)),
+            },
+            _ => return Err(self.invalid_type("an integer", value)),
+        };
+        n.ok_or_else(out_of_range)
+    }
+
+    /// Reads a float, `null` stands for NaN as JSON cannot represent it
+    fn float(&self, value: &Json) -> Result<f64, EncodeError> {
+        match value {
+            Json::Number(n) => Ok(n.as_f64().unwrap()),
+            Json::Null => Ok(f64::NAN),
+            _ => Err(self.invalid_type("a number", value)),
+        }
+    }
+}
+
+/// Number of elements of an array value, or of bytes of a string standing for a `u8` array
+fn array_len(value: &Json) -> Option<usize> {
+    match value {
+        Json::Array(items) => Some(items.len()),
+        Json::String(text) => Some(text.len()),
+        _ => None,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root {
+           size  : vu32,
+           name  : u8[size],
+           delta : vi32,
+           zz    : vzi64,
+           big   : u128,
+           flags : Flags,
+           items : Item[],
+         }
+         message Item { ok: bool, f: f32, }
+         bitfield Flags { a: 0, c: 2, }",
+    )])
+    .unwrap();
+
+    let document = serde_json::json!({
+        "name": "hi",
+        "delta": -300,
+        "zz": i64::MIN,
+        "big": u128::MAX.to_string(),
+        "flags": ["c", "bit7"],
+        "items": [{ "ok": true, "f": 1.5 }],
+    });
+    let bytes = encode(&schema, "Root", &document).unwrap();
+    assert_eq!(bytes[..6], [0x02, b'h', b'i', 0xD4, 0x7D, 0xFF]);
+
+    let decoded = super::decode(&schema, "Root", &bytes).unwrap().to_json();
+    assert_eq!(decoded["size"], 2);
+    for field in ["name", "delta", "zz", "big", "flags", "items"] {
+        assert_eq!(decoded[field], document[field], "{field}");
+    }
+    assert_eq!(encode(&schema, "Root", &decoded).unwrap(), bytes);
+}
+
+#[test]
+fn encode_errors() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root { n: u8, items: Item[n], }
+         message Item { v: u8, }",
+    )])
+    .unwrap();
+
+    let err = encode(
+        &schema,
+        "Root",
+        &serde_json::json!({ "items": [{ "v": 256 }] }),
+    );
+    let err = err.unwrap_err();
+    assert_eq!(err.path, "items[0].v");
+    assert!(matches!(
+        err.kind,
+        EncodeErrorKind::OutOfRange { ty: "u8", .. }
+    ));
+
+    let err = encode(&schema, "Root", &serde_json::json!({ "n": 2, "items": [] }));
+    assert_eq!(err.unwrap_err().path, "n");
+
+    let err = encode(&schema, "Root", &serde_json::json!({ "n": 0 }));
+    let kind = err.unwrap_err().kind;
+    assert_eq!(kind, EncodeErrorKind::MissingField("items".to_string()));
+}
@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+use crate::schema::{ArrayLength, Schema, Type};
+
+use super::{Node, Path, Segment, Value};
+
+/// The value covering a byte, along with the containers it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub field: Location,
+    /// enclosing messages and arrays, innermost first, the last one being the root message
+    pub containers: Vec<Location>,
+}
+
+/// A value of a decoded message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    /// path of the value (eg. `types.types[12].flags`), empty for the root message
+    pub path: String,
+    pub range: Range<usize>,
+    /// type as written in the schema (eg. `u8[size]`)
+    pub ty: String,
+    pub value: String,
+}
+
+/// Finds the value of `node`, a decoded `root` message, that covers the byte at `offset`.
+///
+/// Returns `None` when `root` is unknown or `offset` is not covered by `node`, which can happen
+/// with the partial result of a failed decoding.
+pub fn explain(schema: &Schema, root: &str, node: &Node, offset: usize) -> Option<Explanation> {
+    let root = schema.message(root)?;
+    if !node.range.contains(&offset) {
+        return None;
+    }
+    let mut path = Path::default();
+    let mut containers = Vec::new();
+    let mut node = node;
+    let mut ty = Type::Message(root);
+
+    loop {
+        let location = |path: &Path, node: &Node, ty: String| Location {
+            path: path.to_string(),
+            range: node.range.clone(),
+            ty,
+            value: node.value.to_string(),
+        };
+
+        let child = match (&node.value, ty) {
+            (Value::Message(fields), Type::Message(msg)) => fields
+                .iter()
+                .find(|(_, child)| child.range.contains(&offset))
+                .and_then(|(name, child)| {
+                    let ty = msg.field(name)?.ty();
+                    Some((Segment::Field(name), child, ty))
+                }),
+            (Value::Array(items), Type::Array(array)) => {
+                let first = items
+                    .first()
+                    .map_or(node.range.end, |item| item.range.start);
+                if array.length() == ArrayLength::Prefixed && offset < first {
+                    // the element count preceding the items
+                    containers.insert(0, location(&path, node, ty.to_string()));
+                    path.push(Segment::Field("count"));
+                    let field = Location {
+                        path: path.to_string(),
+                        range: node.range.start..first,
+                        ty: "u32".to_string(),
+                        value: items.len().to_string(),
+                    };
+                    return Some(Explanation { field, containers });
+                }
+                items
+                    .iter()
+                    .position(|item| item.range.contains(&offset))
+                    .map(|i| (Segment::Index(i), &items[i], array.element()))
+            }
+            _ => None,
+        };
+
+        let Some((segment, child, child_ty)) = child else {
+            let field = location(&path, node, ty.to_string());
+            return Some(Explanation { field, containers });
+        };
+        containers.insert(0, location(&path, node, ty.to_string()));
+        path.push(segment);
+        node = child;
+        ty = child_ty;
+    }
+}
+
+#[test]
+fn explain_offset() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root { n: u16, items: Item[], }
+         message Item { a: u8, b: vu32, }",
+    )])
+    .unwrap();
+    let bytes = [
+        0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x05, 0x7F, 0x06, 0x80, 0x01,
+    ];
+    let node = super::decode(&schema, "Root", &bytes).unwrap();
+
+    let explanation = explain(&schema, "Root", &node, 10).unwrap();
+    assert_eq!(explanation.field.path, "items[1].b");
+    assert_eq!(explanation.field.range, 9..11);
+    assert_eq!(explanation.field.ty, "vu32");
+    assert_eq!(explanation.field.value, "128");
+    let containers: Vec<_> = explanation
+        .containers
+        .iter()
+        .map(|c| (c.path.as_str(), c.ty.as_str(), c.range.clone()))
+        .collect();
+    assert_eq!(
+        containers,
+        [
+            ("items[1]", "Item", 8..11),
+            ("items", "Item[]", 2..11),
+            ("", "Root", 0..11)
+        ]
+    );
+
+    let explanation = explain(&schema, "Root", &node, 3).unwrap();
+    assert_eq!(explanation.field.path, "items.count");
+    assert_eq!(explanation.field.range, 2..6);
+    assert_eq!(explain(&schema, "Root", &node, 11), None);
+}
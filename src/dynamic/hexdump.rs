@@ -0,0 +1,211 @@
+use std::fmt::Write as _;
+
+use crate::error::DecodeError;
+
+use super::{Node, Value};
+
+/// Bytes shown per line
+const WIDTH: usize = 16;
+/// Longest value shown next to the bytes, longer ones are truncated
+const MAX_VALUE_LEN: usize = 48;
+
+const RESET: &str = "\x1b[0m";
+const ERROR: &str = "\x1b[1;31m";
+const DIM: &str = "\x1b[2m";
+const PALETTE: [&str; 6] = [
+    "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[36m", "\x1b[91m",
+];
+
+/// Renders `bytes` grouped by the values of `decoded`, nested by message.
+///
+/// Each line shows an offset, up to 16 bytes and, for the first line of a value, its path and
+/// decoded value. When decoding failed, the values decoded before the error are shown followed by
+/// the bytes starting at the offending one. With `color`, values are told apart by ANSI colours.
+pub fn hexdump(
+    bytes: &[u8],
+    root: &str,
+    decoded: &Result<Node, DecodeError>,
+    color: bool,
+) -> String {
+    let mut dump = HexDump {
+        bytes,
+        color,
+        out: String::new(),
+        leaves: 0,
+    };
+    dump.write(root, decoded).unwrap();
+    dump.out
+}
+
+struct HexDump<'a> {
+    bytes: &'a [u8],
+    color: bool,
+    out: String,
+    /// number of values written so far, used to pick their colour
+    leaves: usize,
+}
+
+impl HexDump<'_> {
+    fn write(&mut self, root: &str, decoded: &Result<Node, DecodeError>) -> std::fmt::Result {
+        let err = match decoded {
+            Ok(node) => return self.node(root, node, 0),
+            Err(err) => err,
+        };
+        if let Some(partial) = &err.partial {
+            self.node(root, partial, 0)?;
+        }
+
+        let end = (err.offset + WIDTH).min(self.bytes.len());
+        let mut hex = self.bytes[err.offset..end]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if hex.is_empty() {
+            hex.push_str("<end>");
+        }
+        let hex = format!("{hex:width$}", width = hex_width(WIDTH));
+        let (error, reset) = self.style(ERROR);
+        // highlight the offending byte
+        let (first, rest) = hex.split_at(2);
+        writeln!(
+            self.out,
+            "{:08x}  {error}{first}{reset}{rest}  {error}^ {err}{reset}",
+            err.offset
+        )
+    }
+
+    fn node(&mut self, name: &str, node: &Node, depth: usize) -> std::fmt::Result {
+        let indent = depth * 2;
+        let (dim, reset) = self.style(DIM);
+        let children: Vec<(String, &Node)> = match &node.value {
+            Value::Message(fields) => fields
+                .iter()
+                .map(|(name, node)| (name.clone(), node))
+                .collect(),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| (format!("[{i}]"), item))
+                .collect(),
+            _ => return self.leaf(name, node, depth),
+        };
+
+        let size = node.range.len();
+        writeln!(
+            self.out,
+            "{:08x}  {:width$}  {:indent$}{name}: {dim}{} ({size} bytes){reset}",
+            node.range.start,
+            "",
+            "",
+            node.value,
+            width = hex_width(WIDTH),
+        )?;
+
+        // bytes of the container that are not part of a child, eg. the count of a `T[]` array
+        let mut cursor = node.range.start;
+        for (name, child) in children {
+            if child.range.start > cursor {
+                self.bytes(cursor, child.range.start, "count", depth + 1, None)?;
+            }
+            self.node(&name, child, depth + 1)?;
+            cursor = child.range.end;
+        }
+        if node.range.end > cursor && !matches!(node.value, Value::Message(_)) {
+            self.bytes(cursor, node.range.end, "count", depth + 1, None)?;
+        }
+        Ok(())
+    }
+
+    fn leaf(&mut self, name: &str, node: &Node, depth: usize) -> std::fmt::Result {
+        let mut value = node.value.to_string();
+        if value.chars().count() > MAX_VALUE_LEN {
+            value = value.chars().take(MAX_VALUE_LEN - 3).collect::<String>() + "...";
+        }
+        let color = PALETTE[self.leaves % PALETTE.len()];
+        self.leaves += 1;
+        self.bytes(
+            node.range.start,
+            node.range.end,
+            name,
+            depth,
+            Some((color, &value)),
+        )
+    }
+
+    /// Writes the bytes `start..end` labelled with `name` and an optional coloured value
+    fn bytes(
+        &mut self,
+        start: usize,
+        end: usize,
+        name: &str,
+        depth: usize,
+        value: Option<(&str, &str)>,
+    ) -> std::fmt::Result {
+        let (color, reset) = match value {
+            Some((color, _)) => self.style(color),
+            None => self.style(DIM),
+        };
+        let mut offset = start;
+        loop {
+            let line_end = (offset + WIDTH).min(end);
+            let hex = self.bytes[offset..line_end]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            write!(
+                self.out,
+                "{offset:08x}  {color}{hex:width$}{reset}",
+                width = hex_width(WIDTH)
+            )?;
+            if offset == start {
+                write!(
+                    self.out,
+                    "  {:indent$}{color}{name}{reset}",
+                    "",
+                    indent = depth * 2
+                )?;
+                if let Some((_, value)) = value {
+                    write!(self.out, ": {value}")?;
+                }
+            }
+            writeln!(self.out)?;
+
+            offset = line_end;
+            if offset >= end {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The escape sequences to start and end `style`, empty without colours
+    fn style<'s>(&self, style: &'s str) -> (&'s str, &'static str) {
+        if self.color { (style, RESET) } else { ("", "") }
+    }
+}
+
+/// Width of `len` bytes written as space separated hex pairs
+fn hex_width(len: usize) -> usize {
+    (len * 3).saturating_sub(1)
+}
+
+#[test]
+fn points_at_error() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Root { n: u16, items: Item[], }
+         message Item { a: u8, b: vu32, }",
+    )])
+    .unwrap();
+    let bytes = [0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x05, 0x7F, 0x06, 0xFF];
+
+    let decoded = super::decode(&schema, "Root", &bytes);
+    let dump = hexdump(&bytes, "Root", &decoded, false);
+    let lines: Vec<_> = dump.lines().map(str::trim_end).collect();
+    assert_eq!(lines[1], format!("00000000  01 00{:46}n: 1", ""));
+    assert_eq!(lines[3], format!("00000002  02 00 00 00{:42}count", ""));
+    assert!(lines[8].ends_with("a: 6"));
+    assert!(lines[9].starts_with("00000009  ff "));
+    assert!(lines[9].ends_with("in 'items[1].b'"));
+}
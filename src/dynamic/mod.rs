@@ -0,0 +1,175 @@
+//! Schema-driven handling of binary data, without generating any code.
+
+use std::{fmt::Write as _, ops::Range};
+
+mod decode;
+mod diff;
+mod encode;
+mod explain;
+mod hexdump;
+
+pub use decode::decode;
+pub use diff::{DiffOptions, Difference, diff};
+pub use encode::encode;
+pub use explain::{Explanation, Location, explain};
+pub use hexdump::hexdump;
+
+/// A decoded value along with the bytes it was decoded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub value: Value,
+    /// byte range of the value in the input
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    UInt(u128),
+    Int(i128),
+    Float(f64),
+    /// a `u8` array
+    Bytes(Vec<u8>),
+    /// the value of a bitfield, `names` lists the bits that are set (`bitN` for the unnamed ones)
+    Flags {
+        bits: u8,
+        names: Vec<String>,
+    },
+    Array(Vec<Node>),
+    /// the fields of a message, in wire order
+    Message(Vec<(String, Node)>),
+}
+
+impl Value {
+    /// The value as an array length, `None` if it is not a non-negative integer
+    pub fn as_len(&self) -> Option<usize> {
+        match self {
+            Self::UInt(n) => usize::try_from(*n).ok(),
+            Self::Int(n) => usize::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::UInt(n) => write!(f, "{n}"),
+            Self::Int(n) => write!(f, "{n}"),
+            Self::Float(n) => write!(f, "{n}"),
+            Self::Bytes(bytes) => match printable(bytes) {
+                Some(text) => write!(f, "{text:?}"),
+                None => write!(f, "{bytes:02x?}"),
+            },
+            Self::Flags { names, .. } => write!(f, "{{{}}}", names.join(", ")),
+            Self::Array(items) => write!(f, "[{} item(s)]", items.len()),
+            Self::Message(fields) => write!(f, "{{{} field(s)}}", fields.len()),
+        }
+    }
+}
+
+impl Node {
+    /// Converts the node to JSON, `u8` arrays become strings when they hold printable UTF-8
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value as Json;
+
+        match &self.value {
+            Value::Bool(b) => Json::Bool(*b),
+            Value::UInt(n) => match u64::try_from(*n) {
+                Ok(n) => n.into(),
+                Err(_) => n.to_string().into(),
+            },
+            Value::Int(n) => match i64::try_from(*n) {
+                Ok(n) => n.into(),
+                Err(_) => n.to_string().into(),
+            },
+            Value::Float(n) => serde_json::Number::from_f64(*n).map_or(Json::Null, Json::Number),
+            Value::Bytes(bytes) => match printable(bytes) {
+                Some(text) => text.into(),
+                None => bytes.iter().copied().collect(),
+            },
+            Value::Flags { names, .. } => names.iter().map(String::as_str).collect(),
+            Value::Array(items) => items.iter().map(Node::to_json).collect(),
+            Value::Message(fields) => fields
+                .iter()
+                .map(|(name, node)| (name.clone(), node.to_json()))
+                .collect(),
+        }
+    }
+
+    /// Renders the node as an indented tree, one value per line along with its byte range
+    pub fn to_text(&self, name: &str) -> String {
+        let mut out = String::new();
+        self.write_text(name, 0, &mut out).unwrap();
+        out
+    }
+
+    fn write_text(&self, name: &str, depth: usize, out: &mut String) -> std::fmt::Result {
+        let Range { start, end } = self.range;
+        write!(
+            out,
+            "{:indent$}{name}: {}",
+            "",
+            self.value,
+            indent = depth * 2
+        )?;
+        writeln!(out, " [{start:#x}..{end:#x}]")?;
+
+        match &self.value {
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    item.write_text(&format!("[{i}]"), depth + 1, out)?;
+                }
+            }
+            Value::Message(fields) => {
+                for (name, node) in fields {
+                    node.write_text(name, depth + 1, out)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The bytes as text if they are valid UTF-8 without control characters
+fn printable(bytes: &[u8]) -> Option<&str> {
+    let text = std::str::from_utf8(bytes).ok()?;
+    (!text.chars().any(char::is_control)).then_some(text)
+}
+
+/// Path to the value being processed, used to report errors (eg. `types.types[12].flags`)
+#[derive(Default)]
+struct Path<'a>(Vec<Segment<'a>>);
+
+enum Segment<'a> {
+    Field(&'a str),
+    Index(usize),
+    /// an array element identified by the value of one of its fields
+    Key(&'a str, String),
+}
+
+impl<'a> Path<'a> {
+    fn push(&mut self, segment: Segment<'a>) {
+        self.0.push(segment);
+    }
+
+    fn pop(&mut self) {
+        self.0.pop();
+    }
+}
+
+impl std::fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Field(name) if i == 0 => write!(f, "{name}")?,
+                Segment::Field(name) => write!(f, ".{name}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+                Segment::Key(field, value) => write!(f, "[{field}={value}]")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Token, TokenKind, lexer::Span};
+use crate::{Node, Token, TokenKind, lexer::Span};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ParseError {
@@ -8,6 +8,18 @@ pub enum ParseError {
     Eof,
 }
 
+impl ParseError {
+    /// Location of the error, `None` when the end of the source was reached
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::UnexpectedToken { got, .. } => Some(got.span),
+            Self::UnexpectedIdent { span, .. } => Some(*span),
+            Self::InvalidNumber(span) => Some(*span),
+            Self::Eof => None,
+        }
+    }
+}
+
 impl std::error::Error for ParseError {}
 
 impl std::fmt::Display for ParseError {
@@ -26,3 +38,217 @@ impl std::fmt::Display for ParseError {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HirError {
+    UndefinedType {
+        name: String,
+        span: Span,
+    },
+    UnknownField {
+        name: String,
+        array: String,
+        span: Span,
+    },
+    DuplicateDefinition {
+        name: String,
+        span: Span,
+    },
+    LengthAfterArray {
+        name: String,
+        array: String,
+        span: Span,
+    },
+    InvalidLengthType {
+        name: String,
+        ty: String,
+        span: Span,
+    },
+}
+
+impl HirError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UndefinedType { span, .. }
+            | Self::UnknownField { span, .. }
+            | Self::DuplicateDefinition { span, .. }
+            | Self::LengthAfterArray { span, .. }
+            | Self::InvalidLengthType { span, .. } => *span,
+        }
+    }
+}
+
+impl std::error::Error for HirError {}
+
+impl std::fmt::Display for HirError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedType { name, span } => {
+                write!(f, "use of undefined type '{name}' at {span}")
+            }
+            Self::UnknownField { name, array, span } => write!(
+                f,
+                "referenced field '{name}' in array '{array}' is unknown at {span}"
+            ),
+            Self::DuplicateDefinition { name, span } => {
+                write!(f, "'{name}' is defined multiple times at {span}")
+            }
+            Self::LengthAfterArray { name, array, span } => write!(
+                f,
+                "length field '{name}' must come before array '{array}' at {span}"
+            ),
+            Self::InvalidLengthType { name, ty, span } => write!(
+                f,
+                "length field '{name}' has type '{ty}', expected an unsigned integer at {span}"
+            ),
+        }
+    }
+}
+
+/// A compilation error located in one of the compiled sources
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// name of the source the error originates from
+    pub source: String,
+    /// `None` when the error is located at the end of the source
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+/// All the errors reported while compiling a schema
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl std::error::Error for Diagnostics {}
+
+impl std::fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, diagnostic) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An error raised while decoding binary data with a schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    /// path of the field being decoded (eg. `types.types[12].flags`), empty for the root message
+    pub path: String,
+    /// offset of the offending byte in the input
+    pub offset: usize,
+    pub kind: DecodeErrorKind,
+    /// what was decoded before the error, the last value of each container leads to the failing one
+    pub partial: Option<Box<Node>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    UnknownMessage(String),
+    UnexpectedEof { needed: usize },
+    VarintOverflow,
+    InvalidBool(u8),
+    InvalidLength(String),
+    TooDeep,
+    TrailingBytes(usize),
+}
+
+impl std::error::Error for DecodeError {}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DecodeErrorKind::UnknownMessage(name) => return write!(f, "unknown message '{name}'"),
+            DecodeErrorKind::UnexpectedEof { needed } => {
+                write!(f, "unexpected end of input, {needed} more byte(s) needed")?
+            }
+            DecodeErrorKind::VarintOverflow => write!(f, "varint overflows its type")?,
+            DecodeErrorKind::InvalidBool(byte) => write!(f, "invalid bool value {byte:#04x}")?,
+            DecodeErrorKind::InvalidLength(value) => write!(f, "invalid array length {value}")?,
+            DecodeErrorKind::TooDeep => write!(f, "messages are nested too deeply")?,
+            DecodeErrorKind::TrailingBytes(len) => write!(f, "{len} unexpected trailing byte(s)")?,
+        }
+        write!(f, " at byte {} ({:#x})", self.offset, self.offset)?;
+        if !self.path.is_empty() {
+            write!(f, " in '{}'", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error raised while encoding a document with a schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodeError {
+    /// path of the value being encoded (eg. `types.types[12].flags`), empty for the root message
+    pub path: String,
+    pub kind: EncodeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodeErrorKind {
+    UnknownMessage(String),
+    InvalidType { expected: &'static str, got: String },
+    OutOfRange { ty: &'static str, value: String },
+    MissingField(String),
+    UnknownField(String),
+    UnknownFlag(String),
+    LengthMismatch { expected: usize, got: String },
+}
+
+impl std::error::Error for EncodeError {}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            EncodeErrorKind::UnknownMessage(name) => write!(f, "unknown message '{name}'")?,
+            EncodeErrorKind::InvalidType { expected, got } => {
+                write!(f, "expected {expected}, got {got}")?
+            }
+            EncodeErrorKind::OutOfRange { ty, value } => {
+                write!(f, "{value} is out of the range of {ty}")?
+            }
+            EncodeErrorKind::MissingField(name) => write!(f, "missing field '{name}'")?,
+            EncodeErrorKind::UnknownField(name) => write!(f, "unknown field '{name}'")?,
+            EncodeErrorKind::UnknownFlag(name) => write!(f, "unknown flag '{name}'")?,
+            EncodeErrorKind::LengthMismatch { expected, got } => {
+                write!(f, "length is {got} but the array has {expected} element(s)")?
+            }
+        }
+        if !self.path.is_empty() {
+            write!(f, " in '{}'", self.path)?;
+        }
+        Ok(())
+    }
+}
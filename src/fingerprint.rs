@@ -0,0 +1,135 @@
+//! Stable hash of the wire layout of a message.
+//!
+//! The layout is first written in a canonical form, then hashed with 64-bit FNV-1a so that the
+//! result does not depend on the platform nor on the compiler version. Without names, two messages
+//! have the same fingerprint when they are encoded the same way.
+
+use std::fmt::Write as _;
+
+use crate::schema::{ArrayLength, Field, Message, Type};
+
+/// The canonical layout of `msg`, eg. `{vu32;[@0]u8}` (`Symbol{size:vu32;text:[@0]u8}` with
+/// names).
+///
+/// Array lengths given by a field refer to it by index, and a message nested in itself refers to
+/// its enclosing occurrence by depth (`^0` for the innermost one).
+pub fn layout(msg: Message<'_>, names: bool) -> String {
+    let mut out = String::new();
+    let mut stack = Vec::new();
+    write_message(msg, names, &mut stack, &mut out).unwrap();
+    out
+}
+
+/// Hash of the canonical layout of `msg`
+pub fn fingerprint(msg: Message<'_>, names: bool) -> u64 {
+    fnv1a(layout(msg, names).as_bytes())
+}
+
+fn write_message<'a>(
+    msg: Message<'a>,
+    names: bool,
+    stack: &mut Vec<&'a str>,
+    out: &mut String,
+) -> std::fmt::Result {
+    if let Some(depth) = stack.iter().rev().position(|name| *name == msg.name()) {
+        return write!(out, "^{depth}");
+    }
+    stack.push(msg.name());
+
+    if names {
+        out.push_str(msg.name());
+    }
+    out.push('{');
+    let fields: Vec<_> = msg.fields().collect();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(';');
+        }
+        if names {
+            write!(out, "{}:", field.name())?;
+        }
+        write_type(field.ty(), &fields, names, stack, out)?;
+    }
+    out.push('}');
+
+    stack.pop();
+    Ok(())
+}
+
+fn write_type<'a>(
+    ty: Type<'a>,
+    fields: &[Field<'a>],
+    names: bool,
+    stack: &mut Vec<&'a str>,
+    out: &mut String,
+) -> std::fmt::Result {
+    match ty {
+        Type::Native(ty) => out.push_str(ty.name()),
+        Type::Message(msg) => write_message(msg, names, stack, out)?,
+        Type::Bitfield(bitfield) => {
+            if names {
+                out.push_str(bitfield.name());
+            }
+            let mut flags: Vec<_> = bitfield.flags().collect();
+            flags.sort_by_key(|flag| flag.offset());
+            out.push('(');
+            for (i, flag) in flags.iter().enumerate() {
+                if i > 0 {
+                    out.push(';');
+                }
+                if names {
+                    write!(out, "{}:", flag.name())?;
+                }
+                write!(out, "{}", flag.offset())?;
+            }
+            out.push(')');
+        }
+        Type::Array(array) => {
+            match array.length() {
+                ArrayLength::Prefixed => out.push_str("[]"),
+                ArrayLength::Field(name) => {
+                    let index = fields
+                        .iter()
+                        .position(|field| field.name() == name)
+                        .unwrap();
+                    write!(out, "[@{index}]")?;
+                }
+            }
+            write_type(array.element(), fields, names, stack, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[test]
+fn canonical_layout() {
+    let schema = crate::compile([crate::Source::new(
+        "test.bl",
+        "message Symbol { size: vu32, text: u8[size], flags: Flags, }
+         message Text { len: vu32, bytes: u8[len], f: Flags, }
+         message Tree { children: Tree[], }
+         bitfield Flags { b: 3, a: 0, }",
+    )])
+    .unwrap();
+    let symbol = schema.message("Symbol").unwrap();
+    let text = schema.message("Text").unwrap();
+
+    assert_eq!(layout(symbol, false), "{vu32;[@0]u8;(0;3)}");
+    assert_eq!(
+        layout(symbol, true),
+        "Symbol{size:vu32;text:[@0]u8;flags:Flags(a:0;b:3)}"
+    );
+    assert_eq!(layout(schema.message("Tree").unwrap(), false), "{[]^0}");
+    assert_eq!(fingerprint(symbol, false), fingerprint(text, false));
+    assert_ne!(fingerprint(symbol, true), fingerprint(text, true));
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+}
@@ -0,0 +1,159 @@
+use std::fmt::Write as _;
+
+use crate::{ast::*, error::ParseError, parser::parse};
+
+const INDENT: &str = "  ";
+
+/// Formats a binlang source into its canonical layout:
+///  - one blank line between definitions
+///  - entries indented by two spaces, colons aligned, always followed by a comma
+///  - comments are kept where they were attached
+pub fn format(source: &str) -> Result<String, ParseError> {
+    let file = parse(source)?;
+    let mut out = String::with_capacity(source.len());
+
+    for (i, def) in file.defs.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match def {
+            TopLevel::Message(msg) => format_message(msg, &mut out),
+            TopLevel::Bitfield(bitfield) => format_bitfield(bitfield, &mut out),
+        }
+    }
+
+    if !file.end_comments.is_empty() {
+        if !file.defs.is_empty() {
+            out.push('\n');
+        }
+        for comment in &file.end_comments {
+            writeln!(out, "{comment}").unwrap();
+        }
+    }
+
+    Ok(out)
+}
+
+fn format_message(msg: &Message, out: &mut String) {
+    let entries: Vec<_> = msg
+        .fields
+        .iter()
+        .map(|field| {
+            let key = match &field.decorator {
+                Some(decorator) => format!("{decorator} {}", field.name),
+                None => field.name.clone(),
+            };
+            (key, field.ty.to_string(), &field.comments)
+        })
+        .collect();
+
+    format_block(
+        msg.decorator.as_ref(),
+        "message",
+        &msg.name,
+        &msg.comments,
+        &entries,
+        &msg.end_comments,
+        out,
+    );
+}
+
+fn format_bitfield(bitfield: &Bitfield, out: &mut String) {
+    let entries: Vec<_> = bitfield
+        .flags
+        .iter()
+        .map(|flag| (flag.name.clone(), flag.offset.to_string(), &flag.comments))
+        .collect();
+
+    format_block(
+        bitfield.decorator.as_ref(),
+        "bitfield",
+        &bitfield.name,
+        &bitfield.comments,
+        &entries,
+        &bitfield.end_comments,
+        out,
+    );
+}
+
+fn format_block(
+    decorator: Option<&Decorator>,
+    keyword: &str,
+    name: &str,
+    comments: &Comments,
+    entries: &[(String, String, &Comments)],
+    end_comments: &[String],
+    out: &mut String,
+) {
+    for comment in &comments.leading {
+        writeln!(out, "{comment}").unwrap();
+    }
+    if let Some(decorator) = decorator {
+        write!(out, "{decorator} ").unwrap();
+    }
+    write!(out, "{keyword} {name} {{").unwrap();
+    format_trailing(comments, out);
+
+    let width = entries
+        .iter()
+        .map(|(key, _, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (key, value, comments) in entries {
+        for comment in &comments.leading {
+            writeln!(out, "{INDENT}{comment}").unwrap();
+        }
+        write!(out, "{INDENT}{key:<width$}: {value},").unwrap();
+        format_trailing(comments, out);
+    }
+
+    for comment in end_comments {
+        writeln!(out, "{INDENT}{comment}").unwrap();
+    }
+    out.push_str("}\n");
+}
+
+fn format_trailing(comments: &Comments, out: &mut String) {
+    if let Some(comment) = &comments.trailing {
+        write!(out, " {comment}").unwrap();
+    }
+    out.push('\n');
+}
+
+#[test]
+fn canonical_layout() {
+    let source = "// the root
+message Abi{ headers:Headers, // first
+  @foo symbols : Symbol[],
+        types:u8[nb]
+// dangling
+}
+@c_name( \"flags_t\" )
+bitfield Flags {
+  native: 0, enum: 2 }
+// the end";
+
+    let expected = "// the root
+message Abi {
+  headers     : Headers, // first
+  @foo symbols: Symbol[],
+  types       : u8[nb],
+  // dangling
+}
+
+@c_name(\"flags_t\") bitfield Flags {
+  native: 0,
+  enum  : 2,
+}
+
+// the end
+";
+    assert_eq!(format(source).unwrap(), expected);
+}
+
+#[test]
+fn idempotent() {
+    let source = include_str!("../examples/greycat_abi.bl");
+    let once = format(source).unwrap();
+    assert_eq!(format(&once).unwrap(), once);
+}
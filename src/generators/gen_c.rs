@@ -1,271 +1,2263 @@
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
-use std::fmt::Write as _;
-use std::fs::File;
-use std::io::Write;
-use std::io::{BufReader, BufWriter, LineWriter};
-use std::path::Path;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use topological_sort::TopologicalSort;
 
+use super::{Generator, Options, OutputFiles, write_files};
 use crate::hir::*;
 use crate::symbols::SymbolId;
-use crate::{ast::*, error::ParseError, parser::parse};
+use crate::{Schema, Source, ast::*, compile, error::ParseError, parser::parse, schema};
+
+/// Generates the C deserializer (`<name>.h` and `<name>.c`) of a schema
+pub struct CGenerator;
+
+/// How the generated readers store arrays, set with `-O mode=copy|view`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// every array is copied into memory from `alloc.h`
+    Copy,
+    /// byte arrays and arrays of fixed-size elements are `bl_view_t`s pointing into the decoded
+    /// buffer, other arrays are copied
+    View,
+}
+
+/// The keys of the `-O` options of the C generator, read by `Config` and `Namespace`
+const OPTIONS: [&str; 10] = [
+    "fingerprint",
+    "mode",
+    "stream",
+    "errors",
+    "single_header",
+    "allocator",
+    "prefix",
+    "type_suffix",
+    "case",
+    "linkage",
+];
+
+/// The options of the C generator
+#[derive(Debug, Clone, Copy)]
+struct Config {
+    /// include the names in the fingerprints, `-O fingerprint=layout|names`
+    fingerprint_names: bool,
+    mode: Mode,
+    /// also generate readers resumed chunk by chunk, `-O stream=true`
+    stream: bool,
+    /// readers record why and where they failed in a `bl_error_t`, `-O errors=true`
+    errors: bool,
+    /// generate a single stb-style header embedding the runtime, `-O single_header=true`
+    single_header: bool,
+    /// readers, destructors and clones take a `bl_allocator_t`, `-O allocator=true`
+    allocator: bool,
+}
+
+impl Config {
+    fn parse(options: &Options) -> Result<Self> {
+        if let Some(key) = options.keys().find(|key| !OPTIONS.contains(&key.as_str())) {
+            anyhow::bail!(
+                "unknown option '{key}', expected one of: {}",
+                OPTIONS.join(", ")
+            );
+        }
+        let fingerprint_names = match options.get("fingerprint").map(String::as_str) {
+            None | Some("layout") => false,
+            Some("names") => true,
+            Some(other) => anyhow::bail!("invalid fingerprint '{other}', expected layout or names"),
+        };
+        let mode = match options.get("mode").map(String::as_str) {
+            None | Some("copy") => Mode::Copy,
+            Some("view") => Mode::View,
+            Some(other) => anyhow::bail!("invalid mode '{other}', expected copy or view"),
+        };
+        let stream = match options.get("stream").map(String::as_str) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => anyhow::bail!("invalid stream '{other}', expected true or false"),
+        };
+        let errors = match options.get("errors").map(String::as_str) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => anyhow::bail!("invalid errors '{other}', expected true or false"),
+        };
+        let single_header = match options.get("single_header").map(String::as_str) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => {
+                anyhow::bail!("invalid single_header '{other}', expected true or false")
+            }
+        };
+        let allocator = match options.get("allocator").map(String::as_str) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => anyhow::bail!("invalid allocator '{other}', expected true or false"),
+        };
+        if stream && mode == Mode::View {
+            // views would point into chunks the caller is free to reuse
+            anyhow::bail!("stream=true cannot be used with mode=view");
+        }
+        Ok(Self {
+            fingerprint_names,
+            mode,
+            stream,
+            errors,
+            single_header,
+            allocator,
+        })
+    }
+
+    /// Whether the generated code uses the section `name` of the runtime, see `write_runtime`
+    fn runtime_section(&self, name: &str) -> bool {
+        match name {
+            "allocator" => self.allocator,
+            "errors" => self.errors,
+            "stream" => self.stream,
+            _ => true,
+        }
+    }
+
+    /// `, allocator` when the generated functions take an allocator
+    fn allocator_arg(&self, allocator: &str) -> String {
+        if self.allocator {
+            format!(", {allocator}")
+        } else {
+            String::new()
+        }
+    }
+
+    /// The statement allocating the `size` elements of `array`
+    fn reserve(&self, array: &str, size: &str, allocator: &str) -> String {
+        if self.allocator {
+            format!("array_reserve_in(&{array}, {size}, {allocator});")
+        } else {
+            format!("array_reserve(&{array}, {size});")
+        }
+    }
+
+    /// The statement releasing the elements of `array`
+    fn delete(&self, array: &str, allocator: &str) -> String {
+        if self.allocator {
+            format!("array_delete_in(&{array}, {allocator});")
+        } else {
+            format!("array_delete(&{array});")
+        }
+    }
+}
+
+/// How the names of the generated types and functions are cased, `-O case=snake|camel|pascal`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    /// `type_flags`
+    Snake,
+    /// `typeFlags`
+    Camel,
+    /// `TypeFlags`
+    Pascal,
+}
+
+/// Where the generated functions are defined, `-O linkage=extern|static_inline`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Linkage {
+    /// in `<name>.c`, declared in the header
+    Extern,
+    /// `static inline` in the header, no `<name>.c` is generated
+    StaticInline,
+}
+
+/// Names of the generated C symbols, from the naming options and the `@c_name("...")` decorators
+/// of the definitions.
+///
+/// Displayed as the namespace of the functions, eg. `bl_greycat_abi` in
+/// `bl_greycat_abi__read_abi`.
+#[derive(Debug)]
+struct Namespace {
+    /// `<prefix>_<name>`, `-O prefix=bl`
+    name: String,
+    /// appended to the type names, `-O type_suffix=_t`
+    type_suffix: String,
+    case: Case,
+    linkage: Linkage,
+    /// messages and bitfields named with `@c_name`
+    c_names: HashMap<String, String>,
+}
+
+impl Namespace {
+    fn new(filename: &str, hir: &Hir, options: &Options) -> Result<Self> {
+        let prefix = options.get("prefix").map_or("bl", String::as_str);
+        if !prefix.is_empty() && !is_c_ident(prefix) {
+            anyhow::bail!("invalid prefix '{prefix}', expected a C identifier");
+        }
+        let type_suffix = options.get("type_suffix").map_or("_t", String::as_str);
+        if !type_suffix
+            .chars()
+            .all(|c| c == '_' || c.is_ascii_alphanumeric())
+        {
+            anyhow::bail!("invalid type_suffix '{type_suffix}', expected identifier characters");
+        }
+        let case = match options.get("case").map(String::as_str) {
+            None | Some("snake") => Case::Snake,
+            Some("camel") => Case::Camel,
+            Some("pascal") => Case::Pascal,
+            Some(other) => anyhow::bail!("invalid case '{other}', expected snake, camel or pascal"),
+        };
+        let linkage = match options.get("linkage").map(String::as_str) {
+            None | Some("extern") => Linkage::Extern,
+            Some("static_inline") => Linkage::StaticInline,
+            Some(other) => {
+                anyhow::bail!("invalid linkage '{other}', expected extern or static_inline")
+            }
+        };
+
+        let mut c_names = HashMap::new();
+        for id in &hir.defs {
+            let decorator = match hir.types.get(id).unwrap() {
+                Type::Message(msg) => &msg.decorator,
+                Type::Bitfield(bitfield) => &bitfield.decorator,
+                _ => continue,
+            };
+            let name = hir.symbols.get(*id).unwrap();
+            if let Some(c_name) = c_name(hir, decorator, name)? {
+                c_names.insert(name.to_string(), c_name.to_string());
+            }
+        }
+
+        let filename = to_c_name(filename, false);
+        Ok(Self {
+            name: match prefix {
+                "" => filename.into_owned(),
+                prefix => format!("{prefix}_{filename}"),
+            },
+            type_suffix: type_suffix.to_string(),
+            case,
+            linkage,
+            c_names,
+        })
+    }
+
+    /// The namespace in macros, eg. `BL_GREYCAT_ABI`
+    fn upper(&self) -> String {
+        self.name.to_ascii_uppercase()
+    }
+
+    /// `static inline ` when the functions are defined in the header
+    fn linkage(&self) -> &'static str {
+        match self.linkage {
+            Linkage::Extern => "",
+            Linkage::StaticInline => "static inline ",
+        }
+    }
+
+    /// Name of a definition in the names of its functions, eg. `type_flags` in
+    /// `bl_greycat_abi__print_type_flags`
+    fn fn_name<'a>(&'a self, name: &'a str) -> Cow<'a, str> {
+        if let Some(c_name) = self.c_names.get(name) {
+            return Cow::Borrowed(c_name);
+        }
+        let snake = to_c_name(name, false);
+        let mut words = snake.split('_').filter(|word| !word.is_empty());
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        };
+        match self.case {
+            Case::Snake => snake,
+            Case::Camel => Cow::Owned(
+                words.next().unwrap_or_default().to_string()
+                    + &words.map(capitalize).collect::<String>(),
+            ),
+            Case::Pascal => Cow::Owned(words.map(capitalize).collect()),
+        }
+    }
+
+    /// The C type of a native type or a definition, eg. `type_flags_t`
+    fn type_name<'a>(&'a self, name: &'a str) -> Cow<'a, str> {
+        match to_c_name(name, true) {
+            Cow::Borrowed(native) => Cow::Borrowed(native),
+            Cow::Owned(_) => Cow::Owned(format!("{}{}", self.fn_name(name), self.type_suffix)),
+        }
+    }
+
+    /// The state of the streaming reader of a message, eg. `bl_greycat_abi__abi_state_t`
+    fn state_type(&self, name: &str) -> String {
+        format!("{self}__{}_state{}", self.fn_name(name), self.type_suffix)
+    }
+
+    /// The tag of the struct of a message, its schema name unless it has a `@c_name`
+    fn tag<'a>(&'a self, name: &'a str) -> &'a str {
+        self.c_names.get(name).map_or(name, String::as_str)
+    }
+
+    /// Name of a definition in macros, eg. `TYPE_FLAGS` in `TYPE_FLAGS_ENUM`
+    fn macro_name(&self, name: &str) -> String {
+        match self.c_names.get(name) {
+            Some(c_name) => c_name.to_ascii_uppercase(),
+            None => to_c_name(name, false).to_ascii_uppercase(),
+        }
+    }
+
+    /// Macro of a flag of a bitfield, eg. `TYPE_FLAGS_ENUM`
+    fn flag_macro(&self, bitfield: &str, flag: &str) -> String {
+        format!(
+            "{}_{}",
+            self.macro_name(bitfield),
+            to_c_name(flag, false).to_ascii_uppercase()
+        )
+    }
+
+    /// Macro describing a message, eg. `BL_GREYCAT_ABI__ABI_FIXED_SIZE`
+    fn message_macro(&self, name: &str, suffix: &str) -> String {
+        format!("{}__{}_{suffix}", self.upper(), self.macro_name(name))
+    }
+
+    /// Fails on the generated names that cannot be used in C: reserved words and names given to
+    /// several types, struct tags, members or macros
+    fn check(&self, schema: &Schema) -> Result<()> {
+        let hir = schema.hir();
+        if is_reserved(&self.name) {
+            anyhow::bail!("'{}' is reserved in C, change the prefix", self.name);
+        }
+        let mut types = HashSet::new();
+        let mut tags = HashSet::new();
+        for id in &hir.defs {
+            let name = hir.symbols.get(*id).unwrap();
+            let type_name = self.type_name(name);
+            if is_reserved(&type_name) {
+                anyhow::bail!(
+                    "'{name}' is named '{type_name}' in C, which is reserved, rename it with @c_name(\"...\")"
+                );
+            }
+            if let Type::Message(msg) = hir.types.get(id).unwrap() {
+                let tag = self.tag(name);
+                if is_reserved(tag) {
+                    anyhow::bail!(
+                        "'{name}' is named '{tag}' in C, which is reserved, rename it with @c_name(\"...\")"
+                    );
+                }
+                if !tags.insert(tag) {
+                    anyhow::bail!(
+                        "'{tag}' tags several structs in C, rename one of them with @c_name(\"...\")"
+                    );
+                }
+                let mut members = HashSet::new();
+                for field in &msg.fields {
+                    let field_name = hir.symbols.get(field.name).unwrap();
+                    let member = match c_name(hir, &field.decorator, field_name)? {
+                        Some(c_name) => c_name,
+                        None => field_name,
+                    };
+                    // length fields are not members
+                    if field.associated.is_some() {
+                        continue;
+                    }
+                    if is_reserved(member) {
+                        anyhow::bail!(
+                            "field '{name}.{field_name}' is named '{member}' in C, which is reserved, rename it with @c_name(\"...\")"
+                        );
+                    }
+                    if !members.insert(member) {
+                        anyhow::bail!("'{member}' names several fields of '{name}' in C");
+                    }
+                }
+            }
+            if !types.insert(type_name) {
+                anyhow::bail!(
+                    "'{}' names several types in C, rename one of them with @c_name(\"...\")",
+                    self.type_name(name)
+                );
+            }
+        }
+
+        // the macros defined in the header, flags are not namespaced and may start with `_`
+        let mut macros = Vec::new();
+        for bitfield in schema.bitfields() {
+            for flag in bitfield.flags() {
+                macros.push((
+                    bitfield.name(),
+                    self.flag_macro(bitfield.name(), flag.name()),
+                ));
+            }
+        }
+        for msg in schema.messages() {
+            let size = msg.wire_size();
+            let mut suffixes = vec!["FINGERPRINT".to_string(), "MIN_SIZE".to_string()];
+            if size.max.is_some() {
+                suffixes.push("MAX_SIZE".to_string());
+            }
+            if size.is_fixed() {
+                suffixes.push("FIXED_SIZE".to_string());
+            }
+            for (field, _) in msg.fixed_offsets() {
+                let field = to_c_name(field.name(), false).to_ascii_uppercase();
+                suffixes.push(format!("{field}_OFFSET"));
+            }
+            for suffix in suffixes {
+                macros.push((msg.name(), self.message_macro(msg.name(), &suffix)));
+            }
+        }
+        let mut defined = HashSet::new();
+        for (name, macro_name) in macros {
+            if is_reserved(&macro_name) {
+                anyhow::bail!(
+                    "'{name}' defines the macro '{macro_name}' in C, which is reserved, rename it with @c_name(\"...\")"
+                );
+            }
+            if !defined.insert(macro_name.clone()) {
+                anyhow::bail!(
+                    "'{macro_name}' is defined several times in C, rename '{name}' with @c_name(\"...\")"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// The name given with `@c_name("...")` to `name`, a definition or a field
+fn c_name<'a>(
+    hir: &Hir,
+    decorator: &'a Option<crate::hir::Decorator>,
+    name: &str,
+) -> Result<Option<&'a str>> {
+    let Some(decorator) = decorator else {
+        return Ok(None);
+    };
+    let decorator_name = hir.symbols.get(decorator.name).unwrap();
+    if decorator_name != "c_name" {
+        anyhow::bail!("unknown decorator @{decorator_name} on '{name}', expected @c_name");
+    }
+    match decorator.arg.as_deref() {
+        Some(c_name) if is_c_ident(c_name) => Ok(Some(c_name)),
+        Some(c_name) => anyhow::bail!("@c_name of '{name}' is not a C identifier: '{c_name}'"),
+        None => anyhow::bail!("@c_name of '{name}' needs a name, eg. @c_name(\"{name}_\")"),
+    }
+}
+
+/// The member of the struct holding a field, its name unless it has a `@c_name`
+fn member<'a>(hir: &'a Hir, field: &'a crate::hir::Field) -> &'a str {
+    let name = hir.symbols.get(field.name).unwrap();
+    // checked by `Namespace::check`
+    c_name(hir, &field.decorator, name)
+        .ok()
+        .flatten()
+        .unwrap_or(name)
+}
+
+/// Keywords of C11 and C23
+const C_KEYWORDS: [&str; 59] = [
+    "alignas",
+    "alignof",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "nullptr",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "struct",
+    "switch",
+    "thread_local",
+    "true",
+    "typedef",
+    "typeof",
+    "typeof_unqual",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Alignas",
+    "_Alignof",
+    "_Atomic",
+    "_BitInt",
+    "_Bool",
+    "_Complex",
+    "_Decimal128",
+    "_Decimal32",
+    "_Decimal64",
+    "_Generic",
+    "_Imaginary",
+    "_Noreturn",
+    "_Static_assert",
+    "_Thread_local",
+];
+
+/// Whether `name` is a keyword or an identifier reserved to the implementation
+fn is_reserved(name: &str) -> bool {
+    let mut chars = name.chars();
+    let reserved_prefix = chars.next() == Some('_')
+        && chars
+            .next()
+            .is_some_and(|c| c == '_' || c.is_ascii_uppercase());
+    reserved_prefix || C_KEYWORDS.contains(&name)
+}
+
+fn is_c_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+impl Generator for CGenerator {
+    fn generate(&self, name: &str, schema: &Schema, options: &Options) -> Result<OutputFiles> {
+        let config = Config::parse(options)?;
+        let hir = schema.hir();
+        let ns = Namespace::new(name, hir, options)?;
+        ns.check(schema)?;
+        let sorted = topological_sort(hir);
+        log::debug!("{hir:#?}");
+
+        let mut files = OutputFiles::new();
+        let impl_file = generate_impl_file(name, hir, &sorted, &config, &ns)?;
+        // static inline functions are defined in the header, which is all there is
+        let (definitions, impl_file) = match ns.linkage {
+            Linkage::Extern => (None, impl_file),
+            Linkage::StaticInline => (Some(impl_file), String::new()),
+        };
+        let mut header =
+            generate_header_file(name, schema, &sorted, &config, &ns, definitions.as_deref())?;
+        if config.single_header {
+            generate_single_header_impl(&ns, &config, &impl_file, &mut header)?;
+        } else if ns.linkage == Linkage::Extern {
+            files.insert(PathBuf::from(format!("{name}.c")), impl_file);
+        }
+        files.insert(PathBuf::from(format!("{name}.h")), header);
+
+        Ok(files)
+    }
+}
 
 pub fn generate_c(filename: &str, source: &str, outdir: &Path) -> Result<()> {
-    let file = parse(source)?;
-    let hir = Hir::new(&file);
-    let sorted = topological_sort(&hir);
-    log::debug!("{hir:#?}");
+    let schema = compile([Source::new(filename, source)])?;
+    let files = CGenerator.generate(filename, &schema, &Options::new())?;
+    write_files(outdir, &files)?;
+
+    Ok(())
+}
+
+fn generate_header_file(
+    filename: &str,
+    schema: &Schema,
+    sorted: &[&Type],
+    config: &Config,
+    ns: &Namespace,
+    definitions: Option<&str>,
+) -> Result<String> {
+    let hir = schema.hir();
+    let mode = config.mode;
+    let mut buf = String::new();
+
+    if config.single_header {
+        let implementation = format!("{}_IMPLEMENTATION", ns.upper());
+        writeln!(
+            buf,
+            "// Readers of `{filename}` generated by binlang, with their runtime."
+        )?;
+        writeln!(buf, "//")?;
+        writeln!(
+            buf,
+            "// Define {implementation} in exactly one C file before including this header:"
+        )?;
+        writeln!(buf, "//")?;
+        writeln!(buf, "//   #define {implementation}")?;
+        writeln!(buf, "//   #include \"{filename}.h\"")?;
+        writeln!(buf, "//")?;
+        writeln!(
+            buf,
+            "// and BL_RUNTIME_IMPLEMENTATION once in the program, in a C file including all of its"
+        )?;
+        writeln!(buf, "// single headers, to compile the runtime they share.")?;
+        writeln!(buf)?;
+    }
+    writeln!(buf, "#ifndef BINLANG_{filename}_H_")?;
+    writeln!(buf, "#define BINLANG_{filename}_H_")?;
+    writeln!(buf)?;
+    if config.single_header {
+        let mut sections = BTreeMap::new();
+        for runtime in RUNTIME_HEADERS {
+            write_runtime(runtime, config, &mut buf, &mut sections)?;
+        }
+        write_runtime_sections(&sections, "H", &mut buf)?;
+    } else {
+        writeln!(buf, "#include \"binlang.h\"")?;
+    }
+    writeln!(buf)?;
+
+    for ty in sorted {
+        generate_forward_decl(hir, ns, ty, &mut buf)?;
+    }
+
+    writeln!(buf)?;
+
+    for ty in sorted {
+        if let Type::Bitfield(bitfield) = ty {
+            generate_bitfield(hir, ns, bitfield, &mut buf)?;
+        }
+    }
+
+    generate_fingerprints(schema, ns, config.fingerprint_names, &mut buf)?;
+    generate_layouts(schema, ns, &mut buf)?;
+
+    for ty in sorted {
+        generate_type(hir, ns, ty, mode, &mut buf)?;
+    }
+    if config.stream {
+        for ty in sorted {
+            if let Type::Message(msg) = ty {
+                generate_stream_state(hir, ns, msg, &mut buf)?;
+            }
+        }
+    }
+
+    for ty in sorted {
+        generate_fn_forward_decl(hir, ns, ty, config, &mut buf)?;
+    }
+    for elem in view_elements(hir, sorted, mode) {
+        generate_view_get(hir, ns, elem, config, true, &mut buf)?;
+    }
+    if config.stream {
+        for ty in sorted {
+            if let Type::Message(msg) = ty {
+                generate_stream_message(hir, ns, msg, config, true, &mut buf)?;
+            }
+        }
+    }
+
+    if let Some(definitions) = definitions {
+        writeln!(buf)?;
+        buf.push_str(definitions);
+    }
+
+    writeln!(buf)?;
+    writeln!(buf, "#endif // BINLANG_{filename}_H_")?;
+
+    Ok(buf)
+}
+
+fn generate_impl_file(
+    filename: &str,
+    hir: &Hir,
+    sorted: &[&Type],
+    config: &Config,
+    ns: &Namespace,
+) -> Result<String> {
+    let mode = config.mode;
+    let mut buf = String::new();
+
+    if !config.single_header && ns.linkage == Linkage::Extern {
+        writeln!(buf, "#include \"{filename}.h\"")?;
+        writeln!(buf)?;
+    }
+
+    for ty in sorted {
+        generate_impl_type(hir, ns, ty, config, &mut buf)?;
+    }
+    for elem in view_elements(hir, sorted, mode) {
+        generate_view_get(hir, ns, elem, config, false, &mut buf)?;
+    }
+    if config.stream {
+        for ty in sorted {
+            if let Type::Message(msg) = ty {
+                generate_stream_message(hir, ns, msg, config, false, &mut buf)?;
+            }
+        }
+    }
+
+    Ok(buf)
+}
+
+/// The headers of the C runtime (`libs/c`) in the order they include each other
+const RUNTIME_HEADERS: [&str; 4] = [
+    include_str!("../../libs/c/alloc.h"),
+    include_str!("../../libs/c/array.h"),
+    include_str!("../../libs/c/vec.h"),
+    include_str!("../../libs/c/binlang.h"),
+];
+
+/// The sources of the C runtime
+const RUNTIME_SOURCES: [&str; 2] = [
+    include_str!("../../libs/c/alloc.c"),
+    include_str!("../../libs/c/binlang.c"),
+];
+
+/// Appends the implementation section of a single header: the runtime, compiled where
+/// `BL_RUNTIME_IMPLEMENTATION` is defined so that a program linking several single headers
+/// defines it once, and `impl_file`
+fn generate_single_header_impl(
+    ns: &Namespace,
+    config: &Config,
+    impl_file: &str,
+    out: &mut String,
+) -> Result<()> {
+    let ns = ns.upper();
+    writeln!(out)?;
+    writeln!(out, "#ifdef BL_RUNTIME_IMPLEMENTATION")?;
+    writeln!(out, "#ifndef BINLANG_RUNTIME_IMPLEMENTATION_")?;
+    writeln!(out, "#define BINLANG_RUNTIME_IMPLEMENTATION_")?;
+    let mut sections = BTreeMap::new();
+    for runtime in RUNTIME_SOURCES {
+        writeln!(out)?;
+        write_runtime(runtime, config, out, &mut sections)?;
+    }
+    writeln!(out, "#endif // BINLANG_RUNTIME_IMPLEMENTATION_")?;
+    write_runtime_sections(&sections, "IMPLEMENTATION", out)?;
+    writeln!(out, "#endif // BL_RUNTIME_IMPLEMENTATION")?;
+    writeln!(out)?;
+    writeln!(out, "#ifdef {ns}_IMPLEMENTATION")?;
+    out.push_str(impl_file);
+    writeln!(out, "#endif // {ns}_IMPLEMENTATION")?;
+    Ok(())
+}
+
+/// Writes a file of the runtime without its includes of the other files of the runtime, which
+/// are all part of the single header. The lines between `// binlang:begin <section>` and
+/// `// binlang:end <section>` are only needed by some options: they are moved into `sections`
+/// when `config` uses them and dropped otherwise.
+fn write_runtime<'a>(
+    runtime: &'a str,
+    config: &Config,
+    out: &mut String,
+    sections: &mut BTreeMap<&'a str, String>,
+) -> std::fmt::Result {
+    let mut section = None;
+    for line in runtime.lines() {
+        if let Some(name) = line.strip_prefix("// binlang:begin ") {
+            section = Some(name);
+        } else if line.starts_with("// binlang:end ") {
+            section = None;
+        } else if line.starts_with("#include \"") {
+            continue;
+        } else if let Some(name) = section {
+            if config.runtime_section(name) {
+                writeln!(sections.entry(name).or_default(), "{line}")?;
+            }
+        } else {
+            writeln!(out, "{line}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `sections` of the runtime, each guarded on its own as the single headers of a
+/// program may be generated with different options
+fn write_runtime_sections(
+    sections: &BTreeMap<&str, String>,
+    suffix: &str,
+    out: &mut String,
+) -> std::fmt::Result {
+    for (name, section) in sections {
+        let guard = format!("BINLANG_RUNTIME_{}_{suffix}_", name.to_uppercase());
+        writeln!(out)?;
+        writeln!(out, "#ifndef {guard}")?;
+        writeln!(out, "#define {guard}")?;
+        out.push_str(section);
+        writeln!(out, "#endif // {guard}")?;
+    }
+    Ok(())
+}
+
+fn generate_impl_type<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &Type,
+    config: &Config,
+    out: &mut W,
+) -> std::fmt::Result {
+    match ty {
+        Type::Message(ty) => {
+            generate_impl_message(hir, ns, ty, config, false, out)?;
+            generate_free_message(hir, ns, ty, config, false, out)?;
+            generate_print_message(hir, ns, ty, config.mode, false, out)?;
+            generate_json_message(hir, ns, ty, config.mode, false, out)?;
+            generate_eq_message(hir, ns, ty, config.mode, false, out)?;
+            generate_clone_message(hir, ns, ty, config, false, out)?;
+            generate_hash_message(hir, ns, ty, config.mode, false, out)?;
+        }
+        Type::Bitfield(bitfield) => {
+            generate_print_bitfield(hir, ns, bitfield, out)?;
+            generate_json_bitfield(hir, ns, bitfield, out)?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn generate_impl_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    config: &Config,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let mode = config.mode;
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(
+        out,
+        "{linkage}bl_result_t {ns}__read_{fn_name}(bl_slice_t *b, {typedef} *value"
+    )?;
+    if config.allocator {
+        write!(out, ", bl_allocator_t *allocator")?;
+    }
+    if config.errors {
+        write!(out, ", bl_error_t *err")?;
+    }
+    write!(out, ")")?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+
+    // readers of messages owning memory start from a zeroed value, so that whatever was read
+    // before a failure can be freed
+    let owns = owns_memory(hir, ty.name, mode, &mut Vec::new());
+    let checked = owns || config.errors;
+    let err = config.errors.then_some("err");
+    let allocator = config.allocator.then_some("allocator");
+    let try_read = |(call, kind): (String, &str), field: SymbolId| {
+        if config.errors {
+            let field = hir.symbols.get(field).unwrap();
+            format!("BL_TRY_ERR({call}, res, fail, err, b, {kind}, \"{field}\");")
+        } else if owns {
+            format!("BL_TRY_GOTO({call}, res, fail);")
+        } else {
+            format!("BL_TRY({call});")
+        }
+    };
+    if checked {
+        writeln!(out, "  bl_result_t res;")?;
+    }
+    if owns {
+        writeln!(out, "  memset(value, 0, sizeof *value);")?;
+    } else if config.allocator {
+        writeln!(out, "  (void)allocator;")?;
+    }
+
+    let indent = "  ";
+    for field in &ty.fields {
+        let f_name = match field.associated {
+            Some(associated_name) => {
+                let array = ty
+                    .fields
+                    .iter()
+                    .find(|f| f.name == associated_name)
+                    .unwrap();
+                Cow::Owned(format!("{}.size", member(hir, array)))
+            }
+            None => Cow::Borrowed(member(hir, field)),
+        };
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Array(ArrayType::Default(elem_type)) => {
+                let call = (
+                    format!("bl_slice__read_u32(b, &value->{f_name}.size)"),
+                    "bl_error_eof",
+                );
+                writeln!(out, "{indent}{}", try_read(call, field.name))?;
+                *elem_type
+            }
+            Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+            _ => {
+                let dest = format!("&value->{f_name}");
+                let call = match field.associated {
+                    Some(_) => read_length_call(hir, field.ty, "b", &dest),
+                    None => read_call(hir, ns, field.ty, "b", &dest, allocator, err),
+                };
+                writeln!(out, "{indent}{}", try_read(call, field.name))?;
+                continue;
+            }
+        };
 
-    generate_header_file(filename, outdir, &hir, &sorted)?;
-    generate_impl_file(filename, outdir, &hir, &sorted)?;
+        if let Some(stride) = view_stride(hir, ns, elem_type, mode) {
+            let call = (
+                format!("bl_slice__read_view(b, &value->{f_name}, {stride})"),
+                "bl_error_eof",
+            );
+            writeln!(out, "{indent}{}", try_read(call, field.name))?;
+            continue;
+        }
+        // a hostile count fails before anything is allocated
+        let elem_ty = hir.symbols.get(elem_type).unwrap();
+        let min_size = hir.wire_size(elem_type).min;
+        let call = (
+            format!(
+                "bl_slice__check_count(b, value->{f_name}.size, {min_size}, sizeof *value->{f_name}.elems)"
+            ),
+            "bl_error_limit",
+        );
+        writeln!(out, "{indent}{}", try_read(call, field.name))?;
+        let array = format!("value->{f_name}");
+        let size = format!("value->{f_name}.size");
+        writeln!(
+            out,
+            "{indent}{}",
+            config.reserve(&array, &size, "allocator")
+        )?;
+        // the global allocator aborts rather than failing, unlike the ones given to the readers
+        if config.errors || config.allocator {
+            let call = (format!("BL_RESERVED(&value->{f_name})"), "bl_error_alloc");
+            writeln!(out, "{indent}{}", try_read(call, field.name))?;
+        }
+        if elem_ty == "u8" {
+            let call = (
+                format!("bl_slice__read_exact(b, value->{f_name}.elems, value->{f_name}.size)"),
+                "bl_error_eof",
+            );
+            writeln!(out, "{indent}{}", try_read(call, field.name))?;
+        } else if checked {
+            // only the elements read so far are freed, the failed one already freed itself
+            let (call, kind) = read_call(
+                hir,
+                ns,
+                elem_type,
+                "b",
+                &format!("value->{f_name}.elems + i"),
+                allocator,
+                err,
+            );
+            writeln!(
+                out,
+                "{indent}for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
+            )?;
+            writeln!(out, "{indent}  res = {call};")?;
+            writeln!(out, "{indent}  if (res <= 0) {{")?;
+            if config.errors {
+                let field = hir.symbols.get(field.name).unwrap();
+                writeln!(
+                    out,
+                    "{indent}    bl_error__record(err, res, {kind}, b, \"{field}\", i);"
+                )?;
+            }
+            writeln!(out, "{indent}    value->{f_name}.size = i;")?;
+            writeln!(out, "{indent}    goto fail;")?;
+            writeln!(out, "{indent}  }}")?;
+            writeln!(out, "{indent}}}")?;
+        } else {
+            let (call, _) = read_call(
+                hir,
+                ns,
+                elem_type,
+                "b",
+                &format!("value->{f_name}.elems + i"),
+                allocator,
+                err,
+            );
+            writeln!(
+                out,
+                "{indent}for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
+            )?;
+            writeln!(out, "{indent}  BL_TRY({call});")?;
+            writeln!(out, "{indent}}}")?;
+        }
+    }
+    writeln!(out, "  return bl_result_ok;")?;
+    if checked {
+        writeln!(out, "fail:")?;
+        if owns {
+            let allocator = config.allocator_arg("allocator");
+            writeln!(out, "  {ns}__free_{fn_name}(value{allocator});")?;
+        }
+        writeln!(out, "  return res;")?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
 
+/// The call reading a value of type `ty` from the slice pointer `b` into `dest`, along with the
+/// kind of error reported when it fails with `bl_result_err`.
+///
+/// Messages are read with `allocator` and the error context `err` when the readers take them.
+fn read_call(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: SymbolId,
+    b: &str,
+    dest: &str,
+    allocator: Option<&str>,
+    err: Option<&str>,
+) -> (String, &'static str) {
+    match hir.types.get(&ty).unwrap() {
+        Type::Message(msg) => {
+            let fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+            let allocator = allocator
+                .map(|allocator| format!(", {allocator}"))
+                .unwrap_or_default();
+            // the nested reader records its own failures
+            let err = err.map(|err| format!(", {err}")).unwrap_or_default();
+            (
+                format!("{ns}__read_{fn_name}({b}, {dest}{allocator}{err})"),
+                "bl_error_none",
+            )
+        }
+        Type::Bitfield(_) => (format!("bl_slice__read_u8({b}, {dest})"), "bl_error_eof"),
+        Type::Native(native) => {
+            let kind = match native {
+                NativeType::Bool => "bl_error_constraint",
+                NativeType::VU32
+                | NativeType::VU64
+                | NativeType::VI32
+                | NativeType::VI64
+                | NativeType::VZI32
+                | NativeType::VZI64 => "bl_error_varint_overflow",
+                _ => "bl_error_eof",
+            };
+            let native = hir.symbols.get(ty).unwrap();
+            (format!("bl_slice__read_{native}({b}, {dest})"), kind)
+        }
+        Type::Array(_) => unreachable!("arrays of arrays are not supported"),
+    }
+}
+
+/// The call reading the length field of type `ty` into `dest`, the `uint32_t` size of its
+/// array, and the kind of error it fails with. Lengths are unsigned integers, see
+/// [`crate::error::HirError::InvalidLengthType`].
+fn read_length_call(hir: &Hir, ty: SymbolId, b: &str, dest: &str) -> (String, &'static str) {
+    let Some(Type::Native(native)) = hir.types.get(&ty) else {
+        unreachable!("length fields are unsigned integers");
+    };
+    let name = hir.symbols.get(ty).unwrap();
+    match native {
+        NativeType::U32 => (format!("bl_slice__read_u32({b}, {dest})"), "bl_error_eof"),
+        NativeType::VU32 => (
+            format!("bl_slice__read_vu32({b}, {dest})"),
+            "bl_error_varint_overflow",
+        ),
+        NativeType::U8 | NativeType::U16 => (
+            format!("bl_slice__read_{name}_len({b}, {dest})"),
+            "bl_error_eof",
+        ),
+        NativeType::VU64 => (
+            format!("bl_slice__read_vu64_len({b}, {dest})"),
+            "bl_error_varint_overflow",
+        ),
+        // or a value too large for a `uint32_t` size
+        _ => (
+            format!("bl_slice__read_{name}_len({b}, {dest})"),
+            "bl_error_constraint",
+        ),
+    }
+}
+
+/// Releases the arrays owned by a message, nested messages and elements first
+fn generate_free_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    config: &Config,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let mode = config.mode;
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(out, "{linkage}void {ns}__free_{fn_name}({typedef} *value")?;
+    if config.allocator {
+        write!(out, ", bl_allocator_t *allocator")?;
+    }
+    write!(out, ")")?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+    if !owns_memory(hir, ty.name, mode, &mut Vec::new()) {
+        writeln!(out, "  (void)value;")?;
+        if config.allocator {
+            writeln!(out, "  (void)allocator;")?;
+        }
+    }
+    let allocator = config.allocator_arg("allocator");
+    for field in ty.fields.iter().rev() {
+        let f_name = member(hir, field);
+        match hir.types.get(&field.ty).unwrap() {
+            Type::Message(msg) => {
+                if owns_memory(hir, msg.name, mode, &mut Vec::new()) {
+                    let f_ty_fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+                    writeln!(
+                        out,
+                        "  {ns}__free_{f_ty_fn_name}(&value->{f_name}{allocator});"
+                    )?;
+                }
+            }
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. }) => {
+                if view_stride(hir, ns, *elem_type, mode).is_some() {
+                    continue;
+                }
+                if owns_memory(hir, *elem_type, mode, &mut Vec::new()) {
+                    let elem_ty_name = ns.fn_name(hir.symbols.get(*elem_type).unwrap());
+                    writeln!(out, "  if (value->{f_name}.elems != NULL) {{")?;
+                    writeln!(
+                        out,
+                        "    for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
+                    )?;
+                    writeln!(
+                        out,
+                        "      {ns}__free_{elem_ty_name}(value->{f_name}.elems + i{allocator});"
+                    )?;
+                    writeln!(out, "    }}")?;
+                    writeln!(out, "  }}")?;
+                }
+                let array = format!("value->{f_name}");
+                writeln!(out, "  {}", config.delete(&array, "allocator"))?;
+            }
+            Type::Bitfield(_) | Type::Native(_) => (),
+        }
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// The state of the streaming reader of a message, fed with `bl_<ns>__feed_<msg>`
+fn generate_stream_state<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(ty.name).unwrap();
+    let fn_name = ns.fn_name(name);
+    writeln!(
+        out,
+        "/// Streaming reader of `{name}`, zero-initialized before the first chunk. `value` is freed"
+    )?;
+    writeln!(
+        out,
+        "/// with `{ns}__free_{fn_name}` once complete or when the input ends early"
+    )?;
+    writeln!(out, "typedef struct {{")?;
+    writeln!(out, "  bl_stream_t stream;")?;
+    writeln!(out, "  {} value;", ns.type_name(name))?;
+    writeln!(out, "}} {};\n", ns.state_type(name))?;
+    Ok(())
+}
+
+/// A reader resumed where it stopped when it runs out of input.
+///
+/// Each message keeps the step it is at in the frame of its depth: a step reads a value, which is
+/// either complete or not consumed at all, or the elements of an array, counted by the frame.
+fn generate_stream_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    config: &Config,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let state = ns.state_type(name);
+    let linkage = ns.linkage();
+    let stream_fn = format!("{ns}__stream_{fn_name}");
+    let stream_sig = format!(
+        "{linkage}bl_result_t {stream_fn}(bl_stream_t *s, uint32_t depth, bl_slice_t *b, {typedef} *value)"
+    );
+    let feed_sig = format!(
+        "{linkage}bl_result_t {ns}__feed_{fn_name}({state} *state, const uint8_t *data, uint32_t len)"
+    );
+    if forward_decl {
+        writeln!(out, "{stream_sig};")?;
+        writeln!(out, "{feed_sig};")?;
+        return Ok(());
+    }
+
+    let try_read = |call: &str| format!("    BL_STREAM_TRY({call}, res, fail);\n");
+    let mut steps = vec!["    memset(value, 0, sizeof *value);\n".to_string()];
+    for field in &ty.fields {
+        let f_name = match field.associated {
+            Some(associated_name) => {
+                let array = ty
+                    .fields
+                    .iter()
+                    .find(|f| f.name == associated_name)
+                    .unwrap();
+                Cow::Owned(format!("{}.size", member(hir, array)))
+            }
+            None => Cow::Borrowed(member(hir, field)),
+        };
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Message(msg) => {
+                let f_ty_fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+                steps.push(try_read(&format!(
+                    "{ns}__stream_{f_ty_fn_name}(s, depth + 1, b, &value->{f_name})"
+                )));
+                continue;
+            }
+            Type::Bitfield(_) => {
+                steps.push(try_read(&format!("bl_slice__read_u8(b, &value->{f_name})")));
+                continue;
+            }
+            Type::Native(_) if field.associated.is_some() => {
+                let dest = format!("&value->{f_name}");
+                steps.push(try_read(&read_length_call(hir, field.ty, "b", &dest).0));
+                continue;
+            }
+            Type::Native(_) => {
+                let native = hir.symbols.get(field.ty).unwrap();
+                steps.push(try_read(&format!(
+                    "bl_slice__read_{native}(b, &value->{f_name})"
+                )));
+                continue;
+            }
+            Type::Array(ArrayType::Default(elem_type)) => {
+                steps.push(try_read(&format!(
+                    "bl_slice__read_u32(b, &value->{f_name}.size)"
+                )));
+                *elem_type
+            }
+            Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+        };
+
+        // the declared count is kept in the frame and the budgets checked against it, in a step of
+        // their own, while the array only grows with the elements received so that a short input
+        // cannot make it allocate the whole count
+        let mut step = String::new();
+        writeln!(step, "    f->count = value->{f_name}.size;")?;
+        writeln!(step, "    value->{f_name}.size = 0;")?;
+        step.push_str(&try_read(&format!(
+            "bl_slice__check_count(b, f->count, 0, sizeof *value->{f_name}.elems)"
+        )));
+        steps.push(step);
+
+        let allocator = if config.allocator {
+            "s->allocator"
+        } else {
+            "NULL"
+        };
+        let reserve = |needed: &str| {
+            try_read(&format!(
+                "bl_stream__reserve((BlArray *)&value->{f_name}, sizeof *value->{f_name}.elems, {needed}, f->count, {allocator})"
+            ))
+        };
+        let elem_ty = hir.symbols.get(elem_type).unwrap();
+        let elem_ty_name = ns.fn_name(elem_ty);
+        let elem = hir.types.get(&elem_type).unwrap();
+        let mut step = String::new();
+        let reader = match elem {
+            _ if elem_ty == "u8" => None,
+            Type::Message(_) => Some(format!("{ns}__stream_{elem_ty_name}(s, depth + 1, b, ")),
+            Type::Bitfield(_) => Some("bl_slice__read_u8(b, ".to_string()),
+            _ => Some(format!("bl_slice__read_{elem_ty}(b, ")),
+        };
+        match reader {
+            None => {
+                step.push_str(&reserve("(uint64_t)f->index + b->len"));
+                step.push_str(&try_read(&format!(
+                    "bl_stream__read_bytes(b, value->{f_name}.elems, f->count, &f->index)"
+                )));
+            }
+            Some(reader) => {
+                writeln!(step, "    for (; f->index < f->count; f->index++) {{")?;
+                write!(step, "  {}", reserve("f->index + 1"))?;
+                write!(
+                    step,
+                    "  {}",
+                    try_read(&format!("{reader}value->{f_name}.elems + f->index)"))
+                )?;
+                writeln!(step, "    }}")?;
+            }
+        }
+        step.push_str("    f->index = 0;\n");
+        steps.push(step);
+    }
+
+    writeln!(out, "{stream_sig} {{")?;
+    writeln!(out, "  if (depth >= BL_STREAM_MAX_DEPTH) {{")?;
+    writeln!(out, "    return bl_result_err;")?;
+    writeln!(out, "  }}")?;
+    writeln!(out, "  bl_stream_frame_t *f = &s->frames[depth];")?;
+    if !ty.fields.is_empty() {
+        writeln!(out, "  bl_result_t res;")?;
+    }
+    writeln!(out, "  switch (f->field) {{")?;
+    for (i, step) in steps.iter().enumerate() {
+        writeln!(out, "  case {i}:")?;
+        write!(out, "{step}")?;
+        writeln!(out, "    f->field++;")?;
+        if i + 1 < steps.len() {
+            writeln!(out, "    // fallthrough")?;
+        }
+    }
+    writeln!(out, "  }}")?;
+    writeln!(out, "  f->field = 0;")?;
+    writeln!(out, "  return bl_result_ok;")?;
+    if !ty.fields.is_empty() {
+        writeln!(out, "fail:")?;
+        let allocator = config.allocator_arg("s->allocator");
+        writeln!(out, "  {ns}__free_{fn_name}(value{allocator});")?;
+        writeln!(out, "  return bl_result_err;")?;
+    }
+    writeln!(out, "}}")?;
+
+    writeln!(
+        out,
+        "static bl_result_t {stream_fn}_root(bl_stream_t *s, bl_slice_t *b, void *value) {{"
+    )?;
+    writeln!(out, "  return {stream_fn}(s, 0, b, value);")?;
+    writeln!(out, "}}")?;
+    writeln!(out, "{feed_sig} {{")?;
+    writeln!(
+        out,
+        "  return bl_stream__feed(&state->stream, {stream_fn}_root, &state->value, data, len);"
+    )?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Prints a message as nested `Name { field: value }` blocks, `indent` being its nesting level
+fn generate_print_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    mode: Mode,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(
+        out,
+        "{linkage}void {ns}__print_{fn_name}(FILE *out, const {typedef} *value, int indent)"
+    )?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+    writeln!(out, "  fprintf(out, \"{name} {{\\n\");")?;
+    let mut fields = ty
+        .fields
+        .iter()
+        .filter(|f| f.associated.is_none())
+        .peekable();
+    if fields.peek().is_none() {
+        writeln!(out, "  (void)value;")?;
+    }
+    for field in fields {
+        let label = hir.symbols.get(field.name).unwrap();
+        let f_name = member(hir, field);
+        writeln!(out, "  bl_print__indent(out, indent + 1);")?;
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+            _ => {
+                writeln!(out, "  fprintf(out, \"{label}: \");")?;
+                let value = format!("value->{f_name}");
+                write!(
+                    out,
+                    "{}",
+                    print_value(hir, ns, field.ty, &value, "indent + 1")
+                )?;
+                continue;
+            }
+        };
+        if hir.symbols.get(elem_type).unwrap() == "u8" {
+            writeln!(out, "  fprintf(out, \"{label}: \");")?;
+            writeln!(
+                out,
+                "  bl_print__bytes(out, value->{f_name}.elems, value->{f_name}.size);"
+            )?;
+            writeln!(out, "  fputc('\\n', out);")?;
+            continue;
+        }
+        writeln!(out, "  fprintf(out, \"{label}: [\\n\");")?;
+        writeln!(
+            out,
+            "  for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
+        )?;
+        writeln!(out, "    bl_print__indent(out, indent + 2);")?;
+        let elem = if view_stride(hir, ns, elem_type, mode).is_some() {
+            // the elements of a view are decoded one by one
+            let elem_name = hir.symbols.get(elem_type).unwrap();
+            writeln!(out, "    {} elem;", ns.type_name(elem_name))?;
+            writeln!(
+                out,
+                "    if ({}(&value->{f_name}, i, &elem) <= 0) {{",
+                view_get(hir, ns, elem_type)
+            )?;
+            writeln!(out, "      fprintf(out, \"<invalid>\\n\");")?;
+            writeln!(out, "      continue;")?;
+            writeln!(out, "    }}")?;
+            "elem".to_string()
+        } else {
+            format!("value->{f_name}.elems[i]")
+        };
+        for line in print_value(hir, ns, elem_type, &elem, "indent + 2").lines() {
+            writeln!(out, "  {line}")?;
+        }
+        writeln!(out, "  }}")?;
+        writeln!(out, "  bl_print__indent(out, indent + 1);")?;
+        writeln!(out, "  fprintf(out, \"]\\n\");")?;
+    }
+    writeln!(out, "  bl_print__indent(out, indent);")?;
+    writeln!(out, "  fprintf(out, \"}}\\n\");")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Prints the value of a bitfield followed by the names of its flags that are set, eg.
+/// `0x05 (native | enum)`
+fn generate_print_bitfield<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    bitfield: &BitfieldType,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(bitfield.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    writeln!(
+        out,
+        "static bl_unused void {ns}__print_{fn_name}(FILE *out, {typedef} value) {{"
+    )?;
+    writeln!(out, "  fprintf(out, \"0x%02x (\", value);")?;
+    if !bitfield.flags.is_empty() {
+        writeln!(out, "  const char *sep = \"\";")?;
+    }
+    for flag in &bitfield.flags {
+        let flag_name = hir.symbols.get(flag.name).unwrap();
+        writeln!(out, "  if (value & {}) {{", ns.flag_macro(name, flag_name))?;
+        writeln!(out, "    fprintf(out, \"%s{flag_name}\", sep);")?;
+        writeln!(out, "    sep = \" | \";")?;
+        writeln!(out, "  }}")?;
+    }
+    writeln!(out, "  fprintf(out, \")\\n\");")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Writes the flags set in a bitfield as an array of their names
+fn generate_json_bitfield<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    bitfield: &BitfieldType,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(bitfield.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let mut names = vec!["NULL".to_string(); 8];
+    for flag in &bitfield.flags {
+        names[flag.offset as usize] = format!("\"{}\"", hir.symbols.get(flag.name).unwrap());
+    }
+    writeln!(
+        out,
+        "static bl_unused void {ns}__to_json_{fn_name}(bl_json_t *json, {typedef} value) {{"
+    )?;
+    writeln!(
+        out,
+        "  static const char *const names[8] = {{{}}};",
+        names.join(", ")
+    )?;
+    writeln!(out, "  bl_json__flags(json, value, names);")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// The statements printing `value`, which is not an array, followed by a new line
+fn print_value(hir: &Hir, ns: &Namespace, ty: SymbolId, value: &str, indent: &str) -> String {
+    let ty_name = hir.symbols.get(ty).unwrap();
+    let fn_name = ns.fn_name(ty_name);
+    let statement = match hir.types.get(&ty).unwrap() {
+        Type::Message(_) => format!("{ns}__print_{fn_name}(out, &{value}, {indent});"),
+        Type::Bitfield(_) => format!("{ns}__print_{fn_name}(out, {value});"),
+        Type::Native(native) => match native {
+            NativeType::Bool => format!("fprintf(out, \"%s\\n\", {value} ? \"true\" : \"false\");"),
+            NativeType::U128 | NativeType::I128 => format!(
+                "fprintf(out, \"0x%016llx%016llx\\n\", (unsigned long long)((bl_u128_t){value} >> 64), (unsigned long long){value});"
+            ),
+            NativeType::F32 | NativeType::F64 => {
+                format!("fprintf(out, \"%g\\n\", (double){value});")
+            }
+            NativeType::I8
+            | NativeType::I16
+            | NativeType::I32
+            | NativeType::I64
+            | NativeType::VI32
+            | NativeType::VI64
+            | NativeType::VZI32
+            | NativeType::VZI64 => {
+                format!("fprintf(out, \"%lld\\n\", (long long){value});")
+            }
+            _ => format!("fprintf(out, \"%llu\\n\", (unsigned long long){value});"),
+        },
+        Type::Array(_) => unreachable!("arrays of arrays are not supported"),
+    };
+    format!("  {statement}\n")
+}
+
+/// Writes a message as a JSON object into a `bl_json_t`, the same as `Node::to_json` of the
+/// decoded message
+fn generate_json_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    mode: Mode,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(
+        out,
+        "{linkage}bl_result_t {ns}__to_json_{fn_name}(bl_json_t *json, const {typedef} *value)"
+    )?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+    writeln!(out, "  bl_json__begin(json, '{{');")?;
+    if ty.fields.is_empty() {
+        writeln!(out, "  (void)value;")?;
+    }
+    for field in &ty.fields {
+        let label = hir.symbols.get(field.name).unwrap();
+        writeln!(out, "  bl_json__key(json, \"{label}\");")?;
+        // length fields are the size of their array
+        if let Some(associated_name) = field.associated {
+            let array = ty
+                .fields
+                .iter()
+                .find(|f| f.name == associated_name)
+                .unwrap();
+            let value = format!("value->{}.size", member(hir, array));
+            writeln!(out, "  {}", json_value(hir, ns, field.ty, &value))?;
+            continue;
+        }
+        let f_name = member(hir, field);
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+            _ => {
+                let value = format!("value->{f_name}");
+                writeln!(out, "  {}", json_value(hir, ns, field.ty, &value))?;
+                continue;
+            }
+        };
+        if hir.symbols.get(elem_type).unwrap() == "u8" {
+            writeln!(
+                out,
+                "  bl_json__bytes(json, value->{f_name}.elems, value->{f_name}.size);"
+            )?;
+            continue;
+        }
+        writeln!(out, "  bl_json__begin(json, '[');")?;
+        writeln!(
+            out,
+            "  for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
+        )?;
+        writeln!(out, "    bl_json__item(json);")?;
+        let elem = if view_stride(hir, ns, elem_type, mode).is_some() {
+            let elem_name = hir.symbols.get(elem_type).unwrap();
+            writeln!(out, "    {} elem;", ns.type_name(elem_name))?;
+            writeln!(
+                out,
+                "    if ({}(&value->{f_name}, i, &elem) <= 0) {{",
+                view_get(hir, ns, elem_type)
+            )?;
+            writeln!(out, "      json->failed = true;")?;
+            writeln!(out, "      return bl_result_err;")?;
+            writeln!(out, "    }}")?;
+            "elem".to_string()
+        } else {
+            format!("value->{f_name}.elems[i]")
+        };
+        writeln!(out, "    {}", json_value(hir, ns, elem_type, &elem))?;
+        writeln!(out, "  }}")?;
+        writeln!(out, "  bl_json__end(json, ']');")?;
+    }
+    writeln!(out, "  bl_json__end(json, '}}');")?;
+    writeln!(out, "  return json->failed ? bl_result_err : bl_result_ok;")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// The statement writing `value`, which is not an array, as JSON
+fn json_value(hir: &Hir, ns: &Namespace, ty: SymbolId, value: &str) -> String {
+    match hir.types.get(&ty).unwrap() {
+        Type::Message(msg) => {
+            let fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+            // failures are sticky in `json`, checked once the message is written
+            format!("{ns}__to_json_{fn_name}(json, &{value});")
+        }
+        Type::Bitfield(bitfield) => {
+            let fn_name = ns.fn_name(hir.symbols.get(bitfield.name).unwrap());
+            format!("{ns}__to_json_{fn_name}(json, {value});")
+        }
+        Type::Native(native) => match native {
+            NativeType::Bool => format!("bl_json__bool(json, {value});"),
+            NativeType::U128 => format!("bl_json__u128(json, {value});"),
+            NativeType::I128 => format!("bl_json__i128(json, {value});"),
+            NativeType::F32 | NativeType::F64 => format!("bl_json__double(json, {value});"),
+            NativeType::I8
+            | NativeType::I16
+            | NativeType::I32
+            | NativeType::I64
+            | NativeType::VI32
+            | NativeType::VI64
+            | NativeType::VZI32
+            | NativeType::VZI64 => format!("bl_json__int(json, {value});"),
+            _ => format!("bl_json__uint(json, {value});"),
+        },
+        Type::Array(_) => unreachable!("arrays of arrays are not supported"),
+    }
+}
+
+/// Compares two messages field by field, through their arrays and nested messages
+fn generate_eq_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    mode: Mode,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(
+        out,
+        "{linkage}bool {ns}__eq_{fn_name}(const {typedef} *a, const {typedef} *b)"
+    )?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+    let mut fields = ty
+        .fields
+        .iter()
+        .filter(|f| f.associated.is_none())
+        .peekable();
+    if fields.peek().is_none() {
+        writeln!(out, "  (void)a;")?;
+        writeln!(out, "  (void)b;")?;
+    }
+    for field in fields {
+        let f_name = member(hir, field);
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+            Type::Message(msg) => {
+                let f_ty_fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+                writeln!(
+                    out,
+                    "  if (!{ns}__eq_{f_ty_fn_name}(&a->{f_name}, &b->{f_name})) {{"
+                )?;
+                writeln!(out, "    return false;")?;
+                writeln!(out, "  }}")?;
+                continue;
+            }
+            _ => {
+                writeln!(out, "  if (a->{f_name} != b->{f_name}) {{")?;
+                writeln!(out, "    return false;")?;
+                writeln!(out, "  }}")?;
+                continue;
+            }
+        };
+        writeln!(out, "  if (a->{f_name}.size != b->{f_name}.size) {{")?;
+        writeln!(out, "    return false;")?;
+        writeln!(out, "  }}")?;
+        // views without floats are compared encoded, fixed-size integers, booleans and bitfields
+        // having one encoding per value
+        let stride = view_stride(hir, ns, elem_type, mode);
+        let len = match &stride {
+            Some(stride) if !holds_float(hir, elem_type) => {
+                Some(format!("(size_t)a->{f_name}.size * {stride}"))
+            }
+            Some(_) => None,
+            None if is_flat(hir, elem_type) => {
+                Some(format!("a->{f_name}.size * sizeof *a->{f_name}.elems"))
+            }
+            None => None,
+        };
+        if let Some(len) = len {
+            writeln!(
+                out,
+                "  if (a->{f_name}.size > 0 && memcmp(a->{f_name}.elems, b->{f_name}.elems, {len}) != 0) {{"
+            )?;
+            writeln!(out, "    return false;")?;
+            writeln!(out, "  }}")?;
+            continue;
+        }
+        writeln!(out, "  for (uint32_t i = 0; i < a->{f_name}.size; i++) {{")?;
+        if stride.is_some() {
+            // the elements of a view are decoded one by one
+            let get = view_get(hir, ns, elem_type);
+            let elem_typedef = ns.type_name(hir.symbols.get(elem_type).unwrap());
+            writeln!(out, "    {elem_typedef} elem_a;")?;
+            writeln!(out, "    {elem_typedef} elem_b;")?;
+            writeln!(
+                out,
+                "    if ({get}(&a->{f_name}, i, &elem_a) <= 0 || {get}(&b->{f_name}, i, &elem_b) <= 0) {{"
+            )?;
+            writeln!(out, "      return false;")?;
+            writeln!(out, "    }}")?;
+            match hir.types.get(&elem_type).unwrap() {
+                Type::Message(msg) => {
+                    let elem_fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+                    writeln!(
+                        out,
+                        "    if (!{ns}__eq_{elem_fn_name}(&elem_a, &elem_b)) {{"
+                    )?;
+                }
+                _ => writeln!(out, "    if (elem_a != elem_b) {{")?,
+            }
+        } else {
+            match hir.types.get(&elem_type).unwrap() {
+                Type::Message(msg) => {
+                    let elem_fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+                    writeln!(
+                        out,
+                        "    if (!{ns}__eq_{elem_fn_name}(a->{f_name}.elems + i, b->{f_name}.elems + i)) {{"
+                    )?;
+                }
+                _ => writeln!(
+                    out,
+                    "    if (a->{f_name}.elems[i] != b->{f_name}.elems[i]) {{"
+                )?,
+            }
+        }
+        writeln!(out, "      return false;")?;
+        writeln!(out, "    }}")?;
+        writeln!(out, "  }}")?;
+    }
+    writeln!(out, "  return true;")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Copies a message and the arrays it owns into `dst`, which is left empty when it fails.
+/// Views are shared with `src`, they point into the same decoded buffer.
+fn generate_clone_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    config: &Config,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let mode = config.mode;
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(
+        out,
+        "{linkage}bl_result_t {ns}__clone_{fn_name}({typedef} *dst, const {typedef} *src"
+    )?;
+    if config.allocator {
+        write!(out, ", bl_allocator_t *allocator")?;
+    }
+    write!(out, ")")?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+    if !owns_memory(hir, ty.name, mode, &mut Vec::new()) {
+        if config.allocator {
+            writeln!(out, "  (void)allocator;")?;
+        }
+        writeln!(out, "  *dst = *src;")?;
+        writeln!(out, "  return bl_result_ok;")?;
+        writeln!(out, "}}")?;
+        return Ok(());
+    }
+    let allocator = config.allocator_arg("allocator");
+    writeln!(out, "  bl_result_t res;")?;
+    // `dst` can be freed at any point
+    writeln!(out, "  memset(dst, 0, sizeof *dst);")?;
+    for field in ty.fields.iter().filter(|f| f.associated.is_none()) {
+        let f_name = member(hir, field);
+        if !owns_memory(hir, field.ty, mode, &mut Vec::new()) {
+            writeln!(out, "  dst->{f_name} = src->{f_name};")?;
+            continue;
+        }
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+            Type::Message(msg) => {
+                let f_ty_fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+                writeln!(
+                    out,
+                    "  BL_TRY_GOTO({ns}__clone_{f_ty_fn_name}(&dst->{f_name}, &src->{f_name}{allocator}), res, fail);"
+                )?;
+                continue;
+            }
+            _ => unreachable!("only arrays and messages own memory"),
+        };
+        let array = format!("dst->{f_name}");
+        let size = format!("src->{f_name}.size");
+        writeln!(out, "  {}", config.reserve(&array, &size, "allocator"))?;
+        writeln!(
+            out,
+            "  BL_TRY_GOTO(BL_RESERVED(&dst->{f_name}), res, fail);"
+        )?;
+        if !owns_memory(hir, elem_type, mode, &mut Vec::new()) {
+            writeln!(out, "  if (src->{f_name}.size > 0) {{")?;
+            writeln!(
+                out,
+                "    memcpy(dst->{f_name}.elems, src->{f_name}.elems, src->{f_name}.size * sizeof *src->{f_name}.elems);"
+            )?;
+            writeln!(out, "  }}")?;
+            continue;
+        }
+        let elem_fn_name = ns.fn_name(hir.symbols.get(elem_type).unwrap());
+        writeln!(
+            out,
+            "  for (uint32_t i = 0; i < src->{f_name}.size; i++) {{"
+        )?;
+        writeln!(
+            out,
+            "    res = {ns}__clone_{elem_fn_name}(dst->{f_name}.elems + i, src->{f_name}.elems + i{allocator});"
+        )?;
+        writeln!(out, "    if (res <= 0) {{")?;
+        writeln!(out, "      dst->{f_name}.size = i;")?;
+        writeln!(out, "      goto fail;")?;
+        writeln!(out, "    }}")?;
+        writeln!(out, "  }}")?;
+    }
+    writeln!(out, "  return bl_result_ok;")?;
+    writeln!(out, "fail:")?;
+    writeln!(out, "  {ns}__free_{fn_name}(dst{allocator});")?;
+    writeln!(out, "  return res;")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Hashes a message consistently with its `eq` function
+fn generate_hash_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &MessageType,
+    mode: Mode,
+    forward_decl: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    let name = hir.symbols.get(ty.name).unwrap();
+    let typedef = ns.type_name(name);
+    let fn_name = ns.fn_name(name);
+    let linkage = ns.linkage();
+    write!(
+        out,
+        "{linkage}uint64_t {ns}__hash_{fn_name}(const {typedef} *value)"
+    )?;
+    if forward_decl {
+        writeln!(out, ";")?;
+        return Ok(());
+    }
+    writeln!(out, " {{")?;
+    let mut fields = ty
+        .fields
+        .iter()
+        .filter(|f| f.associated.is_none())
+        .peekable();
+    if fields.peek().is_none() {
+        writeln!(out, "  (void)value;")?;
+    }
+    writeln!(out, "  uint64_t hash = BL_HASH_INIT;")?;
+    for field in fields {
+        let f_name = member(hir, field);
+        let elem_type = match hir.types.get(&field.ty).unwrap() {
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. }) => *elem_type,
+            _ => {
+                let value = format!("value->{f_name}");
+                writeln!(out, "  {}", hash_value(hir, ns, field.ty, &value))?;
+                continue;
+            }
+        };
+        writeln!(out, "  hash = bl_hash__u64(hash, value->{f_name}.size);")?;
+        let stride = view_stride(hir, ns, elem_type, mode);
+        let len = match &stride {
+            Some(stride) if !holds_float(hir, elem_type) => {
+                Some(format!("(size_t)value->{f_name}.size * {stride}"))
+            }
+            Some(_) => None,
+            None if is_flat(hir, elem_type) => Some(format!(
+                "value->{f_name}.size * sizeof *value->{f_name}.elems"
+            )),
+            None => None,
+        };
+        if let Some(len) = len {
+            writeln!(
+                out,
+                "  hash = bl_hash__bytes(hash, value->{f_name}.elems, {len});"
+            )?;
+            continue;
+        }
+        writeln!(
+            out,
+            "  for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
+        )?;
+        if stride.is_some() {
+            // elements that cannot be decoded are not equal to any, they are left out
+            let elem_typedef = ns.type_name(hir.symbols.get(elem_type).unwrap());
+            writeln!(out, "    {elem_typedef} elem;")?;
+            writeln!(
+                out,
+                "    if ({}(&value->{f_name}, i, &elem) > 0) {{",
+                view_get(hir, ns, elem_type)
+            )?;
+            writeln!(out, "      {}", hash_value(hir, ns, elem_type, "elem"))?;
+            writeln!(out, "    }}")?;
+        } else {
+            let elem = format!("value->{f_name}.elems[i]");
+            writeln!(out, "    {}", hash_value(hir, ns, elem_type, &elem))?;
+        }
+        writeln!(out, "  }}")?;
+    }
+    writeln!(out, "  return hash;")?;
+    writeln!(out, "}}")?;
     Ok(())
 }
 
-fn generate_header_file(filename: &str, outdir: &Path, hir: &Hir, sorted: &[&Type]) -> Result<()> {
-    let mut file = File::create(outdir.join(format!("{filename}.h")))?;
-    let mut buf = BufWriter::new(file);
-
-    writeln!(buf, "#ifndef BINLANG_{filename}_H_");
-    writeln!(buf, "#define BINLANG_{filename}_H_");
-    writeln!(buf);
-    writeln!(buf, "#include \"binlang.h\"");
-    writeln!(buf);
+/// The statement mixing `value`, which is not an array, into `hash`
+fn hash_value(hir: &Hir, ns: &Namespace, ty: SymbolId, value: &str) -> String {
+    match hir.types.get(&ty).unwrap() {
+        Type::Message(msg) => {
+            let fn_name = ns.fn_name(hir.symbols.get(msg.name).unwrap());
+            format!("hash = bl_hash__u64(hash, {ns}__hash_{fn_name}(&{value}));")
+        }
+        Type::Native(NativeType::U128 | NativeType::I128) => {
+            format!("hash = bl_hash__bytes(hash, &{value}, sizeof {value});")
+        }
+        Type::Native(NativeType::F32 | NativeType::F64) => {
+            format!("hash = bl_hash__double(hash, {value});")
+        }
+        Type::Native(_) | Type::Bitfield(_) => {
+            format!("hash = bl_hash__u64(hash, (uint64_t){value});")
+        }
+        Type::Array(_) => unreachable!("arrays of arrays are not supported"),
+    }
+}
 
-    let ns = to_c_name(filename, false);
-    for ty in sorted {
-        generate_forward_decl(hir, &ns, ty, &mut buf);
+/// Whether arrays of `ty` can be compared and hashed as plain memory: integers, booleans and
+/// bitfields, floating-point numbers having two zeros that are equal
+fn is_flat(hir: &Hir, ty: SymbolId) -> bool {
+    match hir.types.get(&ty).unwrap() {
+        Type::Native(NativeType::F32 | NativeType::F64) => false,
+        Type::Native(_) | Type::Bitfield(_) => true,
+        Type::Message(_) | Type::Array(_) => false,
     }
+}
 
-    writeln!(buf);
+/// Whether the fixed-size `ty` holds floating-point numbers, whose encodings cannot be compared
+fn holds_float(hir: &Hir, ty: SymbolId) -> bool {
+    match hir.types.get(&ty).unwrap() {
+        Type::Native(NativeType::F32 | NativeType::F64) => true,
+        Type::Message(msg) => msg.fields.iter().any(|field| holds_float(hir, field.ty)),
+        Type::Native(_) | Type::Bitfield(_) | Type::Array(_) => false,
+    }
+}
 
-    for ty in sorted {
-        if let Type::Bitfield(bitfield) = ty {
-            generate_bitfield(hir, bitfield, &mut buf);
+/// Whether decoding `ty` allocates, ie. it holds an array that is not a view, `visiting`
+/// holding the messages being checked to stop on recursive ones
+fn owns_memory(hir: &Hir, ty: SymbolId, mode: Mode, visiting: &mut Vec<SymbolId>) -> bool {
+    match hir.types.get(&ty) {
+        Some(Type::Array(ArrayType::Default(elem_type)))
+        | Some(Type::Array(ArrayType::Field { elem_type, .. })) => {
+            mode == Mode::Copy || !hir.wire_size(*elem_type).is_fixed()
+        }
+        Some(Type::Message(msg)) => {
+            if visiting.contains(&ty) {
+                return false;
+            }
+            visiting.push(ty);
+            let owns = msg
+                .fields
+                .iter()
+                .any(|field| owns_memory(hir, field.ty, mode, visiting));
+            visiting.pop();
+            owns
         }
+        _ => false,
     }
+}
 
-    for ty in sorted {
-        generate_type(hir, ty, &mut buf);
+fn generate_forward_decl<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &Type,
+    out: &mut W,
+) -> std::fmt::Result {
+    match ty {
+        Type::Message(ty) => {
+            let struct_name = hir.symbols.get(ty.name).unwrap();
+            let typedef = ns.type_name(struct_name);
+            writeln!(out, "typedef struct {} {typedef};", ns.tag(struct_name))?;
+        }
+        Type::Bitfield(ty) => {
+            let struct_name = hir.symbols.get(ty.name).unwrap();
+            let typedef = ns.type_name(struct_name);
+            writeln!(out, "typedef uint8_t {typedef};")?;
+        }
+        _ => (),
     }
+    Ok(())
+}
 
-    for ty in sorted {
-        generate_fn_forward_decl(hir, &ns, ty, &mut buf);
+fn generate_fn_forward_decl<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &Type,
+    config: &Config,
+    out: &mut W,
+) -> std::fmt::Result {
+    if let Type::Message(ty) = ty {
+        generate_impl_message(hir, ns, ty, config, true, out)?;
+        generate_free_message(hir, ns, ty, config, true, out)?;
+        generate_print_message(hir, ns, ty, config.mode, true, out)?;
+        generate_json_message(hir, ns, ty, config.mode, true, out)?;
+        generate_eq_message(hir, ns, ty, config.mode, true, out)?;
+        generate_clone_message(hir, ns, ty, config, true, out)?;
+        generate_hash_message(hir, ns, ty, config.mode, true, out)?;
     }
-
-    writeln!(buf);
-    writeln!(buf, "#endif // BINLANG_{filename}_H_");
-
-    let inner = buf.into_inner()?;
-
     Ok(())
 }
 
-fn generate_impl_file(filename: &str, outdir: &Path, hir: &Hir, sorted: &[&Type]) -> Result<()> {
-    let mut file = File::create(outdir.join(format!("{filename}.c")))?;
-    let mut buf = BufWriter::new(file);
-
-    writeln!(buf, "#include \"{filename}.h\"");
-    writeln!(buf);
-
-    let ns = to_c_name(filename, false);
-    for ty in sorted {
-        generate_impl_type(hir, &ns, ty, &mut buf);
+/// The encoded size of the elements of an array read as a view, `None` when it is copied
+fn view_stride(hir: &Hir, ns: &Namespace, elem: SymbolId, mode: Mode) -> Option<String> {
+    let size = hir.wire_size(elem);
+    if mode != Mode::View || !size.is_fixed() {
+        return None;
+    }
+    match hir.types.get(&elem) {
+        Some(Type::Message(msg)) => {
+            Some(ns.message_macro(hir.symbols.get(msg.name).unwrap(), "FIXED_SIZE"))
+        }
+        _ => Some(size.min.to_string()),
     }
-
-    Ok(())
 }
 
-fn generate_impl_type<W: Write>(hir: &Hir, ns: &str, ty: &Type, out: &mut W) {
-    if let Type::Message(ty) = ty {
-        generate_impl_message(hir, ns, ty, false, out)
+/// Element types of the views other than bytes, each needs a `get` function decoding an element
+fn view_elements(hir: &Hir, sorted: &[&Type], mode: Mode) -> Vec<SymbolId> {
+    let mut elems = Vec::new();
+    for ty in sorted {
+        let Type::Message(msg) = ty else {
+            continue;
+        };
+        for field in &msg.fields {
+            let elem = match hir.types.get(&field.ty) {
+                Some(Type::Array(ArrayType::Default(elem_type)))
+                | Some(Type::Array(ArrayType::Field { elem_type, .. })) => *elem_type,
+                _ => continue,
+            };
+            if mode == Mode::View
+                && hir.wire_size(elem).is_fixed()
+                && hir.symbols.get(elem).unwrap() != "u8"
+                && !elems.contains(&elem)
+            {
+                elems.push(elem);
+            }
+        }
     }
+    elems
 }
 
-fn generate_impl_message<W: Write>(
+/// Decodes the element `i` of a view on access, as the elements are left encoded in the buffer
+fn generate_view_get<W: Write>(
     hir: &Hir,
-    ns: &str,
-    ty: &MessageType,
+    ns: &Namespace,
+    elem: SymbolId,
+    config: &Config,
     forward_decl: bool,
     out: &mut W,
-) {
-    let name = hir.symbols.get(ty.name).unwrap();
-    let typedef = to_c_name(name, true);
-    let fn_name = to_c_name(name, false);
+) -> std::fmt::Result {
+    let elem_name = hir.symbols.get(elem).unwrap();
+    let fn_name = match hir.types.get(&elem).unwrap() {
+        Type::Native(_) => Cow::Borrowed(elem_name),
+        _ => ns.fn_name(elem_name),
+    };
+    let linkage = ns.linkage();
     write!(
         out,
-        "bl_result_t bl_{ns}__read_{fn_name}(bl_slice_t *b, {typedef} *value)"
-    );
+        "{linkage}bl_result_t {ns}__get_{fn_name}(const bl_view_t *view, uint32_t i, {} *value)",
+        ns.type_name(elem_name)
+    )?;
     if forward_decl {
-        writeln!(out, ";");
-        return;
+        writeln!(out, ";")?;
+        return Ok(());
     }
-    writeln!(out, " {{");
-    let indent = "  ";
-    for field in &ty.fields {
-        write!(out, "{indent}"); // indent
-        let f_name = match field.associated {
-            Some(associated_name) => Cow::Owned(format!(
-                "{}.size",
-                hir.symbols.get(associated_name).unwrap()
-            )),
-            None => Cow::Borrowed(hir.symbols.get(field.name).unwrap()),
-        };
-        match hir.types.get(&field.ty).unwrap() {
-            Type::Message(ty) => {
-                let f_ty_name = hir.symbols.get(ty.name).unwrap();
-                let f_typedef = to_c_name(f_ty_name, true);
-                let f_ty_fn_name = to_c_name(f_ty_name, false);
-                writeln!(
-                    out,
-                    "BL_TRY(bl_{ns}__read_{f_ty_fn_name}(b, &value->{f_name}));"
-                );
-            }
-            Type::Bitfield(ty) => {
-                writeln!(out, "BL_TRY(bl_slice__read_u8(b, &value->{f_name}));");
-            }
-            Type::Native(ty) => {
-                match ty {
-                    NativeType::U8 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_u8(b, &value->{f_name}));")
-                    }
-                    NativeType::U16 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_u16(b, &value->{f_name}));")
-                    }
-                    NativeType::U32 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_u32(b, &value->{f_name}));")
-                    }
-                    NativeType::U64 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_u64(b, &value->{f_name}));")
-                    }
-                    NativeType::I8 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_i8(b, &value->{f_name}));")
-                    }
-                    NativeType::I16 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_i16(b, &value->{f_name}));")
-                    }
-                    NativeType::I32 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_i32(b, &value->{f_name}));")
-                    }
-                    NativeType::I64 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_i64(b, &value->{f_name}));")
-                    }
-                    NativeType::VU32 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_vu32(b, &value->{f_name}));")
-                    }
-                    NativeType::VU64 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_vu64(b, &value->{f_name}));")
-                    }
-                    NativeType::VI32 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_vi32(b, &value->{f_name}));")
-                    }
-                    NativeType::VI64 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_vi64(b, &value->{f_name}));")
-                    }
-                    NativeType::F32 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_f32(b, &value->{f_name}));")
-                    }
-                    NativeType::F64 => {
-                        writeln!(out, "BL_TRY(bl_slice__read_f64(b, &value->{f_name}));")
-                    }
-                };
-            }
-            Type::Array(ArrayType::Default(type_id)) => {
-                let elem_ty = hir.symbols.get(*type_id).unwrap();
-                let elem_ty_name = to_c_name(elem_ty, false);
-                writeln!(out, "BL_TRY(bl_slice__read_u32(b, &value->{f_name}.size));");
-                writeln!(
-                    out,
-                    "{indent}array_reserve(&value->{f_name}, value->{f_name}.size);"
-                );
-                if elem_ty == "u8" {
-                    writeln!(
-                        out,
-                        "{indent}BL_TRY(bl_slice__read_exact(b, value->{f_name}.elems, value->{f_name}.size));"
-                    );
-                } else {
-                    writeln!(
-                        out,
-                        "{indent}for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
-                    );
-                    writeln!(
-                        out,
-                        "{indent}  BL_TRY(bl_{ns}__read_{elem_ty_name}(b, value->{f_name}.elems + i));"
-                    );
-                    writeln!(out, "{indent}}}");
-                }
-            }
-            Type::Array(ArrayType::Field {
-                elem_type,
-                field_name,
-                field_type,
-            }) => {
-                let elem_ty = hir.symbols.get(*elem_type).unwrap();
-                let elem_ty_name = to_c_name(elem_ty, false);
-                let field_ty = hir.symbols.get(*field_type).unwrap();
-                writeln!(
-                    out,
-                    "array_reserve(&value->{f_name}, value->{f_name}.size);"
-                );
-                if elem_ty == "u8" {
-                    writeln!(
-                        out,
-                        "{indent}BL_TRY(bl_slice__read_exact(b, value->{f_name}.elems, value->{f_name}.size));"
-                    );
-                } else {
-                    writeln!(
-                        out,
-                        "{indent}for (uint32_t i = 0; i < value->{f_name}.size; i++) {{"
-                    );
-                    writeln!(
-                        out,
-                        "{indent}  BL_TRY(bl_{ns}__read_{elem_ty_name}(b, value->{f_name}.elems + i));"
-                    );
-                    writeln!(out, "{indent}}}");
-                }
-            }
-        }
+    let stride = view_stride(hir, ns, elem, Mode::View).unwrap();
+    writeln!(out, " {{")?;
+    writeln!(out, "  if (i >= view->size) {{")?;
+    writeln!(out, "    return bl_result_err;")?;
+    writeln!(out, "  }}")?;
+    writeln!(
+        out,
+        "  bl_slice_t b = {{.data = (uint8_t *)view->elems + (size_t)i * {stride}, .len = {stride}}};"
+    )?;
+    // errors are reported by the return value only, the view has no path to record
+    let err = config.errors.then_some("NULL");
+    // fixed-size elements allocate nothing
+    let allocator = config.allocator.then_some("NULL");
+    let (call, _) = read_call(hir, ns, elem, "&b", "value", allocator, err);
+    writeln!(out, "  return {call};")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// The function decoding an element of a view of `elem`, see [`generate_view_get`]
+fn view_get(hir: &Hir, ns: &Namespace, elem: SymbolId) -> String {
+    let elem_name = hir.symbols.get(elem).unwrap();
+    match hir.types.get(&elem).unwrap() {
+        Type::Native(_) => format!("{ns}__get_{elem_name}"),
+        _ => format!("{ns}__get_{}", ns.fn_name(elem_name)),
     }
-    writeln!(out, "  return bl_result_ok;");
-    writeln!(out, "}}");
 }
 
-fn generate_forward_decl<W: Write>(hir: &Hir, ns: &str, ty: &Type, out: &mut W) {
-    match ty {
-        Type::Message(ty) => {
-            let struct_name = hir.symbols.get(ty.name).unwrap();
-            let typedef = to_c_name(struct_name, true);
-            writeln!(out, "typedef struct {struct_name} {typedef};");
+/// Hash of the wire layout of each message, see [`crate::schema::Message::fingerprint`]
+fn generate_fingerprints<W: Write>(
+    schema: &Schema,
+    ns: &Namespace,
+    names: bool,
+    out: &mut W,
+) -> std::fmt::Result {
+    if names {
+        writeln!(out, "/// Wire layout fingerprints, names included")?;
+    } else {
+        writeln!(out, "/// Wire layout fingerprints")?;
+    }
+    for msg in schema.messages() {
+        writeln!(
+            out,
+            "#define {} UINT64_C({:#018x})",
+            ns.message_macro(msg.name(), "FINGERPRINT"),
+            msg.fingerprint(names)
+        )?;
+    }
+    writeln!(out)
+}
+
+/// Encoded sizes and constant field offsets of each message, checked against the C types
+fn generate_layouts<W: Write>(schema: &Schema, ns: &Namespace, out: &mut W) -> std::fmt::Result {
+    for msg in schema.messages() {
+        let size = msg.wire_size();
+        writeln!(out, "/// Wire layout: {}, {size}", msg.name())?;
+        let define = |suffix: &str| format!("#define {}", ns.message_macro(msg.name(), suffix));
+        writeln!(out, "{} {}", define("MIN_SIZE"), size.min)?;
+        if let Some(max) = size.max {
+            writeln!(out, "{} {max}", define("MAX_SIZE"))?;
         }
-        Type::Bitfield(ty) => {
-            let struct_name = hir.symbols.get(ty.name).unwrap();
-            let typedef = to_c_name(struct_name, true);
-            writeln!(out, "typedef uint8_t {typedef};");
+        if size.is_fixed() {
+            writeln!(out, "{} {}", define("FIXED_SIZE"), size.min)?;
         }
-        _ => (),
+        for (field, offset) in msg.fixed_offsets() {
+            let field = to_c_name(field.name(), false).to_ascii_uppercase();
+            writeln!(out, "{} {offset}", define(&format!("{field}_OFFSET")))?;
+        }
+        writeln!(out)?;
     }
-}
 
-fn generate_fn_forward_decl<W: Write>(hir: &Hir, ns: &str, ty: &Type, out: &mut W) {
-    if let Type::Message(ty) = ty {
-        generate_impl_message(hir, ns, ty, true, out);
+    // fixed-size messages only hold fixed-size C types, whose sizes must match the wire ones
+    for msg in schema.messages().filter(|msg| msg.wire_size().is_fixed()) {
+        let sizes: Option<Vec<_>> = msg
+            .fields()
+            .map(|field| match field.ty() {
+                schema::Type::Native(NativeType::F32) => Some("sizeof(float)".to_string()),
+                schema::Type::Native(NativeType::F64) => Some("sizeof(double)".to_string()),
+                schema::Type::Native(ty) => {
+                    Some(format!("sizeof({})", to_c_name(ty.name(), false)))
+                }
+                schema::Type::Bitfield(_) => Some("sizeof(uint8_t)".to_string()),
+                schema::Type::Message(msg) => Some(ns.message_macro(msg.name(), "FIXED_SIZE")),
+                schema::Type::Array(_) => None,
+            })
+            .collect();
+        let Some(sizes) = sizes else {
+            continue;
+        };
+        let sum = if sizes.is_empty() {
+            "0".to_string()
+        } else {
+            sizes.join(" + ")
+        };
+        writeln!(
+            out,
+            "_Static_assert({} == {sum}, \"{}: C types do not match the wire sizes\");",
+            ns.message_macro(msg.name(), "FIXED_SIZE"),
+            msg.name()
+        )?;
     }
+    writeln!(out)
 }
 
-fn generate_type<W: Write>(hir: &Hir, ty: &Type, out: &mut W) {
+fn generate_type<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    ty: &Type,
+    mode: Mode,
+    out: &mut W,
+) -> std::fmt::Result {
     match ty {
-        Type::Message(ty) => generate_message(hir, ty, out),
-        Type::Bitfield(ty) => {}
-        Type::Native(ty) => {}
-        Type::Array(ty) => {}
+        Type::Message(ty) => generate_message(hir, ns, ty, mode, out),
+        Type::Bitfield(ty) => Ok(()),
+        Type::Native(ty) => Ok(()),
+        Type::Array(ty) => Ok(()),
     }
 }
 
-fn generate_message<W: Write>(hir: &Hir, msg: &MessageType, out: &mut W) {
-    writeln!(out, "struct {} {{", hir.symbols.get(msg.name).unwrap());
+fn generate_message<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    msg: &MessageType,
+    mode: Mode,
+    out: &mut W,
+) -> std::fmt::Result {
+    writeln!(
+        out,
+        "struct {} {{",
+        ns.tag(hir.symbols.get(msg.name).unwrap())
+    )?;
 
     for field in &msg.fields {
         if field.associated.is_some() {
@@ -273,58 +2265,69 @@ fn generate_message<W: Write>(hir: &Hir, msg: &MessageType, out: &mut W) {
             continue;
         }
         let field_ty = hir.types.get(&field.ty).unwrap();
-        write!(out, "  ");
+        write!(out, "  ")?;
         match field_ty {
+            Type::Array(ArrayType::Default(elem_type))
+            | Type::Array(ArrayType::Field { elem_type, .. })
+                if mode == Mode::View && hir.wire_size(*elem_type).is_fixed() =>
+            {
+                write!(out, "bl_view_t")?;
+            }
             Type::Array(ArrayType::Default(elem_type))
             | Type::Array(ArrayType::Field { elem_type, .. }) => {
                 write!(
                     out,
                     "BlArray({})",
-                    to_c_name(hir.symbols.get(*elem_type).unwrap(), true)
-                );
+                    ns.type_name(hir.symbols.get(*elem_type).unwrap())
+                )?;
             }
             _ => {
-                write!(
-                    out,
-                    "{}",
-                    to_c_name(hir.symbols.get(field.ty).unwrap(), true)
-                );
+                write!(out, "{}", ns.type_name(hir.symbols.get(field.ty).unwrap()))?;
             }
         }
-        writeln!(out, " {};", hir.symbols.get(field.name).unwrap());
+        writeln!(out, " {};", member(hir, field))?;
     }
 
-    writeln!(out, "}};\n");
+    writeln!(out, "}};\n")?;
+    Ok(())
 }
 
-fn generate_bitfield<W: Write>(hir: &Hir, bitfield: &BitfieldType, out: &mut W) {
+fn generate_bitfield<W: Write>(
+    hir: &Hir,
+    ns: &Namespace,
+    bitfield: &BitfieldType,
+    out: &mut W,
+) -> std::fmt::Result {
     let name = hir.symbols.get(bitfield.name).unwrap();
-    writeln!(out, "/// Bitfield: {name}");
+    writeln!(out, "/// Bitfield: {name}")?;
 
-    let upper_name = to_c_name(name, false).to_uppercase();
     for flag in &bitfield.flags {
-        let upper_flag_name = to_c_name(hir.symbols.get(flag.name).unwrap(), false).to_uppercase();
         writeln!(
             out,
-            "#define {upper_name}_{upper_flag_name} (1 << {})",
+            "#define {} (1 << {})",
+            ns.flag_macro(name, hir.symbols.get(flag.name).unwrap()),
             flag.offset
-        );
+        )?;
     }
 
-    writeln!(out);
+    writeln!(out)?;
+    Ok(())
 }
 
 /// TODO cache all those strings rather than always re-creating them on the spot
 fn to_c_name(input: &str, as_type: bool) -> Cow<'_, str> {
     match input {
+        "bool" => return Cow::Borrowed("bool"),
         "u8" => return Cow::Borrowed("uint8_t"),
         "u16" => return Cow::Borrowed("uint16_t"),
         "u32" | "vu32" => return Cow::Borrowed("uint32_t"),
         "u64" | "vu64" => return Cow::Borrowed("uint64_t"),
+        "u128" => return Cow::Borrowed("bl_u128_t"),
         "i8" => return Cow::Borrowed("int8_t"),
         "i16" => return Cow::Borrowed("int16_t"),
-        "i32" | "vi32" => return Cow::Borrowed("int32_t"),
-        "i64" | "vi64" => return Cow::Borrowed("int64_t"),
+        "i32" | "vi32" | "vzi32" => return Cow::Borrowed("int32_t"),
+        "i64" | "vi64" | "vzi64" => return Cow::Borrowed("int64_t"),
+        "i128" => return Cow::Borrowed("bl_i128_t"),
         _ => (),
     }
 
@@ -391,3 +2394,508 @@ fn topological_sort(hir: &Hir) -> Vec<&Type> {
 
     sorted
 }
+
+/// Generates the C code of the schema `source` with `options`, as `binlang gen -O key=value`
+#[cfg(test)]
+fn try_generate(source: &str, options: &[(&str, &str)]) -> Result<OutputFiles> {
+    let schema = compile([Source::new("test.bl", source)]).unwrap();
+    let options = options
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    CGenerator.generate("test", &schema, &options)
+}
+
+#[cfg(test)]
+fn generate(source: &str, options: &[(&str, &str)]) -> OutputFiles {
+    try_generate(source, options).unwrap()
+}
+
+#[test]
+fn view_mode() {
+    let schema = "message Root { n: vu32, name: u8[n], ids: u32[], syms: Sym[], }
+                  message Sym { size: vu32, text: u8[size], }";
+    let files = generate(schema, &[("mode", "view")]);
+    let header = &files[Path::new("test.h")];
+    let source = &files[Path::new("test.c")];
+
+    let err = try_generate(schema, &[("moed", "view")]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unknown option 'moed', expected one of: fingerprint, mode,")
+    );
+
+    assert!(header.contains("  bl_view_t name;\n  bl_view_t ids;\n  BlArray(sym_t) syms;\n"));
+    assert!(header.contains(
+        "bl_result_t bl_test__get_u32(const bl_view_t *view, uint32_t i, uint32_t *value);"
+    ));
+    assert!(!header.contains("bl_test__get_u8"));
+    assert!(source.contains("BL_TRY_GOTO(bl_slice__read_view(b, &value->ids, 4), res, fail);"));
+    assert!(source.contains("array_reserve(&value->syms, value->syms.size);"));
+    assert!(!source.contains("array_reserve(&value->name"));
+    // `Sym` only holds a view, its elements have nothing to free
+    assert!(!source.contains("bl_test__free_sym(value->syms.elems + i);"));
+    assert!(source.contains("array_delete(&value->syms);"));
+    assert!(!source.contains("array_delete(&value->ids);"));
+
+    assert!(try_generate(schema, &[("mode", "lazy")]).is_err());
+}
+
+#[cfg(test)]
+fn run_c(name: &str, files: &OutputFiles, main: &str) {
+    if std::process::Command::new("cc")
+        .arg("--version")
+        .output()
+        .is_err()
+    {
+        return;
+    }
+    let dir = std::env::temp_dir().join(format!("binlang-{name}-{}", std::process::id()));
+    write_files(&dir, files).unwrap();
+    std::fs::write(dir.join("main.c"), main).unwrap();
+
+    let runtime = Path::new(env!("CARGO_MANIFEST_DIR")).join("libs/c");
+    let mut cc = std::process::Command::new("cc");
+    cc.args(["-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(dir.join("main"))
+        .arg("-I")
+        .arg(&runtime)
+        .arg(dir.join("main.c"));
+    for path in files
+        .keys()
+        .filter(|path| path.extension() == Some("c".as_ref()))
+    {
+        cc.arg(dir.join(path));
+    }
+    // single headers embed the runtime
+    if !files
+        .values()
+        .any(|file| file.contains("BL_RUNTIME_IMPLEMENTATION"))
+    {
+        cc.arg(runtime.join("alloc.c"))
+            .arg(runtime.join("binlang.c"));
+    }
+    let output = cc.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = std::process::Command::new(dir.join("main"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn free_on_failure() {
+    let schema = "message Root { name: u8[], pts: Point[], }
+                  message Point { x: u16, tags: u8[], }";
+    let files = generate(schema, &[]);
+    let source = &files[Path::new("test.c")];
+
+    // only the elements read are freed along with the rest of the message
+    assert!(source.contains(
+        "    res = bl_test__read_point(b, value->pts.elems + i);\n    if (res <= 0) {\n      value->pts.size = i;\n      goto fail;\n    }"
+    ));
+    assert!(source.contains("fail:\n  bl_test__free_root(value);\n  return res;\n}"));
+
+    // every truncation of the input fails without leaking
+    let main = r#"#include "test.h"
+
+static long live = 0;
+
+static void *count_malloc(size_t size) {
+  live++;
+  return malloc(size);
+}
+
+static void *count_calloc(size_t count, size_t size) {
+  live++;
+  return calloc(count, size);
+}
+
+static void *count_realloc(void *ptr, size_t size) {
+  live += ptr == NULL;
+  return realloc(ptr, size);
+}
+
+static void count_free(void *ptr) {
+  live -= ptr != NULL;
+  free(ptr);
+}
+
+int main(void) {
+  uint8_t input[] = {2, 0, 0, 0, 'h', 'i', 2, 0, 0, 0, 1, 0, 1, 0, 0,
+                     0, 7, 2, 0, 3, 0, 0, 0, 7, 8, 9};
+  bl_current_malloc = count_malloc;
+  bl_current_calloc = count_calloc;
+  bl_current_realloc = count_realloc;
+  bl_current_free = count_free;
+  for (uint32_t len = 0; len <= sizeof input; len++) {
+    bl_slice_t b = {.data = input, .len = len};
+    root_t root;
+    bl_result_t res = bl_test__read_root(&b, &root);
+    if (res == bl_result_ok) {
+      bl_test__free_root(&root);
+    }
+    if ((res == bl_result_ok) != (len == sizeof input) || live != 0) {
+      printf("%u bytes: result %d, %ld allocations left\n", len, res, live);
+      return 1;
+    }
+  }
+  return 0;
+}
+"#;
+    run_c("free_on_failure", &files, main);
+}
+
+#[test]
+fn stream_mode() {
+    let schema = "message Root { n: vu32, name: u8[n], syms: Sym[], }
+                  message Sym { size: vu32, text: u8[size], }";
+    let files = generate(schema, &[("stream", "true")]);
+    let header = &files[Path::new("test.h")];
+    let source = &files[Path::new("test.c")];
+
+    assert!(header.contains("} bl_test__root_state_t;"));
+    assert!(header.contains(
+        "bl_result_t bl_test__feed_root(bl_test__root_state_t *state, const uint8_t *data, uint32_t len);"
+    ));
+    assert!(source.contains(
+        "BL_STREAM_TRY(bl_stream__read_bytes(b, value->name.elems, f->count, &f->index), res, fail);"
+    ));
+    // arrays grow with the elements received, not with the count read from the input
+    assert!(source.contains(
+        "BL_STREAM_TRY(bl_stream__reserve((BlArray *)&value->syms, sizeof *value->syms.elems, f->index + 1, f->count, NULL), res, fail);"
+    ));
+    assert!(source.contains(
+        "BL_STREAM_TRY(bl_test__stream_sym(s, depth + 1, b, value->syms.elems + f->index), res, fail);"
+    ));
+
+    assert!(try_generate(schema, &[("stream", "true"), ("mode", "view"),]).is_err());
+}
+
+#[test]
+fn errors_mode() {
+    let schema = "message Root { ok: bool, ids: vu32[], items: Item[], }
+                  message Item { a: u8, }";
+    let files = generate(schema, &[("errors", "true")]);
+    let source = &files[Path::new("test.c")];
+
+    assert!(source.contains(
+        "bl_result_t bl_test__read_root(bl_slice_t *b, root_t *value, bl_error_t *err) {"
+    ));
+    assert!(source.contains(
+        "BL_TRY_ERR(bl_slice__read_bool(b, &value->ok), res, fail, err, b, bl_error_constraint, \"ok\");"
+    ));
+    assert!(source.contains(
+        "BL_TRY_ERR(BL_RESERVED(&value->ids), res, fail, err, b, bl_error_alloc, \"ids\");"
+    ));
+    assert!(
+        source.contains("bl_error__record(err, res, bl_error_varint_overflow, b, \"ids\", i);")
+    );
+    assert!(source.contains("res = bl_test__read_item(b, value->items.elems + i, err);"));
+}
+
+#[test]
+fn allocation_limits() {
+    let schema = "message Root { n: u8, pts: Point[n], }
+                  message Point { x: u16, y: vu32, }";
+    let files = generate(schema, &[]);
+    let source = &files[Path::new("test.c")];
+    let check = source
+        .find("BL_TRY_GOTO(bl_slice__check_count(b, value->pts.size, 3, sizeof *value->pts.elems), res, fail);")
+        .unwrap();
+    assert!(check < source.find("array_reserve(&value->pts").unwrap());
+
+    let files = generate(schema, &[("stream", "true")]);
+    let source = &files[Path::new("test.c")];
+    assert!(source.contains(
+        "    f->count = value->pts.size;\n    value->pts.size = 0;\n    BL_STREAM_TRY(bl_slice__check_count(b, f->count, 0, sizeof *value->pts.elems), res, fail);"
+    ));
+}
+
+#[test]
+fn length_types() {
+    let schema = "message Root { n: u64, name: u8[n], m: u8, pts: Point[m], }
+                  message Point { k: vu64, ids: u16[k], }";
+    let files = generate(schema, &[("errors", "true")]);
+    let source = &files[Path::new("test.c")];
+    assert!(source.contains(
+        "BL_TRY_ERR(bl_slice__read_u64_len(b, &value->name.size), res, fail, err, b, bl_error_constraint, \"n\");"
+    ));
+    assert!(source.contains("bl_slice__read_u8_len(b, &value->pts.size)"));
+    let files = generate(schema, &[("stream", "true")]);
+    let source = &files[Path::new("test.c")];
+    assert!(
+        source.contains("BL_STREAM_TRY(bl_slice__read_vu64_len(b, &value->ids.size), res, fail);")
+    );
+
+    // lengths that do not fit the size of an array are rejected before it is allocated
+    let main = r#"#include "test.h"
+
+int main(void) {
+  uint8_t input[] = {2, 0, 0, 0, 0, 0, 0, 0, 'h', 'i', 1, 2, 1, 0, 0xff, 0xff};
+  bl_slice_t b = {.data = input, .len = sizeof input};
+  root_t root;
+  if (bl_test__read_root(&b, &root) != bl_result_ok || root.name.size != 2 ||
+      root.pts.elems[0].ids.size != 2 || root.pts.elems[0].ids.elems[1] != 0xffff) {
+    return 1;
+  }
+  bl_test__free_root(&root);
+  input[4] = 1;
+  b = (bl_slice_t){.data = input, .len = sizeof input};
+  return bl_test__read_root(&b, &root) == bl_result_err ? 0 : 2;
+}
+"#;
+    run_c("length_types", &generate(schema, &[]), main);
+}
+
+#[test]
+fn printers() {
+    let schema = "message Root { n: vu32, name: u8[n], pts: Point[], flags: Flags, }
+                  message Point { x: i32, }
+                  bitfield Flags { a: 0, b: 3, }";
+    let files = generate(schema, &[]);
+    let header = &files[Path::new("test.h")];
+    let source = &files[Path::new("test.c")];
+
+    assert!(
+        header.contains("void bl_test__print_root(FILE *out, const root_t *value, int indent);")
+    );
+    assert!(
+        source.contains("static bl_unused void bl_test__print_flags(FILE *out, flags_t value) {")
+    );
+    assert!(source.contains("  if (value & FLAGS_B) {\n    fprintf(out, \"%sb\", sep);"));
+    assert!(source.contains("  bl_print__bytes(out, value->name.elems, value->name.size);"));
+    assert!(source.contains("    bl_test__print_point(out, &value->pts.elems[i], indent + 2);"));
+    assert!(source.contains("  fprintf(out, \"%lld\\n\", (long long)value->x);"));
+    assert!(!source.contains("\"n: \""));
+}
+
+#[test]
+fn json_emitters() {
+    let schema = "message Root { n: vu32, name: u8[n], pts: Point[], big: u128, flags: Flags, }
+                  message Point { x: i32, y: u16, }
+                  bitfield Flags { a: 0, c: 2, }";
+    let files = generate(schema, &[("mode", "view")]);
+    let header = &files[Path::new("test.h")];
+    let source = &files[Path::new("test.c")];
+
+    assert!(
+        header.contains("bl_result_t bl_test__to_json_root(bl_json_t *json, const root_t *value);")
+    );
+    assert!(source.contains("  bl_json__key(json, \"name\");\n  bl_json__bytes(json, value->name.elems, value->name.size);"));
+    assert!(source.contains("    if (bl_test__get_point(&value->pts, i, &elem) <= 0) {"));
+    assert!(source.contains("    bl_test__to_json_point(json, &elem);"));
+    assert!(source.contains("  bl_json__u128(json, value->big);"));
+    assert!(source.contains("  bl_json__int(json, value->x);"));
+    // the same shape as `Node::to_json`: length fields and flag names
+    assert!(
+        source.contains("  bl_json__key(json, \"n\");\n  bl_json__uint(json, value->name.size);")
+    );
+    assert!(source.contains(
+        "  static const char *const names[8] = {\"a\", NULL, \"c\", NULL, NULL, NULL, NULL, NULL};"
+    ));
+    assert!(source.contains("  bl_test__to_json_flags(json, value->flags);"));
+}
+
+#[test]
+fn eq_clone_hash() {
+    let schema = "message Root { ids: u32[], pts: Point[], f: f64, }
+                  message Point { x: i32, syms: u8[], }";
+    let files = generate(schema, &[]);
+    let source = &files[Path::new("test.c")];
+
+    assert!(source.contains("  if (a->ids.size > 0 && memcmp(a->ids.elems, b->ids.elems, a->ids.size * sizeof *a->ids.elems) != 0) {"));
+    assert!(source.contains("    if (!bl_test__eq_point(a->pts.elems + i, b->pts.elems + i)) {"));
+    assert!(
+        source.contains("    res = bl_test__clone_point(dst->pts.elems + i, src->pts.elems + i);")
+    );
+    assert!(source.contains("  hash = bl_hash__bytes(hash, value->ids.elems, value->ids.size * sizeof *value->ids.elems);"));
+    assert!(
+        source
+            .contains("    hash = bl_hash__u64(hash, bl_test__hash_point(&value->pts.elems[i]));")
+    );
+    assert!(source.contains("  hash = bl_hash__double(hash, value->f);"));
+
+    // views of floats are decoded to be compared, 0.0 and -0.0 being equal
+    let schema = "message Root { ids: u32[], pts: Point[], }
+                  message Point { x: f32, y: u8, }";
+    let files = generate(schema, &[("mode", "view")]);
+    let source = &files[Path::new("test.c")];
+    assert!(source.contains("memcmp(a->ids.elems, b->ids.elems, (size_t)a->ids.size * 4)"));
+    assert!(source.contains("    if (bl_test__get_point(&a->pts, i, &elem_a) <= 0 || bl_test__get_point(&b->pts, i, &elem_b) <= 0) {"));
+    assert!(source.contains("    if (!bl_test__eq_point(&elem_a, &elem_b)) {"));
+    assert!(source.contains("    if (bl_test__get_point(&value->pts, i, &elem) > 0) {\n      hash = bl_hash__u64(hash, bl_test__hash_point(&elem));"));
+}
+
+#[test]
+fn single_header() {
+    let files = generate("message Root { a: u8, }", &[("single_header", "true")]);
+    assert_eq!(files.len(), 1);
+    let header = &files[Path::new("test.h")];
+
+    assert!(!header.contains("\n#include \""));
+    let declaration = header.find("typedef struct Root root_t;").unwrap();
+    assert!(header.find("#define binlang_h").unwrap() < declaration);
+    // the runtime is compiled once per program, the readers once per header
+    let runtime = header.find("#ifdef BL_RUNTIME_IMPLEMENTATION").unwrap();
+    let implementation = header.find("#ifdef BL_TEST_IMPLEMENTATION").unwrap();
+    let read_u8 = header
+        .find("bl_result_t bl_slice__read_u8(bl_slice_t *b, uint8_t *value) {")
+        .unwrap();
+    assert!(runtime < read_u8 && read_u8 < implementation);
+    // only the sections of the runtime used by the options are embedded
+    assert!(!header.contains("binlang:"));
+    assert!(!header.contains("bl_stream_t"));
+    assert!(!header.contains("bl_error_t"));
+    assert!(!header.contains("bl_arena_t"));
+    assert!(
+        header
+            .find("bl_result_t bl_test__read_root(bl_slice_t *b, root_t *value) {")
+            .unwrap()
+            > implementation
+    );
+    assert!(header.ends_with("#endif // BL_TEST_IMPLEMENTATION\n"));
+}
+
+#[test]
+fn custom_allocator() {
+    let schema = "message Root { pts: Point[], p: Point, }
+                  message Point { xs: u8[], }";
+    let files = generate(schema, &[("allocator", "true"), ("stream", "true")]);
+    let source = &files[Path::new("test.c")];
+
+    assert!(source.contains(
+        "bl_result_t bl_test__read_root(bl_slice_t *b, root_t *value, bl_allocator_t *allocator) {"
+    ));
+    assert!(source.contains("  array_reserve_in(&value->pts, value->pts.size, allocator);\n  BL_TRY_GOTO(BL_RESERVED(&value->pts), res, fail);"));
+    assert!(source.contains("    res = bl_test__read_point(b, value->pts.elems + i, allocator);"));
+    assert!(source.contains("  array_delete_in(&value->pts, allocator);"));
+    assert!(
+        source.contains(
+            "  BL_TRY_GOTO(bl_test__clone_point(&dst->p, &src->p, allocator), res, fail);"
+        )
+    );
+    assert!(source.contains(
+        "BL_STREAM_TRY(bl_stream__reserve((BlArray *)&value->pts, sizeof *value->pts.elems, f->index + 1, f->count, s->allocator), res, fail);"
+    ));
+    assert!(source.contains("  bl_test__free_root(value, s->allocator);"));
+}
+
+#[test]
+fn naming() {
+    let schema = r#"@c_name("kind") message Type { @c_name("enum_") enum: u8, n: vu32, data: u8[n], }
+                    message TypeInfo { t: Type, }
+                    bitfield TypeFlags { enum: 2, }"#;
+    let files = generate(
+        schema,
+        &[
+            ("prefix", "gc"),
+            ("type_suffix", ""),
+            ("case", "pascal"),
+            ("linkage", "static_inline"),
+        ],
+    );
+    assert!(!files.contains_key(Path::new("test.c")));
+    let header = &files[Path::new("test.h")];
+
+    assert!(header.contains("typedef struct kind kind;\ntypedef struct TypeInfo TypeInfo;\n"));
+    assert!(header.contains("#define TYPE_FLAGS_ENUM (1 << 2)"));
+    assert!(header.contains("#define GC_TEST__KIND_FINGERPRINT"));
+    assert!(header.contains("struct kind {\n  uint8_t enum_;\n  BlArray(uint8_t) data;\n};"));
+    assert!(header.contains(
+        "static inline bl_result_t gc_test__read_TypeInfo(bl_slice_t *b, TypeInfo *value);"
+    ));
+    assert!(
+        header
+            .contains("static inline bl_result_t gc_test__read_kind(bl_slice_t *b, kind *value) {")
+    );
+    assert!(header.contains("  BL_TRY_GOTO(bl_slice__read_u8(b, &value->enum_), res, fail);"));
+    // printers and JSON keep the schema names
+    assert!(header.contains("  fprintf(out, \"enum: \");"));
+
+    let err = try_generate("message Type { enum: u8, }", &[]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("field 'Type.enum' is named 'enum' in C")
+    );
+    assert!(try_generate("message int { a: u8, }", &[("type_suffix", "")]).is_err());
+
+    let err = try_generate("bitfield A { b_c: 0, } bitfield A_b { c: 1, }", &[]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("'A_B_C' is defined several times in C")
+    );
+    let err = try_generate("message A { b_c: u8, } message A_b { c: u8, }", &[]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("'BL_TEST__A_B_C_OFFSET' is defined several times in C")
+    );
+    let err = try_generate("bitfield _b { c: 0, }", &[]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("defines the macro '_B_C' in C, which is reserved")
+    );
+    let err = try_generate(r#"@c_name("MyFoo") message A { } message MyFoo { }"#, &[]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("'MyFoo' tags several structs in C")
+    );
+    let err = try_generate(r#"message A { @cname("k") n: u8, }"#, &[]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unknown decorator @cname on 'n', expected @c_name")
+    );
+    let err = try_generate(r#"message A { @cname("k") n: u32, xs: u8[n], }"#, &[]).unwrap_err();
+    assert!(err.to_string().contains("unknown decorator @cname on 'n'"));
+}
+
+/// Compiles the generated `files` with `main` and the runtime, then runs the program. Skipped
+/// where there is no C compiler.
+
+#[test]
+fn option_matrix() {
+    let schema = "message Root { ok: bool, n: u64, name: u8[n], ids: vu32[], pts: Point[], big: i128, flags: Flags, }
+                  message Point { x: vzi64, tags: u16[], }
+                  bitfield Flags { a: 0, c: 2, }";
+    let main = "#define BL_RUNTIME_IMPLEMENTATION\n#define BL_TEST_IMPLEMENTATION\n#include \"test.h\"\n\nint main(void) { return 0; }\n";
+    // every combination of the options builds without warnings
+    let mut matrix = vec![vec![]];
+    for (key, values) in [
+        ("mode", ["copy", "view"]),
+        ("stream", ["false", "true"]),
+        ("errors", ["false", "true"]),
+        ("allocator", ["false", "true"]),
+        ("single_header", ["false", "true"]),
+        ("linkage", ["extern", "static_inline"]),
+    ] {
+        matrix = matrix
+            .into_iter()
+            .flat_map(|options: Vec<(&str, &str)>| {
+                values.map(|value| [options.as_slice(), &[(key, value)]].concat())
+            })
+            .collect();
+    }
+    std::thread::scope(|scope| {
+        for (i, options) in matrix.iter().enumerate() {
+            match try_generate(schema, options) {
+                Ok(files) => {
+                    scope.spawn(move || run_c(&format!("option_matrix-{i}"), &files, main));
+                }
+                // streamed views would point into chunks the caller reuses
+                Err(_) => assert!(
+                    options.contains(&("mode", "view")) && options.contains(&("stream", "true"))
+                ),
+            }
+        }
+    });
+}
@@ -1 +1,132 @@
-pub mod gen_c;
\ No newline at end of file
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::Schema;
+
+pub mod gen_c;
+
+/// Generator options, given as `key=value` on the command line
+pub type Options = BTreeMap<String, String>;
+
+/// Generated files, keyed by their path relative to the output directory
+pub type OutputFiles = BTreeMap<PathBuf, String>;
+
+/// A code generator for a given target language
+pub trait Generator {
+    /// Generates the files of `schema`, `name` is the base name of the generated files (usually
+    /// the stem of the schema file)
+    fn generate(&self, name: &str, schema: &Schema, options: &Options) -> Result<OutputFiles>;
+}
+
+/// Generators keyed by target name
+#[derive(Default)]
+pub struct Registry {
+    generators: BTreeMap<String, Box<dyn Generator>>,
+}
+
+impl Registry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with all the generators shipped with binlang
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register("c", gen_c::CGenerator);
+        registry
+    }
+
+    /// Registers `generator` for `target`, replacing any previous one
+    pub fn register(&mut self, target: impl Into<String>, generator: impl Generator + 'static) {
+        self.generators.insert(target.into(), Box::new(generator));
+    }
+
+    pub fn get(&self, target: &str) -> Option<&dyn Generator> {
+        self.generators.get(target).map(|generator| &**generator)
+    }
+
+    /// Names of the registered targets, sorted
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.generators.keys().map(String::as_str)
+    }
+}
+
+/// Writes `files` into `outdir`, creating the missing directories
+pub fn write_files(outdir: &Path, files: &OutputFiles) -> std::io::Result<()> {
+    for (path, contents) in files {
+        let path = outdir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+    }
+    Ok(())
+}
+
+/// Returns the paths of `files` that are missing from `outdir` or whose content differs
+pub fn stale_files(outdir: &Path, files: &OutputFiles) -> std::io::Result<Vec<PathBuf>> {
+    let mut stale = Vec::new();
+    for (path, contents) in files {
+        match std::fs::read(outdir.join(path)) {
+            Ok(existing) if existing == contents.as_bytes() => (),
+            Ok(_) => stale.push(path.clone()),
+            Err(err) if err.kind() == ErrorKind::NotFound => stale.push(path.clone()),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(stale)
+}
+
+#[test]
+fn custom_generator() {
+    struct Names;
+
+    impl Generator for Names {
+        fn generate(&self, name: &str, schema: &Schema, options: &Options) -> Result<OutputFiles> {
+            let sep = options.get("sep").map_or("\n", String::as_str);
+            let names: Vec<_> = schema.messages().map(|msg| msg.name()).collect();
+            Ok(OutputFiles::from([(
+                PathBuf::from(format!("{name}.txt")),
+                names.join(sep),
+            )]))
+        }
+    }
+
+    let mut registry = Registry::with_defaults();
+    registry.register("names", Names);
+    assert_eq!(registry.targets().collect::<Vec<_>>(), ["c", "names"]);
+
+    let schema = crate::compile([crate::Source::new("a.bl", "message A {} message B {}")]).unwrap();
+    let options = Options::from([("sep".to_string(), ",".to_string())]);
+    let files = registry
+        .get("names")
+        .unwrap()
+        .generate("a", &schema, &options)
+        .unwrap();
+    assert_eq!(files[&PathBuf::from("a.txt")], "A,B");
+}
+
+#[test]
+fn stale() {
+    let outdir = std::env::temp_dir().join(format!("binlang-stale-{}", std::process::id()));
+    let mut files = OutputFiles::from([
+        (PathBuf::from("a.h"), "a".to_string()),
+        (PathBuf::from("nested/b.c"), "b".to_string()),
+    ]);
+    assert_eq!(stale_files(&outdir, &files).unwrap().len(), 2);
+
+    write_files(&outdir, &files).unwrap();
+    assert!(stale_files(&outdir, &files).unwrap().is_empty());
+
+    files.insert(PathBuf::from("a.h"), "changed".to_string());
+    assert_eq!(
+        stale_files(&outdir, &files).unwrap(),
+        [PathBuf::from("a.h")]
+    );
+
+    std::fs::remove_dir_all(&outdir).unwrap();
+}
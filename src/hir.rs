@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
-    ast::*,
+    ast::{self, *},
+    error::HirError,
     symbols::{SymbolId, Symbols},
 };
 
@@ -9,38 +10,79 @@ pub struct Hir {
     pub root: SymbolId,
     pub symbols: Symbols,
     pub types: HashMap<SymbolId, Type>,
+    /// messages and bitfields, in declaration order
+    pub defs: Vec<SymbolId>,
 }
 
 impl Hir {
-    pub fn new(file: &File) -> Self {
+    pub fn new(file: &File) -> Result<Self, Vec<HirError>> {
+        Self::from_files(std::slice::from_ref(file))
+            .map_err(|errors| errors.into_iter().map(|(_, err)| err).collect())
+    }
+
+    /// Builds the HIR of several files sharing the same namespace, errors are paired with the
+    /// index of the file they originate from
+    pub fn from_files(files: &[File]) -> Result<Self, Vec<(usize, HirError)>> {
+        let all_defs = || {
+            files
+                .iter()
+                .enumerate()
+                .flat_map(|(i, file)| file.defs.iter().map(move |def| (i, def)))
+        };
+        let mut errors = Vec::new();
+        let mut defs = Vec::new();
         let mut symbols = Symbols::new();
         let root = symbols.insert("");
         let natives = NativeTypeSymbols::new(&mut symbols);
         let mut types = HashMap::new();
 
+        types.insert(natives.bool, Type::Native(NativeType::Bool));
         types.insert(natives.i8, Type::Native(NativeType::I8));
         types.insert(natives.i16, Type::Native(NativeType::I16));
         types.insert(natives.i32, Type::Native(NativeType::I32));
         types.insert(natives.i64, Type::Native(NativeType::I64));
+        types.insert(natives.i128, Type::Native(NativeType::I128));
         types.insert(natives.u8, Type::Native(NativeType::U8));
         types.insert(natives.u16, Type::Native(NativeType::U16));
         types.insert(natives.u32, Type::Native(NativeType::U32));
         types.insert(natives.u64, Type::Native(NativeType::U64));
+        types.insert(natives.u128, Type::Native(NativeType::U128));
         types.insert(natives.vi32, Type::Native(NativeType::VI32));
         types.insert(natives.vi64, Type::Native(NativeType::VI64));
+        types.insert(natives.vzi32, Type::Native(NativeType::VZI32));
+        types.insert(natives.vzi64, Type::Native(NativeType::VZI64));
         types.insert(natives.vu32, Type::Native(NativeType::VU32));
         types.insert(natives.vu64, Type::Native(NativeType::VU64));
-
-        for def in &file.defs {
+        types.insert(natives.f32, Type::Native(NativeType::F32));
+        types.insert(natives.f64, Type::Native(NativeType::F64));
+
+        for (source, def) in all_defs() {
+            let (name, name_span) = match def {
+                TopLevel::Message(message) => (&message.name, message.name_span),
+                TopLevel::Bitfield(bitfield) => (&bitfield.name, bitfield.name_span),
+            };
+            if symbols.find(name).is_some_and(|id| types.contains_key(&id)) {
+                errors.push((
+                    source,
+                    HirError::DuplicateDefinition {
+                        name: name.clone(),
+                        span: name_span,
+                    },
+                ));
+                continue;
+            }
             match def {
                 TopLevel::Message(message) => {
                     let id = symbols.insert(&message.name);
-                    let ty = MessageType::new(id);
+                    let mut ty = MessageType::new(id);
+                    ty.decorator = Decorator::new(message.decorator.as_ref(), &mut symbols);
                     types.insert(id, Type::Message(ty));
+                    defs.push(id);
                 }
                 TopLevel::Bitfield(bitfield) => {
                     let id = symbols.insert(&bitfield.name);
                     let mut ty = BitfieldType::new(id);
+                    ty.decorator = Decorator::new(bitfield.decorator.as_ref(), &mut symbols);
                     for flag in &bitfield.flags {
                         let flag_name = symbols.insert(&flag.name);
                         ty.flags.push(Bitflag {
@@ -49,34 +91,60 @@ impl Hir {
                         });
                     }
                     types.insert(id, Type::Bitfield(ty));
+                    defs.push(id);
                 }
             }
         }
 
         // we now have all types defined, let's dive in the fields
-        for def in &file.defs {
+        for (source, def) in all_defs() {
             if let TopLevel::Message(msg) = def {
                 let mut associated_fields = HashMap::new();
                 for field in &msg.fields {
                     if let TypeExpr::ArrayWithField(ident, associated_name) = &field.ty {
                         let field_name = symbols.insert(&field.name);
-                        let associated_field = msg
-                            .fields
-                            .iter()
-                            .find(|f| &f.name == associated_name)
-                            .unwrap_or_else(|| {
-                                panic!(
-                                    "referenced field '{associated_name}' in array {} is unknown",
-                                    field.name
-                                )
-                            });
-                        let associated_field_type = type_expr_to_type_id(
+                        // unknown fields or types are reported when resolving the fields below
+                        let Some(position) =
+                            msg.fields.iter().position(|f| &f.name == associated_name)
+                        else {
+                            continue;
+                        };
+                        let associated_field = &msg.fields[position];
+                        // readers need the length before the elements
+                        if msg.fields[..position].iter().any(|f| f.name == field.name) {
+                            errors.push((
+                                source,
+                                HirError::LengthAfterArray {
+                                    name: associated_name.clone(),
+                                    array: field.name.clone(),
+                                    span: field.size_span.unwrap_or(field.ty_span),
+                                },
+                            ));
+                        }
+                        let unsigned = matches!(
                             &associated_field.ty,
+                            TypeExpr::Ident(TypeIdent::Native(native)) if native.is_unsigned()
+                        );
+                        if !unsigned {
+                            errors.push((
+                                source,
+                                HirError::InvalidLengthType {
+                                    name: associated_name.clone(),
+                                    ty: associated_field.ty.to_string(),
+                                    span: associated_field.ty_span,
+                                },
+                            ));
+                        }
+                        let Ok(associated_field_type) = type_expr_to_type_id(
+                            &associated_field.ty,
+                            associated_field,
                             &mut symbols,
                             &natives,
                             &mut types,
                             &associated_fields,
-                        );
+                        ) else {
+                            continue;
+                        };
                         associated_fields.insert(
                             associated_name.as_str(),
                             AssociatedField {
@@ -90,31 +158,44 @@ impl Hir {
                 let mut fields = Vec::with_capacity(msg.fields.len());
                 for field in &msg.fields {
                     let field_name = symbols.insert(&field.name);
-                    let field_type = type_expr_to_type_id(
+                    let field_type = match type_expr_to_type_id(
                         &field.ty,
+                        field,
                         &mut symbols,
                         &natives,
                         &mut types,
                         &associated_fields,
-                    );
+                    ) {
+                        Ok(field_type) => field_type,
+                        Err(err) => {
+                            errors.push((source, err));
+                            continue;
+                        }
+                    };
                     fields.push(Field {
                         name: field_name,
                         ty: field_type,
                         associated: associated_fields.get(&*field.name).map(|a| a.array_field),
+                        decorator: Decorator::new(field.decorator.as_ref(), &mut symbols),
                     });
                 }
                 let msg_name_id = symbols.find(&msg.name).unwrap();
-                if let Type::Message(ty) = types.get_mut(&msg_name_id).unwrap() {
+                if let Some(Type::Message(ty)) = types.get_mut(&msg_name_id) {
                     ty.fields = fields;
                 }
             }
         }
 
-        Self {
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Self {
             root,
             symbols,
             types,
-        }
+            defs,
+        })
     }
 }
 
@@ -226,16 +307,21 @@ impl std::fmt::Debug for HirDebugBitfieldType<'_> {
 }
 
 pub struct NativeTypeSymbols {
+    pub bool: SymbolId,
     pub i8: SymbolId,
     pub i16: SymbolId,
     pub i32: SymbolId,
     pub i64: SymbolId,
+    pub i128: SymbolId,
     pub u8: SymbolId,
     pub u16: SymbolId,
     pub u32: SymbolId,
     pub u64: SymbolId,
+    pub u128: SymbolId,
     pub vi32: SymbolId,
     pub vi64: SymbolId,
+    pub vzi32: SymbolId,
+    pub vzi64: SymbolId,
     pub vu32: SymbolId,
     pub vu64: SymbolId,
     pub f32: SymbolId,
@@ -245,16 +331,21 @@ pub struct NativeTypeSymbols {
 impl NativeTypeSymbols {
     fn new(symbols: &mut Symbols) -> Self {
         Self {
+            bool: symbols.insert("bool"),
             i8: symbols.insert("i8"),
             i16: symbols.insert("i16"),
             i32: symbols.insert("i32"),
             i64: symbols.insert("i64"),
+            i128: symbols.insert("i128"),
             u8: symbols.insert("u8"),
             u16: symbols.insert("u16"),
             u32: symbols.insert("u32"),
             u64: symbols.insert("u64"),
+            u128: symbols.insert("u128"),
             vi32: symbols.insert("vi32"),
             vi64: symbols.insert("vi64"),
+            vzi32: symbols.insert("vzi32"),
+            vzi64: symbols.insert("vzi64"),
             vu32: symbols.insert("vu32"),
             vu64: symbols.insert("vu64"),
             f32: symbols.insert("f32"),
@@ -264,18 +355,23 @@ impl NativeTypeSymbols {
 
     pub fn type_id(&self, ty: NativeType) -> SymbolId {
         match ty {
+            NativeType::Bool => self.bool,
             NativeType::U8 => self.u8,
             NativeType::U16 => self.u16,
             NativeType::U32 => self.u32,
             NativeType::U64 => self.u64,
+            NativeType::U128 => self.u128,
             NativeType::I8 => self.i8,
             NativeType::I16 => self.i16,
             NativeType::I32 => self.i32,
             NativeType::I64 => self.i64,
+            NativeType::I128 => self.i128,
             NativeType::VU32 => self.vu32,
             NativeType::VU64 => self.vu64,
             NativeType::VI32 => self.vi32,
             NativeType::VI64 => self.vi64,
+            NativeType::VZI32 => self.vzi32,
+            NativeType::VZI64 => self.vzi64,
             NativeType::F32 => self.f32,
             NativeType::F64 => self.f64,
         }
@@ -324,12 +420,30 @@ impl Type {
 pub struct MessageType {
     pub name: SymbolId,
     pub fields: Vec<Field>,
+    pub decorator: Option<Decorator>,
 }
 
 pub struct Field {
     pub name: SymbolId,
     pub ty: SymbolId,
+    /// the array field whose length is given by this field
     pub associated: Option<SymbolId>,
+    pub decorator: Option<Decorator>,
+}
+
+/// A decorator of a definition or a field, eg. `@c_name("kind")`
+pub struct Decorator {
+    pub name: SymbolId,
+    pub arg: Option<String>,
+}
+
+impl Decorator {
+    fn new(decorator: Option<&ast::Decorator>, symbols: &mut Symbols) -> Option<Self> {
+        decorator.map(|decorator| Self {
+            name: symbols.insert(&decorator.name),
+            arg: decorator.arg.clone(),
+        })
+    }
 }
 
 impl MessageType {
@@ -337,6 +451,7 @@ impl MessageType {
         Self {
             name,
             fields: Default::default(),
+            decorator: None,
         }
     }
 }
@@ -344,6 +459,7 @@ impl MessageType {
 pub struct BitfieldType {
     pub name: SymbolId,
     pub flags: Vec<Bitflag>,
+    pub decorator: Option<Decorator>,
 }
 
 pub struct Bitflag {
@@ -356,6 +472,7 @@ impl BitfieldType {
         Self {
             name,
             flags: Default::default(),
+            decorator: None,
         }
     }
 }
@@ -367,71 +484,160 @@ struct AssociatedField {
 
 fn type_expr_to_type_id(
     ty: &TypeExpr,
+    field: &ast::Field,
     symbols: &mut Symbols,
     natives: &NativeTypeSymbols,
     types: &mut HashMap<SymbolId, Type>,
     associated_fields: &HashMap<&str, AssociatedField>,
-) -> SymbolId {
+) -> Result<SymbolId, HirError> {
+    let find_type = |symbols: &Symbols, name: &str| {
+        symbols
+            .find(name)
+            .filter(|id| types.contains_key(id))
+            .ok_or_else(|| HirError::UndefinedType {
+                name: name.to_string(),
+                span: field.ty_span,
+            })
+    };
     match ty {
         TypeExpr::Ident(ty) => match ty {
-            TypeIdent::Native(native_type) => natives.type_id(*native_type),
-            TypeIdent::Custom(name) => symbols
-                .find(name)
-                .unwrap_or_else(|| panic!("use of undefined type '{name}'")),
+            TypeIdent::Native(native_type) => Ok(natives.type_id(*native_type)),
+            TypeIdent::Custom(name) => find_type(symbols, name),
         },
-        TypeExpr::ArrayNoField(ty) => match ty {
-            TypeIdent::Native(native_type) => {
-                let generic_id = natives.type_id(*native_type);
-                let name = symbols.get(generic_id).unwrap();
-                let array_id = symbols.insert(format!("{name}[]"));
-                types.insert(array_id, Type::Array(ArrayType::Default(generic_id)));
-                array_id
-            }
-            TypeIdent::Custom(name) => {
-                let generic_id = symbols
-                    .find(name)
-                    .unwrap_or_else(|| panic!("use of undefined type '{name}'"));
-                let array_id = symbols.insert(format!("{name}[]"));
-                types.insert(array_id, Type::Array(ArrayType::Default(generic_id)));
-                array_id
-            }
-        },
-        TypeExpr::ArrayWithField(ty, field) => {
-            let AssociatedField {
+        TypeExpr::ArrayNoField(ty) => {
+            let generic_id = match ty {
+                TypeIdent::Native(native_type) => natives.type_id(*native_type),
+                TypeIdent::Custom(name) => find_type(symbols, name)?,
+            };
+            let name = symbols.get(generic_id).unwrap();
+            let array_id = symbols.insert(format!("{name}[]"));
+            types.insert(array_id, Type::Array(ArrayType::Default(generic_id)));
+            Ok(array_id)
+        }
+        TypeExpr::ArrayWithField(ty, associated) => {
+            let elem_type = match ty {
+                TypeIdent::Native(native_type) => natives.type_id(*native_type),
+                TypeIdent::Custom(name) => find_type(symbols, name)?,
+            };
+            let Some(AssociatedField {
                 ty: associated_type,
                 ..
-            } = associated_fields.get(field.as_str()).unwrap();
-            match ty {
-                TypeIdent::Native(native_type) => {
-                    let elem_type = natives.type_id(*native_type);
-                    let name = symbols.get(elem_type).unwrap();
-                    let array_id = symbols.insert(format!("{name}[{field}]"));
-                    types.insert(
-                        array_id,
-                        Type::Array(ArrayType::Field {
-                            elem_type,
-                            field_name: symbols.insert(field),
-                            field_type: *associated_type,
-                        }),
-                    );
-                    array_id
-                }
-                TypeIdent::Custom(name) => {
-                    let elem_type = symbols
-                        .find(name)
-                        .unwrap_or_else(|| panic!("use of undefined type '{name}'"));
-                    let array_id = symbols.insert(format!("{name}[{field}]"));
-                    types.insert(
-                        array_id,
-                        Type::Array(ArrayType::Field {
-                            elem_type,
-                            field_name: symbols.insert(field),
-                            field_type: *associated_type,
-                        }),
-                    );
-                    array_id
+            }) = associated_fields.get(associated.as_str())
+            else {
+                return Err(HirError::UnknownField {
+                    name: associated.clone(),
+                    array: field.name.clone(),
+                    span: field.size_span.unwrap_or(field.ty_span),
+                });
+            };
+            let name = symbols.get(elem_type).unwrap();
+            let array_id = symbols.insert(format!("{name}[{associated}]"));
+            types.insert(
+                array_id,
+                Type::Array(ArrayType::Field {
+                    elem_type,
+                    field_name: symbols.insert(associated),
+                    field_type: *associated_type,
+                }),
+            );
+            Ok(array_id)
+        }
+    }
+}
+
+/// Bounds of the encoded size of a type, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireSize {
+    pub min: usize,
+    /// `None` when the size is unbounded (eg. arrays)
+    pub max: Option<usize>,
+}
+
+impl WireSize {
+    pub const fn fixed(size: usize) -> Self {
+        Self {
+            min: size,
+            max: Some(size),
+        }
+    }
+
+    pub const fn unbounded(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.max == Some(self.min)
+    }
+}
+
+impl std::ops::Add for WireSize {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            min: self.min + rhs.min,
+            max: self.max.zip(rhs.max).map(|(a, b)| a + b),
+        }
+    }
+}
+
+impl std::fmt::Display for WireSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max} bytes"),
+            Some(max) => write!(f, "{}..={max} bytes", self.min),
+            None => write!(f, "at least {} bytes", self.min),
+        }
+    }
+}
+
+impl NativeType {
+    pub fn wire_size(&self) -> WireSize {
+        match self {
+            Self::Bool | Self::U8 | Self::I8 => WireSize::fixed(1),
+            Self::U16 | Self::I16 => WireSize::fixed(2),
+            Self::U32 | Self::I32 | Self::F32 => WireSize::fixed(4),
+            Self::U64 | Self::I64 | Self::F64 => WireSize::fixed(8),
+            Self::U128 | Self::I128 => WireSize::fixed(16),
+            Self::VU32 | Self::VI32 | Self::VZI32 => WireSize {
+                min: 1,
+                max: Some(5),
+            },
+            Self::VU64 | Self::VI64 | Self::VZI64 => WireSize {
+                min: 1,
+                max: Some(10),
+            },
+        }
+    }
+}
+
+impl Hir {
+    /// Computes the encoded size bounds of the given type
+    pub fn wire_size(&self, ty: SymbolId) -> WireSize {
+        self.wire_size_rec(ty, &mut Vec::new())
+    }
+
+    fn wire_size_rec(&self, ty: SymbolId, visiting: &mut Vec<SymbolId>) -> WireSize {
+        match self.types.get(&ty) {
+            Some(Type::Native(native)) => native.wire_size(),
+            Some(Type::Bitfield(_)) => WireSize::fixed(1),
+            // an `T[]` array is prefixed by its u32 length
+            Some(Type::Array(ArrayType::Default(_))) => WireSize::unbounded(4),
+            // the length of an `T[field]` array is accounted for by `field`
+            Some(Type::Array(ArrayType::Field { .. })) => WireSize::unbounded(0),
+            Some(Type::Message(msg)) => {
+                if visiting.contains(&ty) {
+                    // recursive messages cannot be bounded
+                    return WireSize::unbounded(0);
                 }
+                visiting.push(ty);
+                let size = msg.fields.iter().fold(WireSize::fixed(0), |acc, field| {
+                    acc + self.wire_size_rec(field.ty, visiting)
+                });
+                visiting.pop();
+                size
             }
+            None => WireSize::unbounded(0),
         }
     }
 }
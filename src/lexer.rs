@@ -11,7 +11,12 @@ pub enum TokenKind {
     RBrace,
     LBracket,
     RBracket,
+    LParen,
+    RParen,
     At,
+    /// a double-quoted string, `\"` and `\\` being escapes
+    String,
+    Comment,
     Eof,
     Unknown,
 }
@@ -83,15 +88,15 @@ impl<'a> Lexer<'a> {
             '}' => self.token(TokenKind::RBrace),
             '[' => self.token(TokenKind::LBracket),
             ']' => self.token(TokenKind::RBracket),
+            '(' => self.token(TokenKind::LParen),
+            ')' => self.token(TokenKind::RParen),
             '@' => self.token(TokenKind::At),
             '/' if self.peek_char(0) == '/' => {
                 self.next_char(); // consume second '/'
-                while self.peek_char(0) != '\n' {
-                    self.next_char();
-                }
-                self.start = self.curr;
-                self.next_token()
+                self.advance_while(|c| c != '\n');
+                self.token(TokenKind::Comment)
             }
+            '"' => self.string(),
             c if is_id_start(c) => {
                 self.advance_while(is_id_continue);
                 self.token(TokenKind::Ident)
@@ -101,6 +106,28 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// The rest of a string after its opening quote, unterminated on the same line it is unknown
+    fn string(&mut self) -> Token {
+        loop {
+            match self.peek_char(0) {
+                '\0' | '\n' => return self.token(TokenKind::Unknown),
+                '"' => {
+                    self.next_char();
+                    return self.token(TokenKind::String);
+                }
+                '\\' => {
+                    self.next_char();
+                    if matches!(self.peek_char(0), '"' | '\\') {
+                        self.next_char();
+                    }
+                }
+                _ => {
+                    self.next_char();
+                }
+            }
+        }
+    }
+
     fn advance_while(&mut self, predicate: fn(char) -> bool) {
         loop {
             let c = self.peek_char(0);
@@ -140,6 +167,22 @@ pub struct Span {
     pub end: Pos,
 }
 
+impl Span {
+    /// Returns the span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+        }
+    }
+
+    /// Whether the byte `offset` is within this span (inclusive of the end so that a
+    /// cursor placed right after an identifier still points at it)
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start.offset <= offset && offset <= self.end.offset
+    }
+}
+
 impl std::fmt::Display for Span {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}:{}", self.start.line + 1, self.start.column + 1)
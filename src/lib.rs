@@ -6,14 +6,29 @@ mod ast;
 mod hir;
 mod symbols;
 mod generators;
+mod formatter;
+mod lsp;
+mod dynamic;
+mod compat;
+mod fingerprint;
 pub mod error;
+pub mod schema;
 
 mod __exports {
     use crate::lexer;
-    pub use lexer::{Token, Span, TokenKind};
+    pub use lexer::{Pos, Span, Token, TokenKind};
     use crate::generators;
 
     pub use generators::gen_c::*;
+    pub use generators::{Generator, Options, OutputFiles, Registry, stale_files, write_files};
+    pub use crate::compat::{Change, Compatibility, compat};
+    pub use crate::formatter::format;
+    pub use crate::lsp::run_lsp;
+    pub use crate::schema::{Schema, Source, compile};
+    pub use crate::dynamic::{
+        DiffOptions, Difference, Explanation, Location, Node, Value, decode, diff, encode, explain,
+        hexdump,
+    };
 }
 
 pub use __exports::*;
\ No newline at end of file
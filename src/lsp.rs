@@ -0,0 +1,659 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use lsp_server::{Connection, Message as LspMessage, Notification, Request, RequestId, Response};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    MarkupContent, MarkupKind, OneOf, Position, PublishDiagnosticsParams, Range, ReferenceParams,
+    ServerCapabilities, SymbolKind, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, References, Request as _,
+    },
+};
+
+use crate::{
+    ast::*,
+    hir::{Hir, Type},
+    lexer::{Pos, Span},
+    parser::parse,
+};
+
+/// Runs a language server over stdio until the client asks for a shutdown
+pub fn run_lsp() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(Default::default()),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server::default();
+    for msg in &connection.receiver {
+        match msg {
+            LspMessage::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    break;
+                }
+                let resp = server.handle_request(req);
+                connection.sender.send(LspMessage::Response(resp))?;
+            }
+            LspMessage::Notification(not) => {
+                if let Some(uri) = server.handle_notification(not) {
+                    let diagnostics = server.diagnostics(&uri);
+                    let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+                    connection
+                        .sender
+                        .send(LspMessage::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_string(),
+                            params,
+                        )))?;
+                }
+            }
+            LspMessage::Response(_) => (),
+        }
+    }
+
+    io_threads.join()?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    /// Returns the uri of the document whose diagnostics need to be published
+    fn handle_notification(&mut self, not: Notification) -> Option<Url> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(not.params).ok()?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), Document::new(params.text_document.text));
+                Some(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(not.params).ok()?;
+                // we only advertise full synchronization, the last change is the whole document
+                let change = params.content_changes.into_iter().last()?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), Document::new(change.text));
+                Some(uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(not.params).ok()?;
+                self.documents.remove(&params.text_document.uri);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_request(&self, req: Request) -> Response {
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            GotoDefinition::METHOD => self.goto_definition(req).and_then(to_value),
+            References::METHOD => self.references(req).and_then(to_value),
+            HoverRequest::METHOD => self.hover(req).and_then(to_value),
+            Completion::METHOD => self.completion(req).and_then(to_value),
+            DocumentSymbolRequest::METHOD => self.document_symbols(req).and_then(to_value),
+            method => {
+                return Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request '{method}'"),
+                );
+            }
+        };
+        match result {
+            Ok(value) => Response::new_ok(id, value),
+            Err(err) => invalid_params(id, err),
+        }
+    }
+
+    fn goto_definition(&self, req: Request) -> Result<Option<GotoDefinitionResponse>> {
+        let params: GotoDefinitionParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        let Some((doc, file)) = self.document_file(&uri) else {
+            return Ok(None);
+        };
+        let def = symbol_at(file, doc.offset(pos)).and_then(|sym| definition(file, &sym));
+        Ok(def.map(|span| {
+            GotoDefinitionResponse::Scalar(Location::new(uri.clone(), doc.range(span)))
+        }))
+    }
+
+    fn references(&self, req: Request) -> Result<Option<Vec<Location>>> {
+        let params: ReferenceParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document_position.text_document.uri;
+        let pos = params.text_document_position.position;
+        let Some((doc, file)) = self.document_file(&uri) else {
+            return Ok(None);
+        };
+        let Some(sym) = symbol_at(file, doc.offset(pos)) else {
+            return Ok(None);
+        };
+        let mut spans = references(file, &sym);
+        if params.context.include_declaration {
+            spans.extend(definition(file, &sym));
+        }
+        spans.sort_by_key(|span| span.start.offset);
+        Ok(Some(
+            spans
+                .into_iter()
+                .map(|span| Location::new(uri.clone(), doc.range(span)))
+                .collect(),
+        ))
+    }
+
+    fn hover(&self, req: Request) -> Result<Option<Hover>> {
+        let params: HoverParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document_position_params.text_document.uri;
+        let pos = params.text_document_position_params.position;
+        let Some((doc, file)) = self.document_file(&uri) else {
+            return Ok(None);
+        };
+        let Some(sym) = symbol_at(file, doc.offset(pos)) else {
+            return Ok(None);
+        };
+        Ok(hover_text(file, doc.hir.as_ref(), &sym).map(|value| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        }))
+    }
+
+    fn completion(&self, req: Request) -> Result<Option<Vec<CompletionItem>>> {
+        let params: lsp_types::CompletionParams = serde_json::from_value(req.params)?;
+        let uri = params.text_document_position.text_document.uri;
+        let mut items: Vec<_> = NATIVE_TYPES
+            .iter()
+            .map(|ty| CompletionItem {
+                label: ty.name().to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                detail: Some(format!("native, {}", ty.wire_size())),
+                ..Default::default()
+            })
+            .collect();
+        if let Some((_, file)) = self.document_file(&uri) {
+            items.extend(file.defs.iter().map(|def| match def {
+                TopLevel::Message(msg) => CompletionItem {
+                    label: msg.name.clone(),
+                    kind: Some(CompletionItemKind::STRUCT),
+                    detail: Some("message".to_string()),
+                    ..Default::default()
+                },
+                TopLevel::Bitfield(bf) => CompletionItem {
+                    label: bf.name.clone(),
+                    kind: Some(CompletionItemKind::ENUM),
+                    detail: Some("bitfield".to_string()),
+                    ..Default::default()
+                },
+            }));
+        }
+        Ok(Some(items))
+    }
+
+    #[allow(deprecated)] // `DocumentSymbol::deprecated` has to be initialized
+    fn document_symbols(&self, req: Request) -> Result<Option<DocumentSymbolResponse>> {
+        let params: DocumentSymbolParams = serde_json::from_value(req.params)?;
+        let Some((doc, file)) = self.document_file(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        let symbol = |name: &str, detail: String, kind, span, name_span, children| DocumentSymbol {
+            name: name.to_string(),
+            detail: Some(detail),
+            kind,
+            tags: None,
+            deprecated: None,
+            range: doc.range(span),
+            selection_range: doc.range(name_span),
+            children,
+        };
+        let symbols = file
+            .defs
+            .iter()
+            .map(|def| match def {
+                TopLevel::Message(msg) => {
+                    let fields = msg
+                        .fields
+                        .iter()
+                        .map(|f| {
+                            let detail = f.ty.to_string();
+                            symbol(
+                                &f.name,
+                                detail,
+                                SymbolKind::FIELD,
+                                f.span,
+                                f.name_span,
+                                None,
+                            )
+                        })
+                        .collect();
+                    let detail = "message".to_string();
+                    symbol(
+                        &msg.name,
+                        detail,
+                        SymbolKind::STRUCT,
+                        msg.span,
+                        msg.name_span,
+                        Some(fields),
+                    )
+                }
+                TopLevel::Bitfield(bf) => {
+                    let flags = bf
+                        .flags
+                        .iter()
+                        .map(|f| {
+                            let detail = format!("1 << {}", f.offset);
+                            symbol(
+                                &f.name,
+                                detail,
+                                SymbolKind::ENUM_MEMBER,
+                                f.name_span,
+                                f.name_span,
+                                None,
+                            )
+                        })
+                        .collect();
+                    let detail = "bitfield".to_string();
+                    symbol(
+                        &bf.name,
+                        detail,
+                        SymbolKind::ENUM,
+                        bf.span,
+                        bf.name_span,
+                        Some(flags),
+                    )
+                }
+            })
+            .collect();
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    fn document_file(&self, uri: &Url) -> Option<(&Document, &File)> {
+        let doc = self.documents.get(uri)?;
+        let file = doc.file.as_ref().ok()?;
+        Some((doc, file))
+    }
+
+    fn diagnostics(&self, uri: &Url) -> Vec<Diagnostic> {
+        let Some(doc) = self.documents.get(uri) else {
+            return Vec::new();
+        };
+        let error = |span, message| Diagnostic {
+            range: doc.range(span),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("binlang".to_string()),
+            message,
+            ..Default::default()
+        };
+        match &doc.file {
+            Err(err) => {
+                let span = err.span().unwrap_or_else(|| doc.end_span());
+                vec![error(span, err.to_string())]
+            }
+            Ok(_) => doc
+                .hir_errors
+                .iter()
+                .map(|err| error(err.span(), err.to_string()))
+                .collect(),
+        }
+    }
+}
+
+struct Document {
+    source: String,
+    file: Result<File, crate::error::ParseError>,
+    hir: Option<Hir>,
+    hir_errors: Vec<crate::error::HirError>,
+}
+
+impl Document {
+    fn new(source: String) -> Self {
+        let file = parse(&source);
+        let (hir, hir_errors) = match &file {
+            Ok(file) => match Hir::new(file) {
+                Ok(hir) => (Some(hir), Vec::new()),
+                Err(errors) => (None, errors),
+            },
+            Err(_) => (None, Vec::new()),
+        };
+        Self {
+            source,
+            file,
+            hir,
+            hir_errors,
+        }
+    }
+
+    fn position(&self, pos: Pos) -> Position {
+        let line_start = self.source[..pos.offset].rfind('\n').map_or(0, |i| i + 1);
+        let character = self.source[line_start..pos.offset].encode_utf16().count();
+        Position::new(pos.line, character as u32)
+    }
+
+    fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+
+    fn offset(&self, pos: Position) -> usize {
+        let mut offset = 0;
+        for (i, line) in self.source.split_inclusive('\n').enumerate() {
+            if i as u32 == pos.line {
+                let mut character = 0;
+                for c in line.chars() {
+                    if character >= pos.character as usize || c == '\n' {
+                        break;
+                    }
+                    character += c.len_utf16();
+                    offset += c.len_utf8();
+                }
+                return offset;
+            }
+            offset += line.len();
+        }
+        offset
+    }
+
+    fn end_span(&self) -> Span {
+        let offset = self.source.len();
+        let line = self.source.matches('\n').count() as u32;
+        let pos = Pos {
+            line,
+            column: 0,
+            offset,
+        };
+        Span {
+            start: pos,
+            end: pos,
+        }
+    }
+}
+
+const NATIVE_TYPES: [NativeType; 19] = [
+    NativeType::Bool,
+    NativeType::U8,
+    NativeType::U16,
+    NativeType::U32,
+    NativeType::U64,
+    NativeType::U128,
+    NativeType::I8,
+    NativeType::I16,
+    NativeType::I32,
+    NativeType::I64,
+    NativeType::I128,
+    NativeType::VU32,
+    NativeType::VU64,
+    NativeType::VI32,
+    NativeType::VI64,
+    NativeType::VZI32,
+    NativeType::VZI64,
+    NativeType::F32,
+    NativeType::F64,
+];
+
+/// A named entity of a binlang file
+#[derive(Debug, PartialEq)]
+enum SymbolRef<'a> {
+    Native(NativeType),
+    Type(&'a str),
+    Field { message: &'a str, field: &'a str },
+    Flag { bitfield: &'a str, flag: &'a str },
+}
+
+fn symbol_at(file: &File, offset: usize) -> Option<SymbolRef<'_>> {
+    for def in &file.defs {
+        match def {
+            TopLevel::Message(msg) => {
+                if msg.name_span.contains(offset) {
+                    return Some(SymbolRef::Type(&msg.name));
+                }
+                for field in &msg.fields {
+                    if field.name_span.contains(offset) {
+                        return Some(SymbolRef::Field {
+                            message: &msg.name,
+                            field: &field.name,
+                        });
+                    }
+                    if field.ty_span.contains(offset) {
+                        return Some(match type_ident(&field.ty) {
+                            TypeIdent::Native(ty) => SymbolRef::Native(*ty),
+                            TypeIdent::Custom(name) => SymbolRef::Type(name),
+                        });
+                    }
+                    if let (Some(span), TypeExpr::ArrayWithField(_, size)) =
+                        (field.size_span, &field.ty)
+                        && span.contains(offset)
+                    {
+                        return Some(SymbolRef::Field {
+                            message: &msg.name,
+                            field: size,
+                        });
+                    }
+                }
+            }
+            TopLevel::Bitfield(bf) => {
+                if bf.name_span.contains(offset) {
+                    return Some(SymbolRef::Type(&bf.name));
+                }
+                for flag in &bf.flags {
+                    if flag.name_span.contains(offset) {
+                        return Some(SymbolRef::Flag {
+                            bitfield: &bf.name,
+                            flag: &flag.name,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn definition(file: &File, sym: &SymbolRef) -> Option<Span> {
+    file.defs.iter().find_map(|def| match (def, sym) {
+        (TopLevel::Message(msg), SymbolRef::Type(name)) if msg.name == *name => Some(msg.name_span),
+        (TopLevel::Bitfield(bf), SymbolRef::Type(name)) if bf.name == *name => Some(bf.name_span),
+        (TopLevel::Message(msg), SymbolRef::Field { message, field }) if msg.name == *message => {
+            msg.fields
+                .iter()
+                .find(|f| f.name == *field)
+                .map(|f| f.name_span)
+        }
+        (TopLevel::Bitfield(bf), SymbolRef::Flag { bitfield, flag }) if bf.name == *bitfield => bf
+            .flags
+            .iter()
+            .find(|f| f.name == *flag)
+            .map(|f| f.name_span),
+        _ => None,
+    })
+}
+
+/// Returns the spans where `sym` is used, excluding its definition
+fn references(file: &File, sym: &SymbolRef) -> Vec<Span> {
+    let mut spans = Vec::new();
+    for def in &file.defs {
+        let TopLevel::Message(msg) = def else {
+            continue;
+        };
+        for field in &msg.fields {
+            match sym {
+                SymbolRef::Type(name) => {
+                    if matches!(type_ident(&field.ty), TypeIdent::Custom(ty) if ty == name) {
+                        spans.push(field.ty_span);
+                    }
+                }
+                SymbolRef::Native(native) => {
+                    if matches!(type_ident(&field.ty), TypeIdent::Native(ty) if ty == native) {
+                        spans.push(field.ty_span);
+                    }
+                }
+                SymbolRef::Field {
+                    message,
+                    field: name,
+                } if msg.name == *message => {
+                    if let (Some(span), TypeExpr::ArrayWithField(_, size)) =
+                        (field.size_span, &field.ty)
+                        && size == name
+                    {
+                        spans.push(span);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    spans
+}
+
+fn hover_text(file: &File, hir: Option<&Hir>, sym: &SymbolRef) -> Option<String> {
+    let wire_size = |name: &str| {
+        let hir = hir?;
+        let id = hir.symbols.find(name)?;
+        Some(format!("\n\nwire size: {}", hir.wire_size(id)))
+    };
+    match sym {
+        SymbolRef::Native(ty) => Some(format!(
+            "```binlang\n{}\n```\n\nwire size: {}",
+            ty.name(),
+            ty.wire_size()
+        )),
+        SymbolRef::Type(name) => {
+            let def = file.defs.iter().find_map(|def| match def {
+                TopLevel::Message(msg) if msg.name == *name => Some("message"),
+                TopLevel::Bitfield(bf) if bf.name == *name => Some("bitfield"),
+                _ => None,
+            })?;
+            Some(format!(
+                "```binlang\n{def} {name}\n```{}",
+                wire_size(name).unwrap_or_default()
+            ))
+        }
+        SymbolRef::Field { message, field } => {
+            let msg = file.defs.iter().find_map(|def| match def {
+                TopLevel::Message(msg) if msg.name == *message => Some(msg),
+                _ => None,
+            })?;
+            let f = msg.fields.iter().find(|f| f.name == *field)?;
+            let mut text = format!("```binlang\n{message}.{field}: {}\n```", f.ty);
+            if let Some(hir) = hir {
+                let hir_field = hir
+                    .symbols
+                    .find(message)
+                    .and_then(|id| match hir.types.get(&id) {
+                        Some(Type::Message(msg)) => Some(msg),
+                        _ => None,
+                    })
+                    .and_then(|msg| {
+                        msg.fields
+                            .iter()
+                            .find(|f| hir.symbols.get(f.name) == Some(*field))
+                    })?;
+                let kind = match hir.types.get(&hir_field.ty)? {
+                    Type::Message(_) => "message",
+                    Type::Bitfield(_) => "bitfield",
+                    Type::Native(_) => "native",
+                    Type::Array(_) => "array",
+                };
+                let ty = hir.symbols.get(hir_field.ty)?;
+                text.push_str(&format!("\n\nresolved type: {kind} `{ty}`"));
+                if let Some(array) = hir_field.associated {
+                    let array = hir.symbols.get(array)?;
+                    text.push_str(&format!("\n\nlength of `{array}`"));
+                }
+                text.push_str(&format!("\n\nwire size: {}", hir.wire_size(hir_field.ty)));
+            }
+            Some(text)
+        }
+        SymbolRef::Flag { bitfield, flag } => {
+            let offset = file.defs.iter().find_map(|def| match def {
+                TopLevel::Bitfield(bf) if bf.name == *bitfield => {
+                    bf.flags.iter().find(|f| f.name == *flag).map(|f| f.offset)
+                }
+                _ => None,
+            })?;
+            Some(format!(
+                "```binlang\n{bitfield}.{flag}: {offset}\n```\n\nmask: `1 << {offset}`"
+            ))
+        }
+    }
+}
+
+fn type_ident(ty: &TypeExpr) -> &TypeIdent {
+    match ty {
+        TypeExpr::Ident(ty) | TypeExpr::ArrayNoField(ty) | TypeExpr::ArrayWithField(ty, _) => ty,
+    }
+}
+
+fn to_value(value: impl serde::Serialize) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+fn invalid_params(id: RequestId, err: anyhow::Error) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::InvalidParams as i32,
+        err.to_string(),
+    )
+}
+
+#[test]
+fn navigation() {
+    let source = "message A {
+  size: vu32,
+  text: u8[size],
+  b: B,
+}
+
+message B {
+  a: A[],
+}";
+    let file = parse(source).unwrap();
+    let offset = |needle: &str, nth: usize| source.match_indices(needle).nth(nth).unwrap().0;
+
+    // `A` used in `A[]`
+    let sym = symbol_at(&file, offset("A[]", 0)).unwrap();
+    assert_eq!(sym, SymbolRef::Type("A"));
+    assert_eq!(
+        definition(&file, &sym).unwrap().start.offset,
+        offset("A", 0)
+    );
+
+    // `size` referenced by `u8[size]`
+    let sym = symbol_at(&file, offset("size", 0)).unwrap();
+    assert_eq!(
+        sym,
+        SymbolRef::Field {
+            message: "A",
+            field: "size"
+        }
+    );
+    let refs = references(&file, &sym);
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].start.offset, offset("size", 1));
+
+    // `B` is used once
+    let refs = references(&file, &SymbolRef::Type("B"));
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].start.offset, offset("B", 0));
+}
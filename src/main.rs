@@ -1,26 +1,579 @@
-use std::path::PathBuf;
+use std::{
+    io::{IsTerminal as _, Write as _},
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
-use binlang::generate_c;
+use anyhow::{Context, Result};
+use binlang::{
+    Compatibility, DiffOptions, Location, Options, Registry, Schema, Source, compat, compile,
+    decode, diff, encode, explain, format, hexdump, run_lsp, stale_files, write_files,
+};
 use clap::Parser as _;
 
 #[derive(clap::Parser)]
 struct Cli {
-    #[clap(index = 1, help = "Input binlang file")]
-    input: PathBuf,
-    #[clap(short, long, default_value = "gen/src", help = "Output directory")]
-    output: PathBuf,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Generates the code of a binlang file for one or more targets
+    Gen {
+        #[clap(index = 1, help = "Input binlang file")]
+        input: PathBuf,
+        #[clap(short, long, default_value = "gen/src", help = "Output directory")]
+        output: PathBuf,
+        #[clap(
+            short,
+            long = "target",
+            default_value = "c",
+            help = "Target language, can be repeated"
+        )]
+        targets: Vec<String>,
+        #[clap(
+            short = 'O',
+            long = "option",
+            value_parser = parse_option,
+            help = "Generator option as key=value, can be repeated"
+        )]
+        options: Vec<(String, String)>,
+        #[clap(
+            long,
+            help = "Generate a single stb-style C header embedding the runtime, same as -O single_header=true"
+        )]
+        single_header: bool,
+        #[clap(
+            long,
+            help = "Do not write anything, exit with an error if the generated files are not up to date"
+        )]
+        check: bool,
+    },
+    /// Rewrites binlang files in their canonical layout
+    Fmt {
+        #[clap(required = true, help = "Input binlang files")]
+        inputs: Vec<PathBuf>,
+        #[clap(
+            long,
+            help = "Do not write anything, exit with an error if a file is not formatted"
+        )]
+        check: bool,
+    },
+    /// Decodes a binary file using the schema of a binlang file
+    Decode {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(index = 2, help = "Binary file to decode")]
+        data: PathBuf,
+        #[clap(short, long, help = "Message to decode, defaults to the first one")]
+        root: Option<String>,
+        #[clap(short, long, value_enum, default_value_t = DecodeFormat::Json)]
+        format: DecodeFormat,
+    },
+    /// Encodes a JSON, YAML or RON document using the schema of a binlang file
+    Encode {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(index = 2, help = "Document to encode")]
+        input: PathBuf,
+        #[clap(short, long, help = "Output binary file, defaults to stdout")]
+        output: Option<PathBuf>,
+        #[clap(short, long, help = "Message to encode, defaults to the first one")]
+        root: Option<String>,
+        #[clap(
+            short,
+            long,
+            value_enum,
+            help = "Format of the document, defaults to the input file extension"
+        )]
+        format: Option<EncodeFormat>,
+    },
+    /// Prints a binary file as hex, grouped and labelled by the fields of a binlang schema
+    Hexdump {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(index = 2, help = "Binary file to dump")]
+        data: PathBuf,
+        #[clap(short, long, help = "Message to decode, defaults to the first one")]
+        root: Option<String>,
+        #[clap(long, value_enum, default_value_t = Color::Auto)]
+        color: Color,
+    },
+    /// Tells which field of a binary file covers a given byte
+    Explain {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(index = 2, help = "Binary file to inspect")]
+        data: PathBuf,
+        #[clap(
+            long,
+            value_parser = parse_offset,
+            help = "Offset of the byte, decimal or hexadecimal with a 0x prefix"
+        )]
+        offset: usize,
+        #[clap(short, long, help = "Message to decode, defaults to the first one")]
+        root: Option<String>,
+    },
+    /// Compares two binary files field by field using the schema of a binlang file
+    Bindiff {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(index = 2, help = "Old binary file")]
+        old: PathBuf,
+        #[clap(index = 3, help = "New binary file")]
+        new: PathBuf,
+        #[clap(short, long, help = "Message to decode, defaults to the first one")]
+        root: Option<String>,
+        #[clap(
+            short,
+            long = "key",
+            help = "Align the arrays of a message by one of its fields instead of by index, as Message.field"
+        )]
+        keys: Vec<String>,
+        #[clap(
+            short,
+            long,
+            help = "Leave a field out of the comparison, as Message.field, can be repeated"
+        )]
+        ignore: Vec<String>,
+    },
+    /// Checks how data encoded with one version of a schema can be read with another one
+    Compat {
+        #[clap(index = 1, help = "Old binlang file")]
+        old: PathBuf,
+        #[clap(index = 2, help = "New binlang file")]
+        new: PathBuf,
+        #[clap(
+            long,
+            value_enum,
+            default_value_t = Require::Either,
+            help = "Compatibility to enforce, exits with an error when not met"
+        )]
+        require: Require,
+    },
+    /// Prints the fingerprint of the wire layout of messages
+    Fingerprint {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(short, long, help = "Only print the fingerprint of this message")]
+        root: Option<String>,
+        #[clap(
+            long,
+            help = "Take the names of messages, fields and flags into account"
+        )]
+        names: bool,
+    },
+    /// Prints the encoded sizes and the constant field offsets of messages
+    Layout {
+        #[clap(index = 1, help = "Input binlang file")]
+        schema: PathBuf,
+        #[clap(short, long, help = "Only print the layout of this message")]
+        root: Option<String>,
+    },
+    /// Starts a language server over stdio
+    Lsp,
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Require {
+    /// The encoding must be unchanged
+    Wire,
+    /// New readers must read old data
+    Backward,
+    /// Old readers must read new data
+    Forward,
+    /// Either backward or forward compatibility
+    Either,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Color {
+    /// Only when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum DecodeFormat {
+    /// The decoded message as JSON, `u8` arrays are shown as strings when printable
+    Json,
+    /// An indented tree with the byte range of every value
+    Text,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum EncodeFormat {
+    Json,
+    Yaml,
+    Ron,
 }
 
 fn main() -> Result<()> {
     env_logger::init();
     let args = Cli::parse();
 
-    let filename = args.input.file_stem().unwrap().to_string_lossy();
-    let source = std::fs::read_to_string(&args.input)?;
-    generate_c(&filename, &source, &args.output)?;
+    match args.command {
+        Command::Gen {
+            input,
+            output,
+            targets,
+            options,
+            single_header,
+            check,
+        } => {
+            let filename = input.file_stem().unwrap().to_string_lossy();
+            let schema = load_schema(&input)?;
+            let mut options: Options = options.into_iter().collect();
+            if single_header {
+                options.insert("single_header".to_string(), "true".to_string());
+            }
+
+            let registry = Registry::with_defaults();
+            let mut stale = 0;
+            for target in &targets {
+                let Some(generator) = registry.get(target) else {
+                    let known: Vec<_> = registry.targets().collect();
+                    anyhow::bail!(
+                        "unknown target '{target}', expected one of: {}",
+                        known.join(", ")
+                    );
+                };
+                let files = generator
+                    .generate(&filename, &schema, &options)
+                    .with_context(|| format!("generating target '{target}'"))?;
+                if check {
+                    for path in stale_files(&output, &files)? {
+                        println!("{} is not up to date", output.join(path).display());
+                        stale += 1;
+                    }
+                } else {
+                    write_files(&output, &files)?;
+                }
+            }
+
+            if check {
+                if stale > 0 {
+                    anyhow::bail!("{stale} generated file(s) not up to date");
+                }
+                println!("Generated files in {output:?} are up to date");
+                return Ok(());
+            }
+            println!("Successfully generated to {output:?}");
+        }
+        Command::Fmt { inputs, check } => {
+            let mut unformatted = 0;
+            for input in &inputs {
+                let source = std::fs::read_to_string(input)?;
+                let formatted = format(&source).with_context(|| format!("{}", input.display()))?;
+                if formatted == source {
+                    continue;
+                }
+                if check {
+                    println!("{} is not formatted", input.display());
+                    unformatted += 1;
+                } else {
+                    std::fs::write(input, formatted)?;
+                    println!("Formatted {}", input.display());
+                }
+            }
+            if unformatted > 0 {
+                anyhow::bail!("{unformatted} file(s) not formatted");
+            }
+        }
+        Command::Decode {
+            schema,
+            data,
+            root,
+            format,
+        } => {
+            let schema = load_schema(&schema)?;
+            let bytes = std::fs::read(&data)?;
+
+            let root = root_message(&schema, root)?;
+            let node = decode(&schema, &root, &bytes)
+                .with_context(|| format!("decoding {}", data.display()))?;
+            match format {
+                DecodeFormat::Json => println!("{:#}", node.to_json()),
+                DecodeFormat::Text => print!("{}", node.to_text(&root)),
+            }
+        }
+        Command::Encode {
+            schema,
+            input,
+            output,
+            root,
+            format,
+        } => {
+            let schema = load_schema(&schema)?;
+            let root = root_message(&schema, root)?;
+
+            let format = match format {
+                Some(format) => format,
+                None => match input.extension().and_then(|ext| ext.to_str()) {
+                    Some("json") => EncodeFormat::Json,
+                    Some("yaml" | "yml") => EncodeFormat::Yaml,
+                    Some("ron") => EncodeFormat::Ron,
+                    _ => anyhow::bail!(
+                        "cannot infer the format of {}, use --format",
+                        input.display()
+                    ),
+                },
+            };
+            let text = std::fs::read_to_string(&input)?;
+            let document: serde_json::Value = match format {
+                EncodeFormat::Json => serde_json::from_str(&text)?,
+                EncodeFormat::Yaml => serde_yaml::from_str(&text)?,
+                EncodeFormat::Ron => ron::from_str(&text)?,
+            };
+
+            let bytes = encode(&schema, &root, &document)
+                .with_context(|| format!("encoding {}", input.display()))?;
+            match output {
+                Some(output) => std::fs::write(output, bytes)?,
+                None => std::io::stdout().write_all(&bytes)?,
+            }
+        }
+        Command::Hexdump {
+            schema,
+            data,
+            root,
+            color,
+        } => {
+            let schema = load_schema(&schema)?;
+            let root = root_message(&schema, root)?;
+            let bytes = std::fs::read(&data)?;
+
+            let color = match color {
+                Color::Auto => std::io::stdout().is_terminal(),
+                Color::Always => true,
+                Color::Never => false,
+            };
+            let decoded = decode(&schema, &root, &bytes);
+            print!("{}", hexdump(&bytes, &root, &decoded, color));
+            decoded.with_context(|| format!("decoding {}", data.display()))?;
+        }
+        Command::Explain {
+            schema,
+            data,
+            offset,
+            root,
+        } => {
+            let schema = load_schema(&schema)?;
+            let root = root_message(&schema, root)?;
+            let bytes = std::fs::read(&data)?;
+            if offset >= bytes.len() {
+                anyhow::bail!(
+                    "offset {offset:#x} is past the end of {} ({} bytes)",
+                    data.display(),
+                    bytes.len()
+                );
+            }
+
+            // a file that does not decode can still be explained up to the error
+            let node = match decode(&schema, &root, &bytes) {
+                Ok(node) => Some(node),
+                Err(err) => {
+                    eprintln!("warning: decoding {} failed: {err}", data.display());
+                    err.partial.map(|node| *node)
+                }
+            };
+            let Some(explanation) = node.and_then(|node| explain(&schema, &root, &node, offset))
+            else {
+                anyhow::bail!("no field covers offset {offset:#x}");
+            };
 
-    println!("Successfully generated to {:?}", args.output);
+            let describe = |location: &Location| {
+                let path = match location.path.as_str() {
+                    "" => root.as_str(),
+                    path => path,
+                };
+                let range = &location.range;
+                format!(
+                    "{path}: {} [{:#x}..{:#x}] ({} bytes)",
+                    location.ty,
+                    range.start,
+                    range.end,
+                    range.len()
+                )
+            };
+            println!("offset {offset:#x} ({offset})");
+            println!("field  {}", describe(&explanation.field));
+            println!("value  {}", explanation.field.value);
+            for (i, container) in explanation.containers.iter().enumerate() {
+                let label = if i == 0 { "in" } else { "" };
+                println!("{label:6} {}", describe(container));
+            }
+        }
+        Command::Bindiff {
+            schema,
+            old,
+            new,
+            root,
+            keys,
+            ignore,
+        } => {
+            let schema = load_schema(&schema)?;
+            let root = root_message(&schema, root)?;
+
+            let mut options = DiffOptions::default();
+            for key in &keys {
+                let (msg, field) = field_ref(&schema, key)?;
+                options.keys.insert(msg.to_string(), field.to_string());
+            }
+            for field in ignore {
+                field_ref(&schema, &field)?;
+                options.ignore.insert(field);
+            }
+
+            let decode_file = |path: &PathBuf| -> Result<_> {
+                let bytes = std::fs::read(path)?;
+                decode(&schema, &root, &bytes)
+                    .with_context(|| format!("decoding {}", path.display()))
+            };
+            let differences = diff(
+                &schema,
+                &root,
+                &decode_file(&old)?,
+                &decode_file(&new)?,
+                &options,
+            );
+            for difference in &differences {
+                println!("{difference}");
+            }
+            if !differences.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Compat { old, new, require } => {
+            let old = load_schema(&old)?;
+            let new = load_schema(&new)?;
+
+            let changes = compat(&old, &new);
+            for change in &changes {
+                println!("{change}");
+            }
+            let compatibility = Compatibility::of(&changes);
+            println!("overall: {compatibility}");
+
+            let met = match compatibility {
+                Compatibility::Wire => true,
+                Compatibility::Backward => matches!(require, Require::Backward | Require::Either),
+                Compatibility::Forward => matches!(require, Require::Forward | Require::Either),
+                Compatibility::Breaking => false,
+            };
+            if !met {
+                std::process::exit(1);
+            }
+        }
+        Command::Fingerprint {
+            schema,
+            root,
+            names,
+        } => {
+            let schema = load_schema(&schema)?;
+            match root {
+                Some(root) => {
+                    let Some(msg) = schema.message(&root) else {
+                        anyhow::bail!("unknown message '{root}'");
+                    };
+                    println!("{:#018x}", msg.fingerprint(names));
+                }
+                None => {
+                    for msg in schema.messages() {
+                        println!("{:#018x}  {}", msg.fingerprint(names), msg.name());
+                    }
+                }
+            }
+        }
+        Command::Layout { schema, root } => {
+            let schema = load_schema(&schema)?;
+            let messages: Vec<_> = match root {
+                Some(root) => match schema.message(&root) {
+                    Some(msg) => vec![msg],
+                    None => anyhow::bail!("unknown message '{root}'"),
+                },
+                None => schema.messages().collect(),
+            };
+
+            for (i, msg) in messages.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                let size = msg.wire_size();
+                let kind = if size.is_fixed() { "fixed" } else { "variable" };
+                println!("{}: {kind}, {size}", msg.name());
+
+                let offsets: Vec<_> = msg.fixed_offsets().collect();
+                let rows: Vec<_> = msg
+                    .fields()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let offset = match offsets.get(i) {
+                            Some((_, offset)) => format!("@{offset}"),
+                            None => "@?".to_string(),
+                        };
+                        (
+                            field.name(),
+                            field.ty().to_string(),
+                            offset,
+                            field.wire_size(),
+                        )
+                    })
+                    .collect();
+                let name_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+                let ty_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0);
+                for (name, ty, offset, size) in rows {
+                    println!("  {name:name_width$}  {ty:ty_width$}  {offset:>5}  {size}");
+                }
+            }
+        }
+        Command::Lsp => run_lsp()?,
+    }
 
     Ok(())
 }
+
+/// Reads and compiles the schema at `path`
+fn load_schema(path: &Path) -> Result<Schema> {
+    let source = std::fs::read_to_string(path).with_context(|| format!("{}", path.display()))?;
+    Ok(compile([Source::new(&path.to_string_lossy(), &source)])?)
+}
+
+/// The message named `root`, or the first message of the schema
+fn root_message(schema: &Schema, root: Option<String>) -> Result<String> {
+    match root {
+        Some(root) => Ok(root),
+        None => match schema.messages().next() {
+            Some(msg) => Ok(msg.name().to_string()),
+            None => anyhow::bail!("the schema does not define any message"),
+        },
+    }
+}
+
+/// Splits a `Message.field` reference, checking that the field exists
+fn field_ref<'a>(schema: &Schema, reference: &'a str) -> Result<(&'a str, &'a str)> {
+    let Some((msg, field)) = reference.split_once('.') else {
+        anyhow::bail!("expected Message.field, got '{reference}'");
+    };
+    match schema.message(msg) {
+        Some(message) if message.field(field).is_some() => Ok((msg, field)),
+        Some(_) => anyhow::bail!("message '{msg}' has no field '{field}'"),
+        None => anyhow::bail!("unknown message '{msg}'"),
+    }
+}
+
+fn parse_offset(s: &str) -> Result<usize, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|err| format!("invalid offset '{s}': {err}"))
+}
+
+fn parse_option(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{s}'"))?;
+    Ok((key.to_string(), value.to_string()))
+}
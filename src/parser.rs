@@ -12,17 +12,23 @@ struct Parser<'a> {
     lexer: Lexer<'a>,
     lookahead: Option<Token>,
     source: &'a str,
+    /// comments read by the lexer that are not yet attached to a node
+    comments: Vec<Token>,
+    /// span of the last consumed token
+    prev: Span,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(source: &'a str) -> Self {
-        let mut lexer = Lexer::new(source);
-        let lookahead = lexer.next();
-        Self {
-            lexer,
-            lookahead,
+        let mut parser = Self {
+            lexer: Lexer::new(source),
+            lookahead: None,
             source,
-        }
+            comments: Vec::new(),
+            prev: Span::default(),
+        };
+        parser.lookahead = parser.advance();
+        parser
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -31,13 +37,19 @@ impl<'a> Parser<'a> {
 
     fn next(&mut self) -> Option<Token> {
         let next = self.lookahead.take();
+        if let Some(tok) = &next {
+            self.prev = tok.span;
+        }
         self.lookahead = self.advance();
         next
     }
 
     fn advance(&mut self) -> Option<Token> {
-        if let Some(tok) = self.lexer.next() {
-            return Some(tok);
+        for tok in self.lexer.by_ref() {
+            if tok.kind != TokenKind::Comment {
+                return Some(tok);
+            }
+            self.comments.push(tok);
         }
         None
     }
@@ -53,10 +65,41 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Takes the pending comment that sits on the same line as the last consumed token
+    fn trailing_comment(&mut self) -> Option<String> {
+        let first = self.comments.first()?;
+        if first.span.start.line != self.prev.end.line {
+            return None;
+        }
+        let tok = self.comments.remove(0);
+        Some(self.comment_text(&tok))
+    }
+
+    /// Takes all the pending comments
+    fn leading_comments(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.comments)
+            .iter()
+            .map(|tok| self.comment_text(tok))
+            .collect()
+    }
+
+    fn comment_text(&self, tok: &Token) -> String {
+        self.slice(&tok.span).trim_end().to_string()
+    }
+
     // For debugging or error recovery
     fn slice(&self, span: &Span) -> &str {
         &self.source[span.start.offset..span.end.offset]
     }
+
+    /// Consumes the `,` separating entries, which is optional before the closing brace
+    fn expect_separator(&mut self) -> Result<(), ParseError> {
+        if self.peek().is_some_and(|t| t.kind == TokenKind::RBrace) {
+            return Ok(());
+        }
+        self.expect(TokenKind::Comma)?;
+        Ok(())
+    }
 }
 
 impl<'a> Parser<'a> {
@@ -65,12 +108,17 @@ impl<'a> Parser<'a> {
 
         while let Some(tok) = self.peek() {
             match tok.kind {
-                TokenKind::Ident => {
+                TokenKind::Ident | TokenKind::At => {
+                    let decorator = match tok.kind {
+                        TokenKind::At => Some(self.parse_decorator()?),
+                        _ => None,
+                    };
+                    let tok = *self.peek().ok_or(ParseError::Eof)?;
                     let kw = self.slice(&tok.span);
                     if kw == "message" {
-                        defs.push(TopLevel::Message(self.parse_message().unwrap()));
+                        defs.push(TopLevel::Message(self.parse_message(decorator)?));
                     } else if kw == "bitfield" {
-                        defs.push(TopLevel::Bitfield(self.parse_bitfield().unwrap()));
+                        defs.push(TopLevel::Bitfield(self.parse_bitfield(decorator)?));
                     } else {
                         return Err(ParseError::UnexpectedIdent {
                             expected: "message",
@@ -88,10 +136,14 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Ok(File { defs })
+        Ok(File {
+            defs,
+            end_comments: self.leading_comments(),
+        })
     }
 
-    fn parse_message(&mut self) -> Result<Message, ParseError> {
+    fn parse_message(&mut self, decorator: Option<Decorator>) -> Result<Message, ParseError> {
+        let leading = self.leading_comments();
         let msg_kw = self.expect(TokenKind::Ident)?;
         if self.slice(&msg_kw.span) != "message" {
             return Err(ParseError::UnexpectedToken {
@@ -100,19 +152,33 @@ impl<'a> Parser<'a> {
             });
         }
 
-        let name = self.expect_ident()?;
+        let (name, name_span) = self.expect_ident()?;
         self.expect(TokenKind::LBrace)?;
+        let comments = Comments {
+            leading,
+            trailing: self.trailing_comment(),
+        };
 
         let mut fields = Vec::new();
         while self.peek().is_some_and(|t| t.kind != TokenKind::RBrace) {
             fields.push(self.parse_field()?);
         }
 
-        self.expect(TokenKind::RBrace)?;
-        Ok(Message { name, fields })
+        let end_comments = self.leading_comments();
+        let rbrace = self.expect(TokenKind::RBrace)?;
+        Ok(Message {
+            decorator,
+            name,
+            fields,
+            span: msg_kw.span.to(rbrace.span),
+            name_span,
+            comments,
+            end_comments,
+        })
     }
 
-    fn parse_bitfield(&mut self) -> Result<Bitfield, ParseError> {
+    fn parse_bitfield(&mut self, decorator: Option<Decorator>) -> Result<Bitfield, ParseError> {
+        let leading = self.leading_comments();
         let kw = self.expect(TokenKind::Ident)?;
         if self.slice(&kw.span) != "bitfield" {
             return Err(ParseError::UnexpectedToken {
@@ -121,62 +187,101 @@ impl<'a> Parser<'a> {
             });
         }
 
-        let name = self.expect_ident()?;
+        let (name, name_span) = self.expect_ident()?;
         self.expect(TokenKind::LBrace)?;
+        let comments = Comments {
+            leading,
+            trailing: self.trailing_comment(),
+        };
 
         let mut flags = Vec::new();
         while self.peek().is_some_and(|t| t.kind != TokenKind::RBrace) {
-            let name = self.expect_ident()?;
+            let leading = self.leading_comments();
+            let (name, name_span) = self.expect_ident()?;
             self.expect(TokenKind::Colon)?;
             let num = self.expect(TokenKind::Number)?;
             let offset = match self.slice(&num.span).parse::<u8>() {
                 Ok(offset) => offset,
                 Err(_) => return Err(ParseError::InvalidNumber(num.span)),
             };
-            self.expect(TokenKind::Comma)?;
-            flags.push(BitFlag { name, offset });
+            self.expect_separator()?;
+            flags.push(BitFlag {
+                name,
+                offset,
+                name_span,
+                comments: Comments {
+                    leading,
+                    trailing: self.trailing_comment(),
+                },
+            });
         }
 
-        self.expect(TokenKind::RBrace)?;
-        Ok(Bitfield { name, flags })
+        let end_comments = self.leading_comments();
+        let rbrace = self.expect(TokenKind::RBrace)?;
+        Ok(Bitfield {
+            decorator,
+            name,
+            flags,
+            span: kw.span.to(rbrace.span),
+            name_span,
+            comments,
+            end_comments,
+        })
     }
 
     fn parse_field(&mut self) -> Result<Field, ParseError> {
+        let leading = self.leading_comments();
+        let start = self.peek().ok_or(ParseError::Eof)?.span;
         let decorator = if self.peek().is_some_and(|t| t.kind == TokenKind::At) {
-            self.next();
-            Some(self.expect_ident()?)
+            Some(self.parse_decorator()?)
         } else {
             None
         };
 
-        let name = self.expect_ident()?;
+        let (name, name_span) = self.expect_ident()?;
         self.expect(TokenKind::Colon)?;
-        let ty = self.parse_type_expr()?;
-        self.expect(TokenKind::Comma)?;
+        let ty_span = self.peek().ok_or(ParseError::Eof)?.span;
+        let (ty, size_span) = self.parse_type_expr()?;
+        let span = start.to(self.prev);
+        self.expect_separator()?;
 
         Ok(Field {
             decorator,
             name,
             ty,
+            span,
+            name_span,
+            ty_span,
+            size_span,
+            comments: Comments {
+                leading,
+                trailing: self.trailing_comment(),
+            },
         })
     }
 
-    fn parse_type_expr(&mut self) -> Result<TypeExpr, ParseError> {
+    /// Returns the type expression and the span of the associated field, if any
+    fn parse_type_expr(&mut self) -> Result<(TypeExpr, Option<Span>), ParseError> {
         let tok = self.next().ok_or(ParseError::Eof)?;
         let base = match tok.kind {
             TokenKind::Ident => match self.slice(&tok.span) {
+                "bool" => TypeIdent::Native(NativeType::Bool),
                 "u8" => TypeIdent::Native(NativeType::U8),
                 "u16" => TypeIdent::Native(NativeType::U16),
                 "u32" => TypeIdent::Native(NativeType::U32),
                 "u64" => TypeIdent::Native(NativeType::U64),
+                "u128" => TypeIdent::Native(NativeType::U128),
                 "i8" => TypeIdent::Native(NativeType::I8),
                 "i16" => TypeIdent::Native(NativeType::I16),
                 "i32" => TypeIdent::Native(NativeType::I32),
                 "i64" => TypeIdent::Native(NativeType::I64),
+                "i128" => TypeIdent::Native(NativeType::I128),
                 "vu32" => TypeIdent::Native(NativeType::VU32),
                 "vu64" => TypeIdent::Native(NativeType::VU64),
                 "vi32" => TypeIdent::Native(NativeType::VI32),
                 "vi64" => TypeIdent::Native(NativeType::VI64),
+                "vzi32" => TypeIdent::Native(NativeType::VZI32),
+                "vzi64" => TypeIdent::Native(NativeType::VZI64),
                 "f32" => TypeIdent::Native(NativeType::F32),
                 "f64" => TypeIdent::Native(NativeType::F64),
                 name => TypeIdent::Custom(name.to_string()),
@@ -194,13 +299,13 @@ impl<'a> Parser<'a> {
             match self.peek().map(|t| t.kind) {
                 Some(TokenKind::RBracket) => {
                     self.next();
-                    Ok(TypeExpr::ArrayNoField(base))
+                    Ok((TypeExpr::ArrayNoField(base), None))
                 }
                 Some(TokenKind::Ident) => {
                     let size_tok = self.next().unwrap();
                     let size = self.slice(&size_tok.span).to_string();
                     self.expect(TokenKind::RBracket)?;
-                    Ok(TypeExpr::ArrayWithField(base, size))
+                    Ok((TypeExpr::ArrayWithField(base, size), Some(size_tok.span)))
                 }
                 _ => Err(ParseError::UnexpectedToken {
                     expected: TokenKind::Ident,
@@ -211,12 +316,97 @@ impl<'a> Parser<'a> {
                 }),
             }
         } else {
-            Ok(TypeExpr::Ident(base))
+            Ok((TypeExpr::Ident(base), None))
         }
     }
 
-    fn expect_ident(&mut self) -> Result<String, ParseError> {
+    /// `@name` optionally followed by a string argument, `@name("arg")`
+    fn parse_decorator(&mut self) -> Result<Decorator, ParseError> {
+        self.expect(TokenKind::At)?;
+        let (name, _) = self.expect_ident()?;
+        let arg = if self.peek().is_some_and(|t| t.kind == TokenKind::LParen) {
+            self.next(); // consume (
+            let tok = self.expect(TokenKind::String)?;
+            self.expect(TokenKind::RParen)?;
+            Some(unescape(self.slice(&tok.span)))
+        } else {
+            None
+        };
+        Ok(Decorator { name, arg })
+    }
+
+    fn expect_ident(&mut self) -> Result<(String, Span), ParseError> {
         let tok = self.expect(TokenKind::Ident)?;
-        Ok(self.slice(&tok.span).to_string())
+        Ok((self.slice(&tok.span).to_string(), tok.span))
     }
 }
+
+/// The content of a string token, without its quotes and escapes
+fn unescape(string: &str) -> String {
+    let mut out = String::with_capacity(string.len());
+    let mut chars = string[1..string.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('"' | '\\')) => out.push(c),
+                other => out.extend(std::iter::once('\\').chain(other)),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[test]
+fn native_types() {
+    let file =
+        parse("message A { a: bool, b: u128, c: i128, d: vi32, e: vzi32, f: vzi64, }").unwrap();
+    let TopLevel::Message(msg) = &file.defs[0] else {
+        panic!("expected a message");
+    };
+    let natives: Vec<_> = msg
+        .fields
+        .iter()
+        .map(|f| match &f.ty {
+            TypeExpr::Ident(TypeIdent::Native(ty)) => *ty,
+            ty => panic!("expected a native type, got {ty:?}"),
+        })
+        .collect();
+    assert!(matches!(
+        natives[..],
+        [
+            NativeType::Bool,
+            NativeType::U128,
+            NativeType::I128,
+            NativeType::VI32,
+            NativeType::VZI32,
+            NativeType::VZI64,
+        ]
+    ));
+}
+
+#[test]
+fn decorators() {
+    let file = parse(
+        r#"@c_name("kind_t") message Kind { @c_name("a\"b") enum: u8, @skip x: u8, }
+           @packed bitfield Flags { a: 0, }"#,
+    )
+    .unwrap();
+    let (TopLevel::Message(msg), TopLevel::Bitfield(bitfield)) = (&file.defs[0], &file.defs[1])
+    else {
+        panic!("expected a message and a bitfield");
+    };
+    assert_eq!(
+        msg.decorator.as_ref().unwrap().to_string(),
+        r#"@c_name("kind_t")"#
+    );
+    let field = msg.fields[0].decorator.as_ref().unwrap();
+    assert_eq!(field.arg.as_deref(), Some(r#"a"b"#));
+    assert_eq!(field.to_string(), r#"@c_name("a\"b")"#);
+    assert_eq!(
+        msg.fields[1].decorator.as_ref().unwrap().to_string(),
+        "@skip"
+    );
+    assert_eq!(bitfield.decorator.as_ref().unwrap().name, "packed");
+    assert!(parse(r#"message A { @c_name("a) x: u8, }"#).is_err());
+}
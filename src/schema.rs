@@ -0,0 +1,483 @@
+//! Read-only view of a compiled binlang schema.
+//!
+//! This is the entry point for tools that want to build on the compiler without depending on its
+//! internal representation:
+//!
+//! ```
+//! let source = "
+//!   message Symbol {
+//!     size: vu32,
+//!     text: u8[size],
+//!   }
+//! ";
+//! let schema = binlang::compile([binlang::Source::new("symbols.bl", source)]).unwrap();
+//! let symbol = schema.message("Symbol").unwrap();
+//! let size = symbol.field("size").unwrap();
+//! assert_eq!(size.length_of(), Some("text"));
+//! ```
+
+use crate::{
+    error::{Diagnostic, Diagnostics},
+    hir::{self, ArrayType, Hir},
+    parser::parse,
+    symbols::SymbolId,
+};
+
+pub use crate::ast::NativeType;
+pub use crate::hir::WireSize;
+
+/// A named binlang source text
+#[derive(Debug, Clone, Copy)]
+pub struct Source<'a> {
+    /// name used to report diagnostics, usually a file path
+    pub name: &'a str,
+    pub text: &'a str,
+}
+
+impl<'a> Source<'a> {
+    pub fn new(name: &'a str, text: &'a str) -> Self {
+        Self { name, text }
+    }
+}
+
+/// Compiles the given sources into a single schema, definitions of all sources share the same
+/// namespace and can reference each other.
+pub fn compile<'a>(sources: impl IntoIterator<Item = Source<'a>>) -> Result<Schema, Diagnostics> {
+    let sources: Vec<_> = sources.into_iter().collect();
+    let mut files = Vec::with_capacity(sources.len());
+    let mut diagnostics = Vec::new();
+
+    for source in &sources {
+        match parse(source.text) {
+            Ok(file) => files.push(file),
+            Err(err) => diagnostics.push(Diagnostic {
+                source: source.name.to_string(),
+                span: err.span(),
+                message: err.to_string(),
+            }),
+        }
+    }
+    if !diagnostics.is_empty() {
+        return Err(Diagnostics(diagnostics));
+    }
+
+    match Hir::from_files(&files) {
+        Ok(hir) => Ok(Schema { hir }),
+        Err(errors) => Err(Diagnostics(
+            errors
+                .into_iter()
+                .map(|(i, err)| Diagnostic {
+                    source: sources[i].name.to_string(),
+                    span: Some(err.span()),
+                    message: err.to_string(),
+                })
+                .collect(),
+        )),
+    }
+}
+
+/// A successfully compiled schema
+pub struct Schema {
+    hir: Hir,
+}
+
+impl Schema {
+    pub(crate) fn hir(&self) -> &Hir {
+        &self.hir
+    }
+
+    /// All messages and bitfields, in declaration order
+    pub fn definitions(&self) -> impl Iterator<Item = Definition<'_>> {
+        self.hir
+            .defs
+            .iter()
+            .filter_map(|id| match self.hir.types.get(id)? {
+                hir::Type::Message(ty) => Some(Definition::Message(Message { hir: &self.hir, ty })),
+                hir::Type::Bitfield(ty) => {
+                    Some(Definition::Bitfield(Bitfield { hir: &self.hir, ty }))
+                }
+                _ => None,
+            })
+    }
+
+    /// All messages, in declaration order
+    pub fn messages(&self) -> impl Iterator<Item = Message<'_>> {
+        self.definitions().filter_map(|def| match def {
+            Definition::Message(msg) => Some(msg),
+            Definition::Bitfield(_) => None,
+        })
+    }
+
+    /// All bitfields, in declaration order
+    pub fn bitfields(&self) -> impl Iterator<Item = Bitfield<'_>> {
+        self.definitions().filter_map(|def| match def {
+            Definition::Bitfield(bf) => Some(bf),
+            Definition::Message(_) => None,
+        })
+    }
+
+    pub fn message(&self, name: &str) -> Option<Message<'_>> {
+        self.messages().find(|msg| msg.name() == name)
+    }
+
+    pub fn bitfield(&self, name: &str) -> Option<Bitfield<'_>> {
+        self.bitfields().find(|bf| bf.name() == name)
+    }
+}
+
+impl std::fmt::Debug for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.hir.fmt(f)
+    }
+}
+
+/// A top-level definition
+#[derive(Debug, Clone, Copy)]
+pub enum Definition<'a> {
+    Message(Message<'a>),
+    Bitfield(Bitfield<'a>),
+}
+
+/// A `message` definition
+#[derive(Clone, Copy)]
+pub struct Message<'a> {
+    hir: &'a Hir,
+    ty: &'a hir::MessageType,
+}
+
+impl<'a> Message<'a> {
+    pub fn name(&self) -> &'a str {
+        self.hir.symbols.get(self.ty.name).unwrap()
+    }
+
+    /// Fields in wire order, including the ones holding the length of an array
+    pub fn fields(&self) -> impl Iterator<Item = Field<'a>> + 'a {
+        let hir = self.hir;
+        self.ty.fields.iter().map(move |field| Field { hir, field })
+    }
+
+    pub fn field(&self, name: &str) -> Option<Field<'a>> {
+        self.fields().find(|field| field.name() == name)
+    }
+
+    /// Bounds of the encoded size of this message
+    pub fn wire_size(&self) -> WireSize {
+        self.hir.wire_size(self.ty.name)
+    }
+
+    /// Fields sitting at a constant offset from the start of the message, ie. only preceded by
+    /// fixed-size fields, along with their offset
+    pub fn fixed_offsets(&self) -> impl Iterator<Item = (Field<'a>, usize)> + 'a {
+        let mut offset = Some(0);
+        self.fields().map_while(move |field| {
+            let at = offset?;
+            let size = field.wire_size();
+            offset = size.is_fixed().then_some(at + size.min);
+            Some((field, at))
+        })
+    }
+
+    /// Stable hash of the wire layout of this message, the names of the message, its fields and
+    /// the types they use are only taken into account with `names`
+    pub fn fingerprint(&self, names: bool) -> u64 {
+        crate::fingerprint::fingerprint(*self, names)
+    }
+}
+
+impl std::fmt::Debug for Message<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Message")
+            .field("name", &self.name())
+            .field("fields", &self.fields().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A field of a message
+#[derive(Clone, Copy)]
+pub struct Field<'a> {
+    hir: &'a Hir,
+    field: &'a hir::Field,
+}
+
+impl<'a> Field<'a> {
+    pub fn name(&self) -> &'a str {
+        self.hir.symbols.get(self.field.name).unwrap()
+    }
+
+    pub fn ty(&self) -> Type<'a> {
+        Type::new(self.hir, self.field.ty)
+    }
+
+    /// Names of the decorators applied to this field (eg. `foo` for `@foo name: u8`)
+    pub fn decorators(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let hir = self.hir;
+        self.field
+            .decorator
+            .iter()
+            .map(move |decorator| hir.symbols.get(decorator.name).unwrap())
+    }
+
+    /// Name of the array field whose length is given by this field, if any
+    pub fn length_of(&self) -> Option<&'a str> {
+        self.field
+            .associated
+            .map(|id| self.hir.symbols.get(id).unwrap())
+    }
+
+    /// Bounds of the encoded size of this field
+    pub fn wire_size(&self) -> WireSize {
+        self.hir.wire_size(self.field.ty)
+    }
+}
+
+impl std::fmt::Debug for Field<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name())
+            .field("ty", &self.ty())
+            .finish()
+    }
+}
+
+/// The resolved type of a field
+#[derive(Clone, Copy)]
+pub enum Type<'a> {
+    Native(NativeType),
+    Message(Message<'a>),
+    Bitfield(Bitfield<'a>),
+    Array(Array<'a>),
+}
+
+impl<'a> Type<'a> {
+    fn new(hir: &'a Hir, id: SymbolId) -> Self {
+        match hir.types.get(&id).unwrap() {
+            hir::Type::Native(ty) => Type::Native(*ty),
+            hir::Type::Message(ty) => Type::Message(Message { hir, ty }),
+            hir::Type::Bitfield(ty) => Type::Bitfield(Bitfield { hir, ty }),
+            hir::Type::Array(ty) => Type::Array(Array { hir, ty }),
+        }
+    }
+
+    /// Bounds of the encoded size of a value of this type
+    pub fn wire_size(&self) -> WireSize {
+        match self {
+            Self::Native(ty) => ty.wire_size(),
+            Self::Message(msg) => msg.wire_size(),
+            Self::Bitfield(_) => WireSize::fixed(1),
+            Self::Array(array) => match array.length() {
+                ArrayLength::Prefixed => WireSize::unbounded(4),
+                ArrayLength::Field(_) => WireSize::unbounded(0),
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for Type<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // only name the definitions, messages can be recursive
+        match self {
+            Self::Native(ty) => f.debug_tuple("Native").field(ty).finish(),
+            Self::Message(msg) => f.debug_tuple("Message").field(&msg.name()).finish(),
+            Self::Bitfield(bf) => f.debug_tuple("Bitfield").field(&bf.name()).finish(),
+            Self::Array(array) => f.debug_tuple("Array").field(array).finish(),
+        }
+    }
+}
+
+/// Formats the type as written in a schema, eg. `u8[size]`
+impl std::fmt::Display for Type<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Native(ty) => write!(f, "{}", ty.name()),
+            Self::Message(msg) => write!(f, "{}", msg.name()),
+            Self::Bitfield(bf) => write!(f, "{}", bf.name()),
+            Self::Array(array) => match array.length() {
+                ArrayLength::Prefixed => write!(f, "{}[]", array.element()),
+                ArrayLength::Field(field) => write!(f, "{}[{field}]", array.element()),
+            },
+        }
+    }
+}
+
+/// An array of elements
+#[derive(Clone, Copy)]
+pub struct Array<'a> {
+    hir: &'a Hir,
+    ty: &'a ArrayType,
+}
+
+impl<'a> Array<'a> {
+    pub fn element(&self) -> Type<'a> {
+        match self.ty {
+            ArrayType::Default(elem_type) | ArrayType::Field { elem_type, .. } => {
+                Type::new(self.hir, *elem_type)
+            }
+        }
+    }
+
+    pub fn length(&self) -> ArrayLength<'a> {
+        match self.ty {
+            ArrayType::Default(_) => ArrayLength::Prefixed,
+            ArrayType::Field { field_name, .. } => {
+                ArrayLength::Field(self.hir.symbols.get(*field_name).unwrap())
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Array<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Array")
+            .field("element", &self.element())
+            .field("length", &self.length())
+            .finish()
+    }
+}
+
+/// Where the number of elements of an array comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayLength<'a> {
+    /// `T[]`: the elements are preceded by their count as a `u32`
+    Prefixed,
+    /// `T[field]`: the count is the value of a previous field of the same message
+    Field(&'a str),
+}
+
+/// A `bitfield` definition, encoded as a single byte
+#[derive(Clone, Copy)]
+pub struct Bitfield<'a> {
+    hir: &'a Hir,
+    ty: &'a hir::BitfieldType,
+}
+
+impl<'a> Bitfield<'a> {
+    pub fn name(&self) -> &'a str {
+        self.hir.symbols.get(self.ty.name).unwrap()
+    }
+
+    pub fn flags(&self) -> impl Iterator<Item = Flag<'a>> + 'a {
+        let hir = self.hir;
+        self.ty.flags.iter().map(move |flag| Flag {
+            name: hir.symbols.get(flag.name).unwrap(),
+            offset: flag.offset,
+        })
+    }
+}
+
+impl std::fmt::Debug for Bitfield<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bitfield")
+            .field("name", &self.name())
+            .field("flags", &self.flags().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A named bit of a bitfield
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flag<'a> {
+    name: &'a str,
+    offset: u8,
+}
+
+impl<'a> Flag<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Position of the bit, starting from the least significant one
+    pub fn offset(&self) -> u8 {
+        self.offset
+    }
+}
+
+#[test]
+fn view() {
+    let schema = compile([Source::new(
+        "greycat_abi.bl",
+        include_str!("../examples/greycat_abi.bl"),
+    )])
+    .unwrap();
+
+    let names: Vec<_> = schema.messages().map(|msg| msg.name()).collect();
+    assert_eq!(names[..3], ["Abi", "Headers", "Symbols"]);
+    assert_eq!(
+        schema.message("Headers").unwrap().wire_size(),
+        WireSize::fixed(16)
+    );
+    let offsets: Vec<_> = schema
+        .message("Types")
+        .unwrap()
+        .fixed_offsets()
+        .map(|(field, offset)| (field.name(), offset))
+        .collect();
+    assert_eq!(
+        offsets,
+        [
+            ("byte_size", 0),
+            ("nb_types", 8),
+            ("nb_attrs", 12),
+            ("types", 16)
+        ]
+    );
+
+    let types = schema.message("Types").unwrap();
+    assert_eq!(types.field("nb_types").unwrap().length_of(), Some("types"));
+    let Type::Array(array) = types.field("types").unwrap().ty() else {
+        panic!("expected an array");
+    };
+    assert_eq!(array.length(), ArrayLength::Field("nb_types"));
+    assert_eq!(
+        types.field("types").unwrap().ty().to_string(),
+        "Type[nb_types]"
+    );
+    assert!(matches!(array.element(), Type::Message(msg) if msg.name() == "Type"));
+
+    let flags: Vec<_> = schema.bitfield("TypeAttrFlags").unwrap().flags().collect();
+    assert_eq!(flags[1].name(), "mapped");
+    assert_eq!(flags[1].offset(), 1);
+}
+
+#[test]
+fn diagnostics() {
+    let errors = compile([
+        Source::new("a.bl", "message A { b: B, c: C, }"),
+        Source::new("b.bl", "message B { a: u8[nope], }"),
+    ])
+    .unwrap_err();
+
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|d| (d.source.as_str(), &d.message))
+        .collect();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, "a.bl");
+    assert!(errors[0].1.contains("'C'"));
+    assert_eq!(errors[1].0, "b.bl");
+    assert!(errors[1].1.contains("'nope'"));
+
+    // the length of an array is read before its elements
+    let errors = compile([Source::new("a.bl", "message A { data: u8[n], n: u32, }")]).unwrap_err();
+    assert!(
+        errors.0[0]
+            .message
+            .contains("length field 'n' must come before array 'data'")
+    );
+
+    // lengths are unsigned integers
+    for ty in ["f32", "bool", "i32", "vzi64", "B", "u8[]"] {
+        let source = format!("message A {{ n: {ty}, data: u8[n], }} message B {{}}");
+        let errors = compile([Source::new("a.bl", &source)]).unwrap_err();
+        assert!(
+            errors.0[0]
+                .message
+                .contains(&format!("length field 'n' has type '{ty}'")),
+            "{ty}"
+        );
+    }
+    for ty in ["u8", "u64", "u128", "vu64"] {
+        let source = format!("message A {{ n: {ty}, data: u8[n], }}");
+        assert!(compile([Source::new("a.bl", &source)]).is_ok(), "{ty}");
+    }
+}